@@ -1,8 +1,8 @@
-use std::collections::HashSet;
-use std::path::{Path, PathBuf};
-use std::process;
+use std::collections::{HashMap, HashSet};
+use std::fs::Metadata;
+use std::path::{Component, Path, PathBuf};
 
-use crossterm::style::Stylize;
+use filetime::FileTime;
 use miette::Diagnostic;
 use run_script::ScriptOptions;
 use thiserror::Error;
@@ -10,10 +10,18 @@ use tokio::fs::{self, File, OpenOptions};
 use tokio::io::{self, AsyncReadExt, AsyncWriteExt};
 use unindent::Unindent;
 
-use crate::actions::State;
+use crate::actions::{Backup, Event, ExecutorOptions, Output, State};
 use crate::config::actions::*;
-use crate::path::{PathClean, Traverser};
+use crate::config::{placeholders, Value, DEFAULT_SEPARATOR};
+use crate::path::{expand, pattern_depth, Match, PathClean, Traverser};
+use crate::repository::{Client, FetchError};
 use crate::spinner::Spinner;
+use crate::utils::diff;
+use crate::utils::prompts as helpers;
+use crate::utils::style::Stylize;
+
+/// Number of unchanged lines shown around changes in diffs of replacements.
+const DIFF_CONTEXT: usize = 3;
 
 #[derive(Debug, Diagnostic, Error)]
 pub enum ActionError {
@@ -24,143 +32,642 @@ pub enum ActionError {
     #[source]
     source: io::Error,
   },
+  #[error("Nothing to rename: '{path}' doesn't exist.")]
+  #[diagnostic(code(decaff::actions::rename))]
+  RenameMissing { path: String },
+  #[error("{message}")]
+  #[diagnostic(code(decaff::actions::patch))]
+  Patch { message: String },
+  #[error("{message}")]
+  #[diagnostic(code(decaff::actions::fetch))]
+  Fetch { message: String },
+  #[error("Command exited with code {code}.")]
+  #[diagnostic(code(decaff::actions::run))]
+  RunFailed { code: i32 },
+  #[error("Working directory '{path}' doesn't exist.")]
+  #[diagnostic(
+    code(decaff::actions::run),
+    help("The `cwd` attribute is resolved relative to the scaffolded directory.")
+  )]
+  RunCwdMissing { path: String },
+  #[error("Both '{first}' and '{second}' would be copied to '{target}'.")]
+  #[diagnostic(
+    code(decaff::actions::copy),
+    help("Flattened files must have unique names, narrow down the `from` globs.")
+  )]
+  FlattenCollision {
+    first: String,
+    second: String,
+    target: String,
+  },
+  #[error("Path '{path}' expands to '{expanded}', which is outside of the scaffolded directory.")]
+  #[diagnostic(
+    code(decaff::actions::path),
+    help(
+      "Paths of actions are relative to the scaffolded directory, so `~` and variables can't \
+       point them elsewhere."
+    )
+  )]
+  PathOutsideRoot { path: String, expanded: String },
+  #[error("Value '{name}' is `{value}`, which is not a boolean.")]
+  #[diagnostic(
+    code(decaff::actions::drop),
+    help("Conditions of `drop` expect `true` or `false`, e.g. the answer of a confirm prompt.")
+  )]
+  ConditionNotBool { name: String, value: String },
 }
 
 impl Copy {
-  pub async fn execute<P>(&self, root: P) -> miette::Result<()>
+  /// Copies matching files, skipping the ones matching `ignore` globs. If `interactive` is not set,
+  /// the [OverwritePolicy::Prompt] policy never overwrites. If `render` is set, placeholders in
+  /// copied files are replaced with values from the state.
+  pub async fn execute<P>(
+    &self,
+    root: P,
+    state: &State,
+    interactive: bool,
+    ignore: &[String],
+    backup: &Backup,
+    output: &Output,
+  ) -> miette::Result<()>
   where
     P: AsRef<Path>,
   {
-    let destination = root.as_ref().join(&self.to);
-
-    let traverser = Traverser::new(root.as_ref())
-      .ignore_dirs(true)
-      .contents_first(true)
-      .pattern(&self.from);
+    let destination = confine(root.as_ref(), &self.to)?;
 
-    println!(
+    output.info(format!(
       "⋅ Copying: {}",
-      format!("{} ╌╌ {}", &self.from, &self.to).dim()
-    );
+      format!("{} ╌╌ {}", self.from.join(" "), &self.to).dim()
+    ));
 
-    for matched in traverser.iter().flatten() {
-      let name = matched
-        .path
-        .file_name()
-        .ok_or_else(|| miette::miette!("Path should end with valid file name."))?;
+    let mut count = 0;
 
-      let target = destination.join(name).clean();
+    // Sources of flattened targets, so files with the same name don't overwrite each other.
+    let mut flattened = HashMap::new();
 
-      if !self.overwrite && target.is_file() {
-        continue;
-      }
+    for pattern in &self.from {
+      let pattern = &expand(pattern);
 
-      if let Some(parent) = target.parent() {
-        fs::create_dir_all(parent).await.map_err(|source| {
-          ActionError::Io {
-            message: format!(
-              "Failed to create directory structure for '{}'.",
-              parent.display()
-            ),
-            source,
+      let traverser = Traverser::new(root.as_ref())
+        .ignore_dirs(true)
+        .contents_first(true)
+        .pattern(pattern)
+        .max_depth(pattern_depth(pattern))
+        .exclude(self.exclude.iter().chain(ignore).cloned().collect());
+
+      for matched in traverser.iter().flatten() {
+        let relative = match matched.path.file_name() {
+          | Some(name) if self.flatten => PathBuf::from(name),
+          | _ => relative_to_base(root.as_ref(), pattern, &matched.path)?,
+        };
+
+        let target = destination.join(relative).clean();
+
+        if self.flatten {
+          match flattened.insert(target.clone(), matched.path.clone()) {
+            | Some(first) if first != matched.path => {
+              return Err(
+                ActionError::FlattenCollision {
+                  first: first.display().to_string(),
+                  second: matched.path.display().to_string(),
+                  target: target.display().to_string(),
+                }
+                .into(),
+              );
+            },
+            | _ => {},
           }
-        })?;
+        }
 
-        fs::copy(&matched.path, &target).await.map_err(|source| {
-          ActionError::Io {
-            message: format!(
-              "Failed to copy from '{}' to '{}'.",
-              matched.path.display(),
-              target.display()
-            ),
-            source,
+        if target.is_file()
+          && !should_overwrite(self.overwrite, &matched.path, &target, interactive).await?
+        {
+          output.detail(format!(
+            "└─ {} {}",
+            &target.display(),
+            "skipped (exists)".dim()
+          ));
+          continue;
+        }
+
+        backup.save(&target).await?;
+
+        if let Some(parent) = target.parent() {
+          fs::create_dir_all(parent).await.map_err(|source| {
+            ActionError::Io {
+              message: format!(
+                "Failed to create directory structure for '{}'.",
+                parent.display()
+              ),
+              source,
+            }
+          })?;
+
+          fs::copy(&matched.path, &target).await.map_err(|source| {
+            ActionError::Io {
+              message: format!(
+                "Failed to copy from '{}' to '{}'.",
+                matched.path.display(),
+                target.display()
+              ),
+              source,
+            }
+          })?;
+
+          if self.render {
+            Self::render(&target, state).await?;
           }
-        })?;
+
+          if self.preserve {
+            preserve_metadata(&matched.path, &target).await?;
+          }
+        }
+
+        output.info(format!(
+          "└─ {} ╌╌ {}",
+          &matched.path.display(),
+          &target.display()
+        ));
+
+        output.event(Event::FileWritten { path: target });
+
+        count += 1;
+      }
+    }
+
+    output.brief(format!(
+      "⋅ Copied {count} file(s): {}",
+      format!("{} ╌╌ {}", self.from.join(" "), &self.to).dim()
+    ));
+
+    Ok(())
+  }
+
+  /// Replaces placeholders of known values in the copied file. Files that aren't valid UTF-8 are
+  /// left untouched.
+  async fn render(path: &Path, state: &State) -> miette::Result<()> {
+    let bytes = fs::read(path).await.map_err(|source| {
+      ActionError::Io {
+        message: format!("Failed to read file '{}'.", path.display()),
+        source,
       }
+    })?;
+
+    let Ok(mut buffer) = String::from_utf8(bytes) else {
+      return Ok(());
+    };
+
+    let names = placeholders(&buffer).into_iter().collect::<HashSet<_>>();
+    let delimiters = ("{", "}");
 
-      println!("└─ {} ╌╌ {}", &matched.path.display(), &target.display());
+    if substitute(&mut buffer, &names, state, DEFAULT_SEPARATOR, delimiters).is_empty() {
+      return Ok(());
     }
 
+    fs::write(path, buffer).await.map_err(|source| {
+      ActionError::Io {
+        message: format!("Failed to write to the file '{}'.", path.display()),
+        source,
+      }
+    })?;
+
     Ok(())
   }
 }
 
 impl Move {
-  pub async fn execute<P>(&self, root: P) -> miette::Result<()>
+  /// Moves matching files. If `interactive` is not set, the [OverwritePolicy::Prompt] policy
+  /// never overwrites.
+  pub async fn execute<P>(
+    &self,
+    root: P,
+    interactive: bool,
+    backup: &Backup,
+    output: &Output,
+  ) -> miette::Result<()>
   where
     P: AsRef<Path>,
   {
-    let destination = root.as_ref().join(&self.to);
-
-    let traverser = Traverser::new(root.as_ref())
-      .ignore_dirs(false)
-      .contents_first(true)
-      .pattern(&self.from);
+    let destination = confine(root.as_ref(), &self.to)?;
 
-    println!(
+    output.info(format!(
       "⋅ Moving: {}",
-      format!("{} ╌╌ {}", &self.from, &self.to).dim()
-    );
+      format!("{} ╌╌ {}", self.from.join(" "), &self.to).dim()
+    ));
 
-    for matched in traverser.iter().flatten() {
-      let name = matched
-        .path
-        .file_name()
-        .ok_or_else(|| miette::miette!("Path should end with valid file name."))?;
+    let mut count = 0;
+
+    for pattern in &self.from {
+      let pattern = &expand(pattern);
+
+      // A directory given by its exact path is moved as a whole, i.e. its files are moved into a
+      // directory of the same name under the destination.
+      let directory = Some(root.as_ref().join(pattern.trim_end_matches('/')))
+        .filter(|path| !has_glob(pattern) && path.is_dir());
 
-      let target = destination.join(name).clean();
+      let walked = match &directory {
+        | Some(_) => format!("{}/**", pattern.trim_end_matches('/')),
+        | None => pattern.to_owned(),
+      };
 
-      if !self.overwrite {
-        if let Ok(true) = target.try_exists() {
+      let traverser = Traverser::new(root.as_ref())
+        .ignore_dirs(true)
+        .contents_first(true)
+        .pattern(&walked)
+        .max_depth(pattern_depth(&walked));
+
+      for matched in traverser.iter().flatten() {
+        let relative = match directory.as_deref().and_then(Path::parent) {
+          | Some(parent) => {
+            matched
+              .path
+              .strip_prefix(parent)
+              .map_err(|_| miette::miette!("Path should be inside the moved directory."))?
+              .to_path_buf()
+          },
+          | None => relative_to_base(root.as_ref(), pattern, &matched.path)?,
+        };
+
+        let target = destination.join(relative).clean();
+
+        if target.is_file()
+          && !should_overwrite(self.overwrite, &matched.path, &target, interactive).await?
+        {
+          output.detail(format!(
+            "└─ {} {}",
+            &target.display(),
+            "skipped (exists)".dim()
+          ));
           continue;
         }
-      }
 
-      if let Some(parent) = target.parent() {
-        fs::create_dir_all(parent).await.map_err(|source| {
-          ActionError::Io {
-            message: format!(
-              "Failed to create directory structure for '{}'.",
-              parent.display()
-            ),
-            source,
+        backup.save(&target).await?;
+        backup.save(&matched.path).await?;
+
+        if let Some(parent) = target.parent() {
+          fs::create_dir_all(parent).await.map_err(|source| {
+            ActionError::Io {
+              message: format!(
+                "Failed to create directory structure for '{}'.",
+                parent.display()
+              ),
+              source,
+            }
+          })?;
+
+          // Renaming usually keeps metadata as is, but we read it beforehand anyway, since the
+          // source path won't exist after renaming.
+          let metadata = if self.preserve {
+            Some(read_metadata(&matched.path).await?)
+          } else {
+            None
+          };
+
+          fs::rename(&matched.path, &target).await.map_err(|source| {
+            ActionError::Io {
+              message: format!(
+                "Failed to move from '{}' to '{}'.",
+                matched.path.display(),
+                target.display()
+              ),
+              source,
+            }
+          })?;
+
+          if let Some(metadata) = metadata {
+            apply_metadata(&metadata, &target).await?;
           }
-        })?;
+        }
 
-        fs::rename(&matched.path, &target).await.map_err(|source| {
-          ActionError::Io {
-            message: format!(
-              "Failed to move from '{}' to '{}'.",
-              matched.path.display(),
-              target.display()
-            ),
-            source,
-          }
-        })?;
+        output.info(format!(
+          "└─ {} ╌╌ {}",
+          &matched.path.display(),
+          &target.display()
+        ));
+
+        output.event(Event::FileMoved { from: matched.path, to: target });
+
+        count += 1;
+      }
+
+      if let Some(directory) = &directory {
+        remove_empty_dirs(directory).await;
+      }
+    }
+
+    output.brief(format!(
+      "⋅ Moved {count} file(s): {}",
+      format!("{} ╌╌ {}", self.from.join(" "), &self.to).dim()
+    ));
+
+    Ok(())
+  }
+}
+
+/// Expands the path and resolves it against the root, so it can't escape the root. Absolute paths
+/// are treated as relative to the root, and leading `..` components are dropped. Paths that only
+/// become absolute when expanded, e.g. `~/dist` or `$HOME/dist`, are an error instead, since they
+/// are meant to point outside the root.
+pub(crate) fn confine(root: &Path, path: &str) -> Result<PathBuf, ActionError> {
+  let expanded = expand(path);
+
+  if Path::new(&expanded).is_absolute() && !Path::new(path).is_absolute() {
+    return Err(ActionError::PathOutsideRoot { path: path.to_string(), expanded });
+  }
+
+  let relative = PathBuf::from(expanded)
+    .clean()
+    .components()
+    .filter(|component| matches!(component, Component::Normal(_)))
+    .collect::<PathBuf>();
+
+  Ok(root.join(relative))
+}
+
+/// Checks if the path is inside the root (but isn't the root itself), comparing canonicalized
+/// paths. Only the parent of the path is canonicalized, so a symlink inside the root is considered
+/// inside even if it points elsewhere, since deleting it only removes the link. Paths that can't be
+/// resolved are not inside.
+fn is_within(root: &Path, path: &Path) -> bool {
+  let (Ok(root), Some(parent), Some(name)) = (root.canonicalize(), path.parent(), path.file_name())
+  else {
+    return false;
+  };
+
+  parent
+    .canonicalize()
+    .map(|parent| parent.join(name))
+    .is_ok_and(|path| path != root && path.starts_with(root))
+}
+
+/// Checks if the pattern contains glob characters.
+fn has_glob(pattern: &str) -> bool {
+  pattern.contains(['*', '?', '[', '{'])
+}
+
+/// Removes the directory and its subdirectories if they are empty. Directories that still have
+/// files in them, e.g. ones that weren't moved, are left as is.
+async fn remove_empty_dirs(directory: &Path) {
+  let traverser = Traverser::new(directory).contents_first(true);
+
+  for matched in traverser.iter().flatten() {
+    if matched.is_dir() {
+      let _ = fs::remove_dir(&matched.path).await;
+    }
+  }
+}
+
+/// Returns the path of a matched source relative to the static (glob-free) base of the pattern, so
+/// the structure below that base is preserved under the destination.
+fn relative_to_base(root: &Path, pattern: &str, path: &Path) -> miette::Result<PathBuf> {
+  let pattern = Path::new(pattern);
+
+  let mut base = pattern
+    .components()
+    .take_while(|component| !has_glob(&component.as_os_str().to_string_lossy()))
+    .collect::<PathBuf>();
+
+  // Patterns without glob characters match a single entry, which should land right under the
+  // destination.
+  if base == pattern {
+    base.pop();
+  }
+
+  match path.strip_prefix(root.join(base)) {
+    | Ok(relative) => Ok(relative.to_path_buf()),
+    | Err(_) => {
+      path
+        .file_name()
+        .map(PathBuf::from)
+        .ok_or_else(|| miette::miette!("Path should end with valid file name."))
+    },
+  }
+}
+
+/// Reads metadata of the given path.
+async fn read_metadata(path: &Path) -> Result<Metadata, ActionError> {
+  fs::metadata(path).await.map_err(|source| {
+    ActionError::Io {
+      message: format!("Failed to read metadata of '{}'.", path.display()),
+      source,
+    }
+  })
+}
+
+/// Applies permissions (Unix only) and timestamps from the given metadata to the target.
+async fn apply_metadata(metadata: &Metadata, target: &Path) -> Result<(), ActionError> {
+  #[cfg(unix)]
+  fs::set_permissions(target, metadata.permissions())
+    .await
+    .map_err(|source| {
+      ActionError::Io {
+        message: format!("Failed to set permissions of '{}'.", target.display()),
+        source,
       }
+    })?;
+
+  let atime = FileTime::from_last_access_time(metadata);
+  let mtime = FileTime::from_last_modification_time(metadata);
+
+  filetime::set_file_times(target, atime, mtime).map_err(|source| {
+    ActionError::Io {
+      message: format!("Failed to set timestamps of '{}'.", target.display()),
+      source,
+    }
+  })
+}
 
-      println!("└─ {} ╌╌ {}", &matched.path.display(), &target.display());
+/// Substitutes the given placeholders in the text with their values from the state, if known.
+/// Line breaks inside values follow the line endings of the text. Returns names of substituted
+/// placeholders.
+fn substitute(
+  text: &mut String,
+  names: &HashSet<String>,
+  state: &State,
+  separator: &str,
+  delimiters: (&str, &str),
+) -> Vec<String> {
+  let line_ending = line_ending(text);
+  let mut substituted = Vec::new();
+
+  for name in names {
+    if let Some(value) = state.get(name) {
+      let value = value.with_line_ending(line_ending);
+
+      *text = value.substitute_delimited(text, name, separator, delimiters);
+      substituted.push(name.to_string());
     }
+  }
+
+  substituted
+}
+
+/// Detects the dominant line ending of the text: `\r\n` if most line breaks are CRLF, otherwise
+/// `\n`.
+fn line_ending(text: &str) -> &'static str {
+  let lines = text.matches('\n').count();
+  let crlf = text.matches("\r\n").count();
+
+  if crlf * 2 > lines {
+    "\r\n"
+  } else {
+    "\n"
+  }
+}
+
+/// Decides whether the existing `target` should be overwritten with the `source` according to the
+/// policy.
+async fn should_overwrite(
+  policy: OverwritePolicy,
+  source: &Path,
+  target: &Path,
+  interactive: bool,
+) -> miette::Result<bool> {
+  match policy {
+    | OverwritePolicy::Always => Ok(true),
+    | OverwritePolicy::Never => Ok(false),
+    | OverwritePolicy::IfNewer => {
+      let source = FileTime::from_last_modification_time(&read_metadata(source).await?);
+      let target = FileTime::from_last_modification_time(&read_metadata(target).await?);
+
+      Ok(source > target)
+    },
+    | OverwritePolicy::Prompt if interactive => {
+      Ok(helpers::confirm(&format!(
+        "Overwrite '{}'?",
+        target.display()
+      ))?)
+    },
+    | OverwritePolicy::Prompt => Ok(false),
+  }
+}
+
+/// Preserves permissions (Unix only) and timestamps of the source on the target.
+async fn preserve_metadata(source: &Path, target: &Path) -> Result<(), ActionError> {
+  let metadata = read_metadata(source).await?;
+  apply_metadata(&metadata, target).await
+}
+
+impl Rename {
+  pub async fn execute<P>(&self, root: P, backup: &Backup, output: &Output) -> miette::Result<()>
+  where
+    P: AsRef<Path>,
+  {
+    let source = confine(root.as_ref(), &self.from)?;
+    let target = confine(root.as_ref(), &self.to)?;
+
+    output.info(format!(
+      "⋅ Renaming: {}",
+      format!("{} ╌╌ {}", &self.from, &self.to).dim()
+    ));
+
+    if !source.exists() {
+      return Err(ActionError::RenameMissing { path: self.from.clone() }.into());
+    }
+
+    backup.save(&target).await?;
+    backup.save(&source).await?;
+
+    if let Some(parent) = target.parent() {
+      fs::create_dir_all(parent).await.map_err(|source| {
+        ActionError::Io {
+          message: format!(
+            "Failed to create directory structure for '{}'.",
+            parent.display()
+          ),
+          source,
+        }
+      })?;
+    }
+
+    fs::rename(&source, &target).await.map_err(|err| {
+      ActionError::Io {
+        message: format!(
+          "Failed to rename '{}' to '{}'.",
+          source.display(),
+          target.display()
+        ),
+        source: err,
+      }
+    })?;
+
+    output.event(Event::FileMoved { from: source, to: target });
+
+    output.brief(format!(
+      "⋅ Renamed: {}",
+      format!("{} ╌╌ {}", &self.from, &self.to).dim()
+    ));
 
     Ok(())
   }
 }
 
 impl Delete {
-  pub async fn execute<P>(&self, root: P) -> miette::Result<()>
+  /// Deletes matching entries. If `confirm` is set, lists them and asks for confirmation first.
+  pub async fn execute<P>(
+    &self,
+    root: P,
+    confirm: bool,
+    backup: &Backup,
+    output: &Output,
+  ) -> miette::Result<()>
   where
     P: AsRef<Path>,
   {
+    let pattern = expand(&self.target);
+
     let traverser = Traverser::new(root.as_ref())
       .ignore_dirs(false)
       .contents_first(false)
-      .pattern(&self.target);
+      .pattern(&pattern)
+      .max_depth(pattern_depth(&pattern));
+
+    output.info(format!("⋅ Deleting: {}", &self.target.clone().dim()));
 
-    println!("⋅ Deleting: {}", &self.target.clone().dim());
+    // Collect matches upfront, skipping entries inside matched directories, since these are deleted
+    // along with the directories anyway.
+    let mut matches: Vec<Match> = Vec::new();
 
     for matched in traverser.iter().flatten() {
+      // Never delete anything outside the root, whatever the pattern is.
+      if !is_within(root.as_ref(), &matched.path) {
+        output.warn(
+          format!(
+            "? Skipping entry outside of the root: {}",
+            matched.path.display()
+          )
+          .yellow()
+          .to_string(),
+        );
+        continue;
+      }
+
+      if !matches
+        .iter()
+        .any(|it| it.is_dir() && matched.path.starts_with(&it.path))
+      {
+        matches.push(matched);
+      }
+    }
+
+    if confirm && !matches.is_empty() {
+      for matched in &matches {
+        output.notice(format!("└─ {}", matched.path.clean().display()));
+      }
+
+      if !helpers::confirm(&format!("Delete {} entries?", matches.len()))? {
+        output.info(format!("└─ {}", "skipped (declined)".dim()));
+        return Ok(());
+      }
+    }
+
+    let mut count = 0;
+
+    for matched in matches {
       let target = &matched.path.clean();
 
+      backup.save(target).await?;
+
       if matched.is_file() {
         fs::remove_file(target).await.map_err(|source| {
           ActionError::Io {
@@ -179,101 +686,512 @@ impl Delete {
         continue;
       }
 
-      println!("└─ {}", &target.display());
+      output.info(format!("└─ {}", &target.display()));
+      output.event(Event::FileDeleted { path: target.to_owned() });
+
+      count += 1;
     }
 
+    output.brief(format!(
+      "⋅ Deleted {count} entries: {}",
+      &self.target.clone().dim()
+    ));
+
     Ok(())
   }
 }
 
-impl Echo {
-  pub async fn execute(&self, state: &State) -> miette::Result<()> {
-    let message = if self.trim {
-      self.message.trim()
-    } else {
-      &self.message
+impl Condition {
+  /// Checks if the condition holds. Values that are not set count as `false`, and values that are
+  /// set, but are not booleans, are an error.
+  pub fn holds(&self, state: &State) -> Result<bool, ActionError> {
+    let name = self.name();
+
+    let value = match state.get(name) {
+      | Some(value) => {
+        value.as_bool().ok_or_else(|| {
+          ActionError::ConditionNotBool {
+            name: name.to_string(),
+            value: value.join(DEFAULT_SEPARATOR),
+          }
+        })?
+      },
+      | None => false,
     };
 
-    let mut message = message.unindent();
-
-    if let Some(injects) = &self.injects {
-      let mut should_print_nl = false;
-
-      for inject in injects {
-        if let Some(value) = state.get(inject) {
-          message = message.replace(&format!("{{{inject}}}"), value.to_string().as_str());
-        } else {
-          println!("{}", format!("? Unknown injection: {inject}").yellow());
-          should_print_nl = true;
-        }
-      }
+    Ok(match self {
+      | Self::If(_) => value,
+      | Self::Unless(_) => !value,
+    })
+  }
+}
 
-      if should_print_nl {
-        println!();
-      }
+impl Discard {
+  /// Deletes matching entries if the condition holds, otherwise keeps them.
+  pub async fn execute<P>(
+    &self,
+    root: P,
+    state: &State,
+    confirm: bool,
+    backup: &Backup,
+    output: &Output,
+  ) -> miette::Result<()>
+  where
+    P: AsRef<Path>,
+  {
+    if !self.condition.holds(state)? {
+      output.info(format!(
+        "⋅ Keeping: {} {}",
+        &self.target.clone().dim(),
+        format!("({})", self.condition.name()).dim()
+      ));
+
+      return Ok(());
     }
 
-    Ok(println!("{message}"))
+    let delete = Delete { target: self.target.clone() };
+    delete.execute(root, confirm, backup, output).await
   }
 }
 
-impl Run {
-  pub async fn execute<P>(&self, root: P, state: &State) -> miette::Result<()>
+impl Touch {
+  /// Creates the target file along with missing parent directories, or updates its modification
+  /// time if it exists. Glob patterns only update modification times of matching files.
+  pub async fn execute<P>(&self, root: P, backup: &Backup, output: &Output) -> miette::Result<()>
   where
-    P: Into<PathBuf> + AsRef<Path>,
+    P: AsRef<Path>,
   {
-    let mut command = self.command.clone();
-    let spinner = Spinner::new();
+    let pattern = expand(&self.target);
+
+    output.info(format!("⋅ Touching: {}", &self.target.clone().dim()));
+
+    let targets = if has_glob(&pattern) {
+      Traverser::new(root.as_ref())
+        .pattern(&pattern)
+        .max_depth(pattern_depth(&pattern))
+        .iter()
+        .flatten()
+        .filter(Match::is_file)
+        .map(|matched| matched.path.clean())
+        .collect::<Vec<_>>()
+    } else {
+      vec![confine(root.as_ref(), &self.target)?]
+    };
 
-    if let Some(injects) = &self.injects {
-      let mut should_print_nl = false;
+    let now = FileTime::now();
 
-      for inject in injects {
-        if let Some(value) = state.get(inject) {
-          command = command.replace(&format!("{{{inject}}}"), value.to_string().as_str());
-        } else {
-          println!("{}", format!("? Unknown injection: {inject}").yellow());
-          should_print_nl = true;
-        }
+    for target in &targets {
+      // Only contents are backed up, so there's nothing to save for existing files.
+      if !target.exists() {
+        backup.save(target).await?;
       }
 
-      if should_print_nl {
-        println!();
+      if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).await.map_err(|source| {
+          ActionError::Io {
+            message: format!(
+              "Failed to create directory structure for '{}'.",
+              parent.display()
+            ),
+            source,
+          }
+        })?;
       }
-    }
 
-    let name = self
-      .name
-      .clone()
-      .or_else(|| {
-        let lines = command.lines().count();
+      // Doesn't truncate existing files, only creates missing ones.
+      OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(target)
+        .await
+        .map_err(|source| {
+          ActionError::Io {
+            message: format!("Failed to touch '{}'.", target.display()),
+            source,
+          }
+        })?;
 
-        if lines > 1 {
-          Some(command.trim().lines().next().unwrap().to_string() + "...")
-        } else {
-          Some(command.clone())
+      filetime::set_file_mtime(target, now).map_err(|source| {
+        ActionError::Io {
+          message: format!("Failed to update timestamps of '{}'.", target.display()),
+          source,
         }
-      })
-      .unwrap();
+      })?;
 
-    let options = ScriptOptions {
-      working_directory: Some(root.into()),
-      ..ScriptOptions::new()
-    };
+      output.info(format!("└─ {}", &target.display()));
+      output.event(Event::FileWritten { path: target.to_owned() });
+    }
 
-    spinner.set_message(format!("{}", name.clone().grey()));
+    output.brief(format!(
+      "⋅ Touched {} file(s): {}",
+      targets.len(),
+      &self.target.clone().dim()
+    ));
 
-    // Actually run the script.
-    let (code, output, err) = run_script::run_script!(command, options)
-      .map_err(|_| miette::miette!("Failed to run script."))?;
+    Ok(())
+  }
+}
 
-    let has_failed = code > 0;
+impl Fetch {
+  pub async fn execute<P>(
+    &self,
+    root: P,
+    state: &State,
+    offline: bool,
+    client: &Client,
+    backup: &Backup,
+    output: &Output,
+  ) -> miette::Result<()>
+  where
+    P: AsRef<Path>,
+  {
+    let url = state.template(&self.url, DEFAULT_SEPARATOR);
+    let to = state.template(&self.to, DEFAULT_SEPARATOR);
+    let target = confine(root.as_ref(), &to)?;
 
-    // Re-format depending on the exit code.
-    let name = if has_failed { name.red() } else { name.green() };
+    output.info(format!("⋅ Fetching: {}", format!("{url} ╌╌ {to}").dim()));
 
-    // Stopping before printing output/errors, otherwise the spinner message won't be cleared.
-    spinner.stop_with_message(format!("{name}\n",));
+    if offline {
+      return Err(
+        ActionError::Fetch {
+          message: format!("Failed to fetch '{url}': network access is disabled."),
+        }
+        .into(),
+      );
+    }
+
+    if !self.overwrite && target.is_file() {
+      output.info(format!(
+        "└─ {} {}",
+        &target.display(),
+        "skipped (exists)".dim()
+      ));
+      return Ok(());
+    }
+
+    let fetch_error = |reason: String| {
+      ActionError::Fetch {
+        message: format!("Failed to fetch '{url}': {reason}"),
+      }
+    };
+
+    // Timeouts are reported as is, since they come with a hint about `--timeout`.
+    let bytes = client.bytes(&url).await.map_err(|err| {
+      match err {
+        | FetchError::Timeout { .. } => miette::Report::from(err),
+        | FetchError::RequestFailedWithCode { code, .. } => {
+          fetch_error(format!("server responded with {code}.")).into()
+        },
+        | FetchError::RequestBodyFailed => {
+          fetch_error("couldn't read the response body.".to_string()).into()
+        },
+        | FetchError::RequestFailed => fetch_error("request failed.".to_string()).into(),
+      }
+    })?;
+
+    backup.save(&target).await?;
+
+    if let Some(parent) = target.parent() {
+      fs::create_dir_all(parent).await.map_err(|source| {
+        ActionError::Io {
+          message: format!(
+            "Failed to create directory structure for '{}'.",
+            parent.display()
+          ),
+          source,
+        }
+      })?;
+    }
+
+    fs::write(&target, &bytes).await.map_err(|source| {
+      ActionError::Io {
+        message: format!("Failed to write '{}'.", target.display()),
+        source,
+      }
+    })?;
+
+    output.info(format!("└─ {}", &target.display()));
+    output.event(Event::FileWritten { path: target });
+
+    output.brief(format!("⋅ Fetched: {}", format!("{url} ╌╌ {to}").dim()));
+
+    Ok(())
+  }
+}
+
+impl Env {
+  pub async fn execute<P>(
+    &self,
+    root: P,
+    state: &State,
+    backup: &Backup,
+    output: &Output,
+  ) -> miette::Result<()>
+  where
+    P: AsRef<Path>,
+  {
+    let target = confine(root.as_ref(), &self.target)?;
+
+    output.info(format!("⋅ Writing env: {}", &self.target.clone().dim()));
+
+    let contents = if target.is_file() {
+      fs::read_to_string(&target).await.map_err(|source| {
+        ActionError::Io {
+          message: format!("Failed to read '{}'.", target.display()),
+          source,
+        }
+      })?
+    } else {
+      String::new()
+    };
+
+    let vars = self
+      .vars
+      .iter()
+      .map(|EnvVar { key, value }| {
+        let value = state.template(value, DEFAULT_SEPARATOR);
+        let value = if self.quote { quote_env(&value) } else { value };
+
+        (key.to_owned(), value)
+      })
+      .collect::<Vec<_>>();
+
+    let (merged, updated) = merge_env(&contents, &vars);
+
+    backup.save(&target).await?;
+
+    if let Some(parent) = target.parent() {
+      fs::create_dir_all(parent).await.map_err(|source| {
+        ActionError::Io {
+          message: format!(
+            "Failed to create directory structure for '{}'.",
+            parent.display()
+          ),
+          source,
+        }
+      })?;
+    }
+
+    fs::write(&target, merged).await.map_err(|source| {
+      ActionError::Io {
+        message: format!("Failed to write '{}'.", target.display()),
+        source,
+      }
+    })?;
+
+    for (key, _) in &vars {
+      let state = if updated.contains(key) {
+        "updated"
+      } else {
+        "added"
+      };
+
+      output.info(format!("└─ {key} {}", state.dim()));
+    }
+
+    output.event(Event::FileWritten { path: target });
+
+    output.brief(format!(
+      "⋅ Wrote {} variable(s): {}",
+      vars.len(),
+      &self.target.clone().dim()
+    ));
+
+    Ok(())
+  }
+}
+
+/// Wraps the value in double quotes, escaping backslashes, quotes and newlines.
+fn quote_env(value: &str) -> String {
+  let escaped = value
+    .replace('\\', "\\\\")
+    .replace('"', "\\\"")
+    .replace('\n', "\\n");
+
+  format!("\"{escaped}\"")
+}
+
+/// Returns the key of a `KEY=value` or `export KEY=value` line, if it's an assignment.
+fn env_key(line: &str) -> Option<&str> {
+  let line = line.trim_start();
+  let line = line.strip_prefix("export ").unwrap_or(line);
+
+  if line.starts_with('#') {
+    return None;
+  }
+
+  line.split_once('=').map(|(key, _)| key.trim())
+}
+
+/// Merges variables into the dotenv contents. Existing assignments are updated in place, keeping
+/// the `export` prefix, and missing ones are appended. Unrelated lines are preserved. Returns the
+/// merged contents and keys that were updated.
+fn merge_env(contents: &str, vars: &[(String, String)]) -> (String, HashSet<String>) {
+  let mut updated = HashSet::new();
+  let mut lines = Vec::new();
+
+  for line in contents.lines() {
+    let var = env_key(line).and_then(|key| vars.iter().find(|(name, _)| name == key));
+
+    match var {
+      | Some((key, value)) => {
+        let export = if line.trim_start().starts_with("export ") {
+          "export "
+        } else {
+          ""
+        };
+
+        lines.push(format!("{export}{key}={value}"));
+        updated.insert(key.to_owned());
+      },
+      | None => lines.push(line.to_string()),
+    }
+  }
+
+  for (key, value) in vars {
+    if !updated.contains(key) {
+      lines.push(format!("{key}={value}"));
+    }
+  }
+
+  let mut merged = lines.join("\n");
+
+  if !merged.is_empty() {
+    merged.push('\n');
+  }
+
+  (merged, updated)
+}
+
+impl Echo {
+  pub async fn execute(
+    &self,
+    state: &State,
+    auto_inject: bool,
+    output: &Output,
+  ) -> miette::Result<()> {
+    let message = if self.trim {
+      self.message.trim()
+    } else {
+      &self.message
+    };
+
+    let mut message = message.unindent();
+
+    if let Some(injects) = &self.injects {
+      let mut should_print_nl = false;
+
+      for inject in injects {
+        if let Some(value) = state.get(inject) {
+          message = value.substitute(&message, inject, &self.separator);
+        } else {
+          output.warn(
+            format!("? Unknown injection: {inject}")
+              .yellow()
+              .to_string(),
+          );
+          should_print_nl = true;
+        }
+      }
+
+      if should_print_nl {
+        output.newline();
+      }
+    } else if auto_inject {
+      message = state.template(&message, &self.separator);
+    }
+
+    output.info(&message);
+    output.event(Event::Output { text: message });
+
+    Ok(())
+  }
+}
+
+impl Run {
+  /// Runs the command and returns its trimmed stdout.
+  pub async fn execute<P>(
+    &self,
+    root: P,
+    state: &State,
+    auto_inject: bool,
+    output: &Output,
+  ) -> miette::Result<String>
+  where
+    P: Into<PathBuf> + AsRef<Path>,
+  {
+    let mut command = self.command.clone();
+    let spinner = Spinner::new();
+
+    if let Some(injects) = &self.injects {
+      let mut should_print_nl = false;
+
+      for inject in injects {
+        if let Some(value) = state.get(inject) {
+          command = value.substitute(&command, inject, DEFAULT_SEPARATOR);
+        } else {
+          output.warn(
+            format!("? Unknown injection: {inject}")
+              .yellow()
+              .to_string(),
+          );
+          should_print_nl = true;
+        }
+      }
+
+      if should_print_nl {
+        output.newline();
+      }
+    } else if auto_inject {
+      command = state.template(&command, DEFAULT_SEPARATOR);
+    }
+
+    let name = self
+      .name
+      .clone()
+      .or_else(|| {
+        let lines = command.lines().count();
+
+        if lines > 1 {
+          Some(command.trim().lines().next().unwrap().to_string() + "...")
+        } else {
+          Some(command.clone())
+        }
+      })
+      .unwrap();
+
+    let root: PathBuf = root.into();
+
+    let cwd = match &self.cwd {
+      | Some(cwd) => confine(&root, cwd)?,
+      | None => root,
+    };
+
+    if !cwd.is_dir() {
+      return Err(ActionError::RunCwdMissing { path: cwd.display().to_string() }.into());
+    }
+
+    let options = ScriptOptions {
+      working_directory: Some(cwd),
+      ..ScriptOptions::new()
+    };
+
+    spinner.set_message(format!("{}", name.clone().grey()));
+
+    output.detail(format!("{}", command.clone().dim()));
+
+    // Actually run the script.
+    let (code, stdout, err) = run_script::run_script!(command, options)
+      .map_err(|_| miette::miette!("Failed to run script."))?;
+
+    let has_failed = code > 0;
+
+    // Re-format depending on the exit code.
+    let name = if has_failed { name.red() } else { name.green() };
+
+    // Stopping before printing output/errors, otherwise the spinner message won't be cleared.
+    spinner.stop_with_message(format!("{name}\n",));
 
     if has_failed {
       if !err.is_empty() {
@@ -292,30 +1210,94 @@ impl Run {
           err
         };
 
-        eprintln!("{message}");
+        output.warn(message);
       }
 
-      process::exit(code);
+      return Err(ActionError::RunFailed { code }.into());
     }
 
-    Ok(println!("{}", output.trim()))
+    let stdout = stdout.trim().to_string();
+
+    output.info(&stdout);
+    output.event(Event::Output { text: stdout.clone() });
+
+    Ok(stdout)
   }
 }
 
 impl Prompt {
-  pub async fn execute(&self, state: &mut State) -> miette::Result<()> {
-    match self {
-      | Self::Confirm(prompt) => prompt.execute(state).await,
-      | Self::Editor(prompt) => prompt.execute(state).await,
-      | Self::Input(prompt) => prompt.execute(state).await,
-      | Self::Number(prompt) => prompt.execute(state).await,
-      | Self::Select(prompt) => prompt.execute(state).await,
+  pub async fn execute(
+    &self,
+    state: &mut State,
+    options: &ExecutorOptions,
+    output: &Output,
+  ) -> miette::Result<()> {
+    if state.get(self.name()).is_some() {
+      return self.execute_preset(state, output);
+    }
+
+    if options.non_interactive {
+      return self.execute_default(state, output);
     }
+
+    let timeout = options.prompt_timeout;
+
+    let answered = match self {
+      | Self::Confirm(prompt) => prompt.execute(state, timeout).await,
+      | Self::Editor(prompt) => prompt.execute(state, timeout).await,
+      | Self::Path(prompt) => prompt.execute(state, timeout).await,
+      | Self::Input(prompt) => prompt.execute(state, timeout).await,
+      | Self::Number(prompt) => prompt.execute(state, timeout).await,
+      | Self::Select(prompt) => prompt.execute(state, timeout).await,
+    }?;
+
+    // Fall back to the default value if the prompt timed out.
+    if !answered {
+      let name = self.name();
+
+      return self.execute_default(state, output).map_err(|_| {
+        miette::miette!(
+          code = "decaff::actions::prompts::timeout",
+          help = "Provide a default value for this prompt or answer it in time.",
+          "Prompt `{name}` timed out and has no default value."
+        )
+      });
+    }
+
+    Ok(())
+  }
+}
+
+impl Set {
+  /// Evaluates the expression and stores the result in the state.
+  pub async fn execute(&self, state: &mut State, output: &Output) -> miette::Result<()> {
+    let value = state.template(&self.value, &self.separator);
+
+    output.notice(format!(
+      "{} {} {}",
+      "=".cyan(),
+      format!("{}:", self.name).dim(),
+      value
+    ));
+
+    state.set(&self.name, Value::String(value));
+
+    Ok(())
   }
 }
 
 impl Replace {
-  pub async fn execute<P>(&self, root: P, state: &State) -> miette::Result<()>
+  /// Applies replacements to matching files, skipping the ones matching `ignore` globs. If `diff`
+  /// is set, prints a unified diff of every changed file.
+  pub async fn execute<P>(
+    &self,
+    root: P,
+    state: &State,
+    diff: bool,
+    ignore: &[String],
+    backup: &Backup,
+    output: &Output,
+  ) -> miette::Result<()>
   where
     P: AsRef<Path>,
   {
@@ -325,16 +1307,17 @@ impl Replace {
     let traverser = Traverser::new(root.as_ref())
       .ignore_dirs(true)
       .contents_first(true)
-      .pattern(&pattern);
+      .pattern(&pattern)
+      .max_depth(pattern_depth(&pattern))
+      .exclude(self.exclude.iter().chain(ignore).cloned().collect());
 
     if !self.replacements.is_empty() {
       let mut performed = HashSet::new();
 
-      println!("⋅ Applying replacements:");
+      output.info("⋅ Applying replacements:");
 
       for matched in traverser.iter().flatten() {
-        let mut buffer = String::new();
-        let mut should_write = false;
+        let mut bytes = Vec::new();
 
         let mut file = File::open(&matched.path).await.map_err(|source| {
           ActionError::Io {
@@ -343,23 +1326,39 @@ impl Replace {
           }
         })?;
 
-        file.read_to_string(&mut buffer).await.map_err(|source| {
+        file.read_to_end(&mut bytes).await.map_err(|source| {
           ActionError::Io {
             message: format!("Failed to read file '{}'.", &matched.path.display()),
             source,
           }
         })?;
 
-        for replacement in &self.replacements {
-          if let Some(value) = state.get(replacement) {
-            buffer = buffer.replace(&format!("{{{replacement}}}"), value.to_string().as_str());
-            should_write = true;
+        // Files that aren't valid UTF-8, e.g. images, are left untouched.
+        let Ok(mut buffer) = String::from_utf8(bytes) else {
+          continue;
+        };
 
-            performed.insert(replacement.to_string());
-          }
+        let original = diff.then(|| buffer.clone());
+
+        let (open, close) = &self.delimiters;
+        let substituted = substitute(
+          &mut buffer,
+          &self.replacements,
+          state,
+          &self.separator,
+          (open, close),
+        );
+
+        let should_write = !substituted.is_empty();
+        performed.extend(substituted);
+
+        if let Some(original) = original.filter(|original| *original != buffer) {
+          Self::print_diff(root.as_ref(), &matched.path, &original, &buffer, output);
         }
 
         if should_write {
+          backup.save(&matched.path).await?;
+
           let mut result = OpenOptions::new()
             .write(true)
             .truncate(true)
@@ -375,15 +1374,20 @@ impl Replace {
               }
             })?;
 
-          result
-            .write_all(buffer.as_bytes())
-            .await
-            .map_err(|source| {
-              ActionError::Io {
-                message: format!("Failed to write to the file '{}'.", &matched.path.display()),
-                source,
-              }
-            })?;
+          // Tokio files don't flush on drop, so the write might still be in flight otherwise.
+          async {
+            result.write_all(buffer.as_bytes()).await?;
+            result.flush().await
+          }
+          .await
+          .map_err(|source| {
+            ActionError::Io {
+              message: format!("Failed to write to the file '{}'.", &matched.path.display()),
+              source,
+            }
+          })?;
+
+          output.event(Event::FileWritten { path: matched.path });
         }
       }
 
@@ -395,19 +1399,1045 @@ impl Replace {
           "✗".red()
         };
 
-        println!("└─ {state} {replacement}");
+        output.info(format!("└─ {state} {replacement}"));
       }
     }
 
     Ok(())
   }
+
+  /// Prints a unified diff between the original and replaced contents of the file.
+  fn print_diff(root: &Path, path: &Path, original: &str, replaced: &str, output: &Output) {
+    let path = path.strip_prefix(root).unwrap_or(path).display();
+
+    output.info(format!("--- a/{path}").red().to_string());
+    output.info(format!("+++ b/{path}").green().to_string());
+
+    for line in diff::unified(original, replaced, DIFF_CONTEXT) {
+      let line = match line.chars().next() {
+        | Some('@') => line.cyan(),
+        | Some('-') => line.red(),
+        | Some('+') => line.green(),
+        | _ => line.dim(),
+      };
+
+      output.info(line.to_string());
+    }
+  }
 }
 
 impl Unknown {
-  pub async fn execute(&self) -> miette::Result<()> {
+  pub async fn execute(&self, output: &Output) -> miette::Result<()> {
     let name = self.name.as_str().yellow();
     let message = format!("? Unknown action: {name}").yellow();
 
-    Ok(println!("{message}"))
+    output.warn(message.to_string());
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs as std_fs;
+  use std::time::Duration;
+
+  use super::*;
+  use crate::actions::Verbosity;
+  use crate::config::Value;
+  use crate::utils::temp::TempDir;
+
+  // Helpers.
+
+  /// Writes a file creating parent directories if needed.
+  fn write(path: PathBuf, contents: &str) {
+    std_fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std_fs::write(path, contents).unwrap();
+  }
+
+  fn read(path: PathBuf) -> String {
+    std_fs::read_to_string(path).unwrap()
+  }
+
+  /// Serves the body over HTTP on a random local port, answering every request the same way.
+  /// Returns the URL.
+  fn serve(body: &'static str) -> String {
+    use std::io::{Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    std::thread::spawn(move || {
+      for mut stream in listener.incoming().flatten() {
+        let _ = stream.read(&mut [0; 1024]);
+        let _ = write!(
+          stream,
+          "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+          body.len()
+        );
+      }
+    });
+
+    url
+  }
+
+  fn client() -> Client {
+    Client::new(Duration::from_secs(5)).unwrap()
+  }
+
+  fn replace(glob: &str, replacements: &[&str]) -> Replace {
+    Replace {
+      replacements: replacements.iter().map(|it| it.to_string()).collect(),
+      glob: Some(glob.to_string()),
+      exclude: None,
+      separator: DEFAULT_SEPARATOR.to_string(),
+      delimiters: ("{".to_string(), "}".to_string()),
+    }
+  }
+
+  // Tests.
+
+  #[tokio::test]
+  async fn move_tree_without_overwrite() {
+    let root = TempDir::new("move-without-overwrite");
+
+    write(root.join("src/a.txt"), "new a");
+    write(root.join("src/nested/b.txt"), "new b");
+    write(root.join("dist/a.txt"), "old a");
+
+    let action = Move {
+      from: vec!["src/**/*".to_string()],
+      to: "dist".to_string(),
+      overwrite: OverwritePolicy::Never,
+      preserve: true,
+    };
+
+    action
+      .execute(
+        &root,
+        false,
+        &Backup::disabled(),
+        &Output::capture(Verbosity::Quiet),
+      )
+      .await
+      .unwrap();
+
+    // Existing target is left intact and its source is not moved.
+    assert_eq!(read(root.join("dist/a.txt")), "old a");
+    assert_eq!(read(root.join("src/a.txt")), "new a");
+
+    // Non-existing target is moved, preserving the structure.
+    assert_eq!(read(root.join("dist/nested/b.txt")), "new b");
+    assert!(!root.join("src/nested/b.txt").exists());
+
+    // Directories themselves are not moved.
+    assert!(root.join("src/nested").is_dir());
+  }
+
+  #[tokio::test]
+  async fn move_directory() {
+    let root = TempDir::new("move-directory");
+
+    write(root.join("src/a.txt"), "a");
+    write(root.join("src/nested/b.txt"), "b");
+    write(root.join("out/src/old.txt"), "old");
+
+    let action = Move {
+      from: vec!["src".to_string()],
+      to: "out".to_string(),
+      overwrite: OverwritePolicy::Always,
+      preserve: true,
+    };
+
+    action
+      .execute(
+        &root,
+        false,
+        &Backup::disabled(),
+        &Output::capture(Verbosity::Quiet),
+      )
+      .await
+      .unwrap();
+
+    // The directory is moved under the destination, merging with the existing one.
+    assert_eq!(read(root.join("out/src/a.txt")), "a");
+    assert_eq!(read(root.join("out/src/nested/b.txt")), "b");
+    assert_eq!(read(root.join("out/src/old.txt")), "old");
+    assert!(!root.join("src").exists());
+  }
+
+  #[tokio::test]
+  async fn move_is_reported_as_moved() {
+    let root = TempDir::new("move-reported");
+
+    write(root.join("src/a.txt"), "a");
+    write(root.join("src/b.txt"), "b");
+
+    let output = Output::capture(Verbosity::Quiet);
+
+    Move {
+      from: vec!["src/*.txt".to_string()],
+      to: "dist".to_string(),
+      overwrite: OverwritePolicy::Always,
+      preserve: true,
+    }
+    .execute(&root, false, &Backup::disabled(), &output)
+    .await
+    .unwrap();
+
+    let report = output.report();
+
+    assert_eq!(report.files_moved, 2);
+    assert_eq!(report.files_written, 0);
+    assert_eq!(report.files_deleted, 0);
+  }
+
+  #[tokio::test]
+  async fn move_tree_with_overwrite() {
+    let root = TempDir::new("move-with-overwrite");
+
+    write(root.join("src/a.txt"), "new a");
+    write(root.join("src/nested/b.txt"), "new b");
+    write(root.join("dist/a.txt"), "old a");
+
+    let action = Move {
+      from: vec!["src/**/*".to_string()],
+      to: "dist".to_string(),
+      overwrite: OverwritePolicy::Always,
+      preserve: true,
+    };
+
+    action
+      .execute(
+        &root,
+        false,
+        &Backup::disabled(),
+        &Output::capture(Verbosity::Quiet),
+      )
+      .await
+      .unwrap();
+
+    assert_eq!(read(root.join("dist/a.txt")), "new a");
+    assert_eq!(read(root.join("dist/nested/b.txt")), "new b");
+    assert!(!root.join("src/a.txt").exists());
+    assert!(!root.join("src/nested/b.txt").exists());
+  }
+
+  #[tokio::test]
+  async fn copy_preserves_metadata() {
+    let root = TempDir::new("copy-preserves-metadata");
+    let source = root.join("src/script.sh");
+    let mtime = FileTime::from_unix_time(1_000_000_000, 0);
+
+    write(source.clone(), "#!/bin/sh");
+    filetime::set_file_mtime(&source, mtime).unwrap();
+
+    #[cfg(unix)]
+    {
+      use std::os::unix::fs::PermissionsExt;
+      std_fs::set_permissions(&source, std_fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    let action = Copy {
+      from: vec!["src/*.sh".to_string()],
+      to: "dist".to_string(),
+      overwrite: OverwritePolicy::Always,
+      preserve: true,
+      exclude: None,
+      render: false,
+      flatten: false,
+    };
+
+    action
+      .execute(
+        &root,
+        &State::new(),
+        false,
+        &[],
+        &Backup::disabled(),
+        &Output::capture(Verbosity::Quiet),
+      )
+      .await
+      .unwrap();
+
+    let metadata = std_fs::metadata(root.join("dist/script.sh")).unwrap();
+
+    assert_eq!(FileTime::from_last_modification_time(&metadata), mtime);
+
+    #[cfg(unix)]
+    {
+      use std::os::unix::fs::PermissionsExt;
+      assert_eq!(metadata.permissions().mode() & 0o777, 0o755);
+    }
+  }
+
+  #[tokio::test]
+  async fn copy_multiple_patterns() {
+    let root = TempDir::new("copy-multiple-patterns");
+
+    write(root.join("README.md"), "readme");
+    write(root.join("LICENSE"), "license");
+    write(root.join("docs/guide/intro.md"), "intro");
+
+    let action = Copy {
+      from: vec![
+        "*.md".to_string(),
+        "LICENSE".to_string(),
+        "docs/**".to_string(),
+      ],
+      to: "dist".to_string(),
+      overwrite: OverwritePolicy::Always,
+      preserve: true,
+      exclude: None,
+      render: false,
+      flatten: false,
+    };
+
+    action
+      .execute(
+        &root,
+        &State::new(),
+        false,
+        &[],
+        &Backup::disabled(),
+        &Output::capture(Verbosity::Quiet),
+      )
+      .await
+      .unwrap();
+
+    assert_eq!(read(root.join("dist/README.md")), "readme");
+    assert_eq!(read(root.join("dist/LICENSE")), "license");
+    assert_eq!(read(root.join("dist/guide/intro.md")), "intro");
+  }
+
+  #[tokio::test]
+  async fn copy_nested_sources() {
+    let root = TempDir::new("copy-nested");
+
+    write(root.join("src/main.rs"), "main");
+    write(root.join("src/cli/args.rs"), "args");
+    write(root.join("src/cli/parse/mod.rs"), "parse");
+
+    for (to, flatten) in [("tree", false), ("flat", true)] {
+      let action = Copy {
+        from: vec!["src/**/*.rs".to_string()],
+        to: to.to_string(),
+        overwrite: OverwritePolicy::Always,
+        preserve: false,
+        exclude: None,
+        render: false,
+        flatten,
+      };
+
+      action
+        .execute(
+          &root,
+          &State::new(),
+          false,
+          &[],
+          &Backup::disabled(),
+          &Output::capture(Verbosity::Quiet),
+        )
+        .await
+        .unwrap();
+    }
+
+    assert_eq!(read(root.join("tree/main.rs")), "main");
+    assert_eq!(read(root.join("tree/cli/args.rs")), "args");
+    assert_eq!(read(root.join("tree/cli/parse/mod.rs")), "parse");
+
+    assert_eq!(read(root.join("flat/main.rs")), "main");
+    assert_eq!(read(root.join("flat/args.rs")), "args");
+    assert_eq!(read(root.join("flat/mod.rs")), "parse");
+    assert!(!root.join("flat/cli").exists());
+  }
+
+  #[tokio::test]
+  async fn copy_flatten_name_collision() {
+    let root = TempDir::new("copy-flatten-collision");
+
+    write(root.join("src/a/mod.rs"), "a");
+    write(root.join("src/b/mod.rs"), "b");
+
+    let result = Copy {
+      from: vec!["src/**/*.rs".to_string()],
+      to: "flat".to_string(),
+      overwrite: OverwritePolicy::Always,
+      preserve: false,
+      exclude: None,
+      render: false,
+      flatten: true,
+    }
+    .execute(
+      &root,
+      &State::new(),
+      false,
+      &[],
+      &Backup::disabled(),
+      &Output::capture(Verbosity::Quiet),
+    )
+    .await;
+
+    assert!(result.is_err());
+  }
+
+  #[tokio::test]
+  async fn copy_renders_placeholders() {
+    let root = TempDir::new("copy-render");
+
+    write(
+      root.join("src/app.toml"),
+      "name = \"{name}\"\r\nkeep = \"{unknown}\"\r\n",
+    );
+
+    let mut state = State::new();
+    state.set("name", Value::String("decaff".to_string()));
+
+    for (to, render) in [("raw", false), ("rendered", true)] {
+      let action = Copy {
+        from: vec!["src/*.toml".to_string()],
+        to: to.to_string(),
+        overwrite: OverwritePolicy::Always,
+        preserve: false,
+        exclude: None,
+        render,
+        flatten: false,
+      };
+
+      action
+        .execute(
+          &root,
+          &state,
+          false,
+          &[],
+          &Backup::disabled(),
+          &Output::capture(Verbosity::Quiet),
+        )
+        .await
+        .unwrap();
+    }
+
+    assert_eq!(
+      read(root.join("raw/app.toml")),
+      "name = \"{name}\"\r\nkeep = \"{unknown}\"\r\n"
+    );
+    assert_eq!(
+      read(root.join("rendered/app.toml")),
+      "name = \"decaff\"\r\nkeep = \"{unknown}\"\r\n"
+    );
+  }
+
+  #[test]
+  #[cfg(unix)]
+  fn confine_paths_to_root() {
+    let root = Path::new("/tmp/scaffold");
+
+    let cases = [
+      ("dist", "/tmp/scaffold/dist"),
+      ("./dist/../out", "/tmp/scaffold/out"),
+      (".", "/tmp/scaffold"),
+      ("/etc/passwd", "/tmp/scaffold/etc/passwd"),
+      ("../../etc", "/tmp/scaffold/etc"),
+    ];
+
+    for (path, expected) in cases {
+      assert_eq!(confine(root, path).unwrap(), PathBuf::from(expected));
+    }
+
+    // Relative paths are fine however they are expanded, but `~` and variables can't point outside.
+    assert_eq!(
+      confine(root, "${DECAFF_TEST_UNSET:-dist}").unwrap(),
+      PathBuf::from("/tmp/scaffold/dist")
+    );
+    assert!(confine(root, "~/dist").is_err());
+    assert!(confine(root, "${DECAFF_TEST_UNSET:-/etc}").is_err());
+  }
+
+  #[tokio::test]
+  #[cfg(unix)]
+  async fn fetch_target_stays_in_root() {
+    let root = TempDir::new("fetch-confined");
+    let outside = std::env::temp_dir().join(format!("decaff-test-fetched-{}", std::process::id()));
+    let url = serve("fetched");
+
+    let targets = [
+      format!("../decaff-test-fetched-{}", std::process::id()),
+      outside.display().to_string(),
+    ];
+
+    for to in targets {
+      Fetch { url: url.clone(), to, overwrite: true }
+        .execute(
+          &root,
+          &State::new(),
+          false,
+          &client(),
+          &Backup::disabled(),
+          &Output::capture(Verbosity::Quiet),
+        )
+        .await
+        .unwrap();
+    }
+
+    let name = outside.file_name().unwrap();
+
+    assert!(!outside.exists());
+    assert_eq!(read(root.join(name)), "fetched");
+    assert_eq!(
+      read(confine(&root, &outside.display().to_string()).unwrap()),
+      "fetched"
+    );
+  }
+
+  #[tokio::test]
+  async fn fetch_times_out() {
+    let root = TempDir::new("fetch-timeout");
+
+    // The connection is accepted by the OS, but no response ever comes.
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    let err = Fetch {
+      url,
+      to: "fetched.txt".to_string(),
+      overwrite: true,
+    }
+    .execute(
+      &root,
+      &State::new(),
+      false,
+      &Client::new(Duration::from_millis(200)).unwrap(),
+      &Backup::disabled(),
+      &Output::capture(Verbosity::Quiet),
+    )
+    .await
+    .unwrap_err();
+
+    assert!(err.to_string().contains("timed out"));
+    assert!(!root.join("fetched.txt").exists());
+
+    drop(listener);
+  }
+
+  #[tokio::test]
+  #[cfg(unix)]
+  async fn copy_absolute_target_stays_in_root() {
+    let root = TempDir::new("copy-absolute-target");
+    let outside = std::env::temp_dir().join(format!("decaff-test-outside-{}", std::process::id()));
+
+    write(root.join("src/file.txt"), "contents");
+
+    let action = Copy {
+      from: vec!["src/file.txt".to_string()],
+      to: outside.display().to_string(),
+      overwrite: OverwritePolicy::Always,
+      preserve: true,
+      exclude: None,
+      render: false,
+      flatten: false,
+    };
+
+    action
+      .execute(
+        &root,
+        &State::new(),
+        false,
+        &[],
+        &Backup::disabled(),
+        &Output::capture(Verbosity::Quiet),
+      )
+      .await
+      .unwrap();
+
+    let confined = root.join(outside.strip_prefix("/").unwrap());
+
+    assert!(!outside.exists());
+    assert_eq!(read(confined.join("file.txt")), "contents");
+  }
+
+  #[tokio::test]
+  async fn delete_stays_within_root() {
+    let base = TempDir::new("delete-within-root");
+    let root = base.join("root");
+
+    write(root.join("keep/file.txt"), "inside");
+    write(base.join("outside.txt"), "outside");
+
+    for target in ["../*", "../**", "keep/../../*"] {
+      let action = Delete { target: target.to_string() };
+
+      action
+        .execute(
+          &root,
+          false,
+          &Backup::disabled(),
+          &Output::capture(Verbosity::Quiet),
+        )
+        .await
+        .unwrap();
+    }
+
+    assert_eq!(read(base.join("outside.txt")), "outside");
+    assert_eq!(read(root.join("keep/file.txt")), "inside");
+
+    assert!(is_within(&root, &root.join("keep/file.txt")));
+    assert!(is_within(&root, &root.join("keep/../keep")));
+    assert!(!is_within(&root, &root.join("../outside.txt")));
+    assert!(!is_within(&root, &base.join("outside.txt")));
+    assert!(!is_within(&root, &root));
+  }
+
+  #[tokio::test]
+  async fn drop_depends_on_condition() {
+    let root = TempDir::new("drop-condition");
+
+    write(root.join("docker/Dockerfile"), "FROM scratch");
+    write(root.join("ci/workflow.yml"), "on: push");
+
+    let mut state = State::new();
+    state.set("use_docker", Value::Bool(true));
+    state.set("use_ci", Value::String("false".to_string()));
+
+    let actions = [
+      Discard {
+        target: "docker".to_string(),
+        condition: Condition::Unless("use_docker".to_string()),
+      },
+      Discard {
+        target: "ci".to_string(),
+        condition: Condition::Unless("use_ci".to_string()),
+      },
+      Discard {
+        target: "missing".to_string(),
+        condition: Condition::If("unset".to_string()),
+      },
+    ];
+
+    for action in actions {
+      action
+        .execute(
+          &root,
+          &state,
+          false,
+          &Backup::disabled(),
+          &Output::capture(Verbosity::Quiet),
+        )
+        .await
+        .unwrap();
+    }
+
+    assert!(root.join("docker/Dockerfile").exists());
+    assert!(!root.join("ci").exists());
+
+    // A value that isn't a boolean, e.g. a typo'd answer, fails instead of deleting anything.
+    state.set("use_docker", Value::String("yes".to_string()));
+
+    let result = Discard {
+      target: "docker".to_string(),
+      condition: Condition::Unless("use_docker".to_string()),
+    }
+    .execute(
+      &root,
+      &state,
+      false,
+      &Backup::disabled(),
+      &Output::capture(Verbosity::Quiet),
+    )
+    .await;
+
+    assert!(result.is_err());
+    assert!(root.join("docker/Dockerfile").exists());
+  }
+
+  #[tokio::test]
+  async fn set_derives_values() {
+    let mut state = State::new();
+    state.set("NAME", Value::String("My Cool App".to_string()));
+
+    let set = |name: &str, value: &str| {
+      Set {
+        name: name.to_string(),
+        value: value.to_string(),
+        separator: DEFAULT_SEPARATOR.to_string(),
+      }
+    };
+
+    let output = Output::capture(Verbosity::Quiet);
+
+    set("SLUG", "{NAME:kebab}")
+      .execute(&mut state, &output)
+      .await
+      .unwrap();
+    set("CRATE", "{SLUG:snake}_core")
+      .execute(&mut state, &output)
+      .await
+      .unwrap();
+
+    assert_eq!(state.get_string("SLUG").as_deref(), Some("my-cool-app"));
+    assert_eq!(
+      state.get_string("CRATE").as_deref(),
+      Some("my_cool_app_core")
+    );
+  }
+
+  #[tokio::test]
+  async fn copy_if_newer() {
+    let root = TempDir::new("copy-if-newer");
+
+    write(root.join("src/stale.txt"), "new stale");
+    write(root.join("src/fresh.txt"), "new fresh");
+    write(root.join("dist/stale.txt"), "old stale");
+    write(root.join("dist/fresh.txt"), "old fresh");
+
+    let older = FileTime::from_unix_time(1_000_000_000, 0);
+    let newer = FileTime::from_unix_time(2_000_000_000, 0);
+
+    filetime::set_file_mtime(root.join("src/stale.txt"), newer).unwrap();
+    filetime::set_file_mtime(root.join("dist/stale.txt"), older).unwrap();
+    filetime::set_file_mtime(root.join("src/fresh.txt"), older).unwrap();
+    filetime::set_file_mtime(root.join("dist/fresh.txt"), newer).unwrap();
+
+    let action = Copy {
+      from: vec!["src/*".to_string()],
+      to: "dist".to_string(),
+      overwrite: OverwritePolicy::IfNewer,
+      preserve: true,
+      exclude: None,
+      render: false,
+      flatten: false,
+    };
+
+    action
+      .execute(
+        &root,
+        &State::new(),
+        false,
+        &[],
+        &Backup::disabled(),
+        &Output::capture(Verbosity::Quiet),
+      )
+      .await
+      .unwrap();
+
+    assert_eq!(read(root.join("dist/stale.txt")), "new stale");
+    assert_eq!(read(root.join("dist/fresh.txt")), "old fresh");
+  }
+
+  #[tokio::test]
+  async fn rename_single_file() {
+    let root = TempDir::new("rename-single-file");
+
+    write(root.join("gitignore"), "target");
+
+    let action = Rename {
+      from: "gitignore".to_string(),
+      to: "nested/.gitignore".to_string(),
+    };
+
+    action
+      .execute(
+        &root,
+        &Backup::disabled(),
+        &Output::capture(Verbosity::Quiet),
+      )
+      .await
+      .unwrap();
+
+    assert_eq!(read(root.join("nested/.gitignore")), "target");
+    assert!(!root.join("gitignore").exists());
+  }
+
+  #[tokio::test]
+  #[cfg(unix)]
+  async fn rename_stays_in_root() {
+    let root = TempDir::new("rename-confined");
+    let outside = std::env::temp_dir().join(format!("decaff-test-renamed-{}", std::process::id()));
+
+    write(root.join("gitignore"), "target");
+
+    let action = Rename {
+      from: "../../gitignore/../gitignore".to_string(),
+      to: outside.display().to_string(),
+    };
+
+    action
+      .execute(
+        &root,
+        &Backup::disabled(),
+        &Output::capture(Verbosity::Quiet),
+      )
+      .await
+      .unwrap();
+
+    assert!(!outside.exists());
+    assert_eq!(
+      read(root.join(outside.strip_prefix("/").unwrap())),
+      "target"
+    );
+  }
+
+  #[tokio::test]
+  async fn rename_missing_source() {
+    let root = TempDir::new("rename-missing-source");
+
+    let action = Rename {
+      from: "gitignore".to_string(),
+      to: ".gitignore".to_string(),
+    };
+
+    let result = action
+      .execute(
+        &root,
+        &Backup::disabled(),
+        &Output::capture(Verbosity::Quiet),
+      )
+      .await;
+
+    assert!(result.is_err());
+    assert!(!root.join(".gitignore").exists());
+  }
+
+  #[tokio::test]
+  async fn touch_creates_and_updates_files() {
+    let root = TempDir::new("touch");
+    let older = FileTime::from_unix_time(1_000_000_000, 0);
+
+    write(root.join("CHANGELOG.md"), "keep me");
+    write(root.join("src/lib.rs"), "");
+    filetime::set_file_mtime(root.join("CHANGELOG.md"), older).unwrap();
+    filetime::set_file_mtime(root.join("src/lib.rs"), older).unwrap();
+
+    let output = Output::capture(Verbosity::Quiet);
+
+    for target in [
+      "CHANGELOG.md",
+      "assets/.gitkeep",
+      "src/*.rs",
+      "missing/*.rs",
+    ] {
+      let action = Touch { target: target.to_string() };
+      action
+        .execute(&root, &Backup::disabled(), &output)
+        .await
+        .unwrap();
+    }
+
+    let mtime = |path: &str| {
+      FileTime::from_last_modification_time(&std_fs::metadata(root.join(path)).unwrap())
+    };
+
+    assert_eq!(read(root.join("CHANGELOG.md")), "keep me");
+    assert_eq!(read(root.join("assets/.gitkeep")), "");
+    assert!(mtime("CHANGELOG.md") > older);
+    assert!(mtime("src/lib.rs") > older);
+    assert!(!root.join("missing").exists());
+  }
+
+  #[tokio::test]
+  #[cfg(unix)]
+  async fn touch_stays_in_root() {
+    let root = TempDir::new("touch-confined");
+    let outside = std::env::temp_dir().join(format!("decaff-test-touched-{}", std::process::id()));
+
+    Touch { target: outside.display().to_string() }
+      .execute(
+        &root,
+        &Backup::disabled(),
+        &Output::capture(Verbosity::Quiet),
+      )
+      .await
+      .unwrap();
+
+    assert!(!outside.exists());
+    assert!(root.join(outside.strip_prefix("/").unwrap()).is_file());
+  }
+
+  #[tokio::test]
+  #[cfg(unix)]
+  async fn run_in_working_directory() {
+    let root = TempDir::new("run-cwd");
+
+    std_fs::create_dir_all(root.join("packages/app")).unwrap();
+
+    let run = |cwd: &str| {
+      Run {
+        name: None,
+        command: "touch marker".to_string(),
+        injects: None,
+        cwd: Some(cwd.to_string()),
+      }
+    };
+
+    let state = State::new();
+    let output = Output::capture(Verbosity::Quiet);
+
+    run("packages/app")
+      .execute(root.path(), &state, false, &output)
+      .await
+      .unwrap();
+
+    assert!(root.join("packages/app/marker").is_file());
+    assert!(!root.join("marker").exists());
+
+    let err = run("packages/missing")
+      .execute(root.path(), &state, false, &output)
+      .await
+      .unwrap_err();
+
+    assert!(err.to_string().contains("packages/missing"));
+
+    // The working directory can't escape the root.
+    run("../../..")
+      .execute(root.path(), &state, false, &output)
+      .await
+      .unwrap();
+
+    assert!(root.join("marker").is_file());
+  }
+
+  #[test]
+  fn merge_env_updates_and_appends() {
+    let contents = "# Database\nexport DB_HOST=localhost\nDB_PORT = 5432\n\nDEBUG=true\n";
+
+    let vars = vec![
+      ("DB_HOST".to_string(), "db".to_string()),
+      ("DB_PORT".to_string(), "6432".to_string()),
+      ("APP_NAME".to_string(), "decaff".to_string()),
+    ];
+
+    let (merged, updated) = merge_env(contents, &vars);
+
+    assert_eq!(
+      merged,
+      "# Database\nexport DB_HOST=db\nDB_PORT=6432\n\nDEBUG=true\nAPP_NAME=decaff\n"
+    );
+
+    assert_eq!(
+      updated,
+      HashSet::from(["DB_HOST".to_string(), "DB_PORT".to_string()])
+    );
+  }
+
+  #[test]
+  fn merge_env_into_empty_file() {
+    let vars = vec![("NAME".to_string(), quote_env("say \"hi\"\n"))];
+
+    let (merged, updated) = merge_env("", &vars);
+
+    assert_eq!(merged, "NAME=\"say \\\"hi\\\"\\n\"\n");
+    assert!(updated.is_empty());
+  }
+
+  #[tokio::test]
+  #[cfg(unix)]
+  async fn env_stays_in_root() {
+    let root = TempDir::new("env-confined");
+    let outside = std::env::temp_dir().join(format!("decaff-test-env-{}", std::process::id()));
+
+    Env {
+      target: outside.display().to_string(),
+      vars: vec![EnvVar {
+        key: "NAME".to_string(),
+        value: "decaff".to_string(),
+      }],
+      quote: false,
+    }
+    .execute(
+      &root,
+      &State::new(),
+      &Backup::disabled(),
+      &Output::capture(Verbosity::Quiet),
+    )
+    .await
+    .unwrap();
+
+    assert!(!outside.exists());
+    assert_eq!(
+      read(root.join(outside.strip_prefix("/").unwrap())),
+      "NAME=decaff\n"
+    );
+  }
+
+  #[tokio::test]
+  async fn replace_with_case_filters() {
+    let root = TempDir::new("replace-case-filters");
+
+    write(
+      root.join("Cargo.toml"),
+      "name = \"{NAME:kebab}\"\ncrate = \"{NAME:snake}\"\n",
+    );
+
+    let mut state = State::new();
+
+    state.set("NAME", Value::String("MyApp".to_string()));
+
+    replace("*", &["NAME"])
+      .execute(
+        &root,
+        &state,
+        false,
+        &[],
+        &Backup::disabled(),
+        &Output::capture(Verbosity::Quiet),
+      )
+      .await
+      .unwrap();
+
+    assert_eq!(
+      read(root.join("Cargo.toml")),
+      "name = \"my-app\"\ncrate = \"my_app\"\n"
+    );
+  }
+
+  #[tokio::test]
+  async fn replace_preserves_line_endings() {
+    let root = TempDir::new("replace-line-endings");
+
+    write(root.join("crlf.txt"), "name: {NAME}\r\nabout: {ABOUT}\r\n");
+    write(root.join("lf.txt"), "name: {NAME}\nabout: {ABOUT}");
+    std_fs::write(
+      root.join("binary.bin"),
+      [0xff, 0xfe, b'{', b'N', b'}', 0x00],
+    )
+    .unwrap();
+
+    let mut state = State::new();
+
+    state.set("NAME", Value::String("decaff".to_string()));
+    state.set("ABOUT", Value::String("one\ntwo".to_string()));
+
+    replace("*", &["NAME", "ABOUT"])
+      .execute(
+        &root,
+        &state,
+        false,
+        &[],
+        &Backup::disabled(),
+        &Output::capture(Verbosity::Quiet),
+      )
+      .await
+      .unwrap();
+
+    // CRLF is kept, including inside the multiline value, as well as the trailing newline.
+    assert_eq!(
+      read(root.join("crlf.txt")),
+      "name: decaff\r\nabout: one\r\ntwo\r\n"
+    );
+
+    // A missing trailing newline isn't added.
+    assert_eq!(read(root.join("lf.txt")), "name: decaff\nabout: one\ntwo");
+
+    // Files that aren't valid UTF-8 are left as-is.
+    assert_eq!(
+      std_fs::read(root.join("binary.bin")).unwrap(),
+      [0xff, 0xfe, b'{', b'N', b'}', 0x00]
+    );
+  }
+
+  #[test]
+  fn detect_line_ending() {
+    assert_eq!(line_ending("a\r\nb\r\nc\n"), "\r\n");
+    assert_eq!(line_ending("a\r\nb\nc\n"), "\n");
+    assert_eq!(line_ending("no line breaks"), "\n");
   }
 }
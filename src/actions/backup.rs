@@ -0,0 +1,272 @@
+use std::fs as std_fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Mutex;
+
+use chrono::Utc;
+use tokio::fs;
+
+use crate::actions::actions::ActionError;
+use crate::path::Traverser;
+
+/// Keeps backups of files that are about to be overwritten or deleted by actions, so they can be
+/// restored if scaffolding fails. Files that are about to be created are recorded as well, along
+/// with their missing parent directories, so they can be removed.
+#[derive(Debug, Default)]
+pub struct Backup {
+  /// Directory to store backups in. If `None`, backups are disabled.
+  root: Option<PathBuf>,
+  /// Pairs of original file paths and their backups, in the order they were saved.
+  entries: Mutex<Vec<(PathBuf, PathBuf)>>,
+  /// Paths that didn't exist when saved, i.e. files created by actions.
+  created: Mutex<Vec<PathBuf>>,
+  /// Missing parent directories of created paths, i.e. directories created by actions.
+  created_dirs: Mutex<Vec<PathBuf>>,
+}
+
+/// Result of restoring a [Backup].
+#[derive(Debug, Default, PartialEq)]
+pub struct Restored {
+  /// Number of restored files.
+  pub restored: usize,
+  /// Number of removed files that were created by actions.
+  pub removed: usize,
+  /// Number of removed directories that were created by actions.
+  pub removed_dirs: usize,
+}
+
+impl Backup {
+  /// Creates a new backup that stores files in a temporary directory.
+  pub fn new() -> Self {
+    let name = format!(
+      "decaff-backup-{}-{}",
+      process::id(),
+      Utc::now().timestamp_millis()
+    );
+
+    Self {
+      root: Some(std::env::temp_dir().join(name)),
+      entries: Mutex::new(Vec::new()),
+      created: Mutex::new(Vec::new()),
+      created_dirs: Mutex::new(Vec::new()),
+    }
+  }
+
+  /// Creates a backup that doesn't store anything.
+  pub fn disabled() -> Self {
+    Self::default()
+  }
+
+  /// Saves a copy of the file, or of all files if given a directory. If the path doesn't exist,
+  /// it's recorded as created, along with its missing parent directories. Does nothing if backups
+  /// are disabled or the path has already been saved.
+  pub async fn save(&self, path: &Path) -> Result<(), ActionError> {
+    if self.root.is_none() {
+      return Ok(());
+    }
+
+    if path.is_dir() {
+      let traverser = Traverser::new(path).ignore_dirs(true);
+
+      for matched in traverser.iter().flatten() {
+        self.save_file(&matched.path).await?;
+      }
+    } else if path.is_file() {
+      self.save_file(path).await?;
+    } else if !path.exists() {
+      let mut created = self.created.lock().unwrap();
+
+      if !created.iter().any(|it| it == path) {
+        created.push(path.to_path_buf());
+      }
+
+      let mut created_dirs = self.created_dirs.lock().unwrap();
+
+      for dir in path.ancestors().skip(1).take_while(|dir| !dir.exists()) {
+        if !created_dirs.iter().any(|it| it == dir) {
+          created_dirs.push(dir.to_path_buf());
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  async fn save_file(&self, path: &Path) -> Result<(), ActionError> {
+    let Some(root) = &self.root else {
+      return Ok(());
+    };
+
+    let backup = {
+      let entries = self.entries.lock().unwrap();
+
+      if entries.iter().any(|(original, _)| original == path) {
+        return Ok(());
+      }
+
+      root.join(entries.len().to_string())
+    };
+
+    fs::create_dir_all(root).await.map_err(|source| {
+      ActionError::Io {
+        message: "Failed to create the backup directory.".to_string(),
+        source,
+      }
+    })?;
+
+    fs::copy(path, &backup).await.map_err(|source| {
+      ActionError::Io {
+        message: format!("Failed to back up '{}'.", path.display()),
+        source,
+      }
+    })?;
+
+    self
+      .entries
+      .lock()
+      .unwrap()
+      .push((path.to_path_buf(), backup));
+
+    Ok(())
+  }
+
+  /// Restores all saved files in reverse order, then removes created files and, deepest first,
+  /// created directories. Directories that aren't empty by then are kept.
+  pub fn restore(&self) -> Result<Restored, ActionError> {
+    let entries = self.entries.lock().unwrap();
+    let created = self.created.lock().unwrap();
+    let mut created_dirs = self.created_dirs.lock().unwrap().clone();
+
+    for (original, backup) in entries.iter().rev() {
+      if let Some(parent) = original.parent() {
+        std_fs::create_dir_all(parent).map_err(|source| {
+          ActionError::Io {
+            message: format!(
+              "Failed to create directory structure for '{}'.",
+              parent.display()
+            ),
+            source,
+          }
+        })?;
+      }
+
+      std_fs::copy(backup, original).map_err(|source| {
+        ActionError::Io {
+          message: format!("Failed to restore '{}'.", original.display()),
+          source,
+        }
+      })?;
+    }
+
+    let mut removed = 0;
+
+    for path in created.iter().rev() {
+      match std_fs::remove_file(path) {
+        | Ok(()) => removed += 1,
+        | Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+        | Err(source) => {
+          return Err(ActionError::Io {
+            message: format!("Failed to remove '{}'.", path.display()),
+            source,
+          });
+        },
+      }
+    }
+
+    let mut removed_dirs = 0;
+
+    created_dirs.sort_by_key(|dir| std::cmp::Reverse(dir.components().count()));
+
+    for dir in &created_dirs {
+      let is_empty = match std_fs::read_dir(dir) {
+        | Ok(mut entries) => entries.next().is_none(),
+        | Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+        | Err(_) => false,
+      };
+
+      if !is_empty {
+        continue;
+      }
+
+      std_fs::remove_dir(dir).map_err(|source| {
+        ActionError::Io {
+          message: format!("Failed to remove '{}'.", dir.display()),
+          source,
+        }
+      })?;
+
+      removed_dirs += 1;
+    }
+
+    Ok(Restored {
+      restored: entries.len(),
+      removed,
+      removed_dirs,
+    })
+  }
+
+  /// Removes the backup directory.
+  pub fn discard(&self) {
+    if let Some(root) = &self.root {
+      let _ = std_fs::remove_dir_all(root);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use super::*;
+  use crate::actions::{Executor, ExecutorOptions, State, Verbosity};
+  use crate::config::Config;
+  use crate::utils::temp::TempDir;
+
+  #[tokio::test]
+  async fn restore_after_failure() {
+    let root = TempDir::new("restore");
+
+    std_fs::write(root.join("existing.txt"), "original").unwrap();
+    std_fs::write(root.join("source.txt"), "copied").unwrap();
+    std_fs::write(
+      root.join("decaff.kdl"),
+      r#"actions {
+        cp from="source.txt" to="out/nested"
+        rename from="source.txt" to="existing.txt"
+        rename from="missing.txt" to="other.txt"
+      }"#,
+    )
+    .unwrap();
+
+    let mut config = Config::new(&root);
+    config.load().unwrap();
+
+    let backup = Arc::new(Backup::new());
+    let options = ExecutorOptions {
+      verbosity: Verbosity::Quiet,
+      ..Default::default()
+    };
+    let executor = Executor::new(config, options).with_backup(backup.clone());
+
+    assert!(executor.execute(State::new()).await.is_err());
+    assert!(root.join("out/nested/source.txt").exists());
+
+    assert_eq!(
+      backup.restore().unwrap(),
+      Restored { restored: 2, removed: 1, removed_dirs: 2 }
+    );
+
+    assert_eq!(
+      std_fs::read_to_string(root.join("existing.txt")).unwrap(),
+      "original"
+    );
+    assert_eq!(
+      std_fs::read_to_string(root.join("source.txt")).unwrap(),
+      "copied"
+    );
+    assert!(!root.join("out").exists());
+
+    backup.discard();
+  }
+}
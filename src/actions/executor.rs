@@ -1,23 +1,19 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
 use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
 
-use crossterm::style::Stylize;
-use miette::Diagnostic;
-use thiserror::Error;
-use tokio::fs;
-
-use crate::config::{ActionSingle, ActionSuite, Actions, Config, Value};
-
-#[derive(Debug, Diagnostic, Error)]
-pub enum ExecutorError {
-  #[error("{message}")]
-  #[diagnostic(code(decaff::actions::executor::io))]
-  Io {
-    message: String,
-    #[source]
-    source: io::Error,
-  },
-}
+use crate::actions::{ActionError, Backup, Event, Output, OutputFormat, Verbosity};
+use crate::config::actions::Prompt;
+use crate::config::{
+  convert_case, fill_placeholders, split_filter, Action, ActionSingle, ActionSuite, Actions,
+  Config, Number, Os, Requirement, Value,
+};
+use crate::repository::{self, Client, DEFAULT_TIMEOUT};
+use crate::utils;
+use crate::utils::style::Stylize;
 
 #[derive(Debug)]
 pub struct State {
@@ -36,10 +32,60 @@ impl State {
     self.values.get(name)
   }
 
+  /// Get a value from the state as a string. Any value can be formatted, lists are joined with
+  /// the default separator.
+  pub fn get_string(&self, name: &str) -> Option<String> {
+    self.get(name).map(Value::to_string)
+  }
+
+  /// Get a value from the state as a boolean. See [Value::as_bool].
+  pub fn get_bool(&self, name: &str) -> Option<bool> {
+    self.get(name).and_then(Value::as_bool)
+  }
+
+  /// Get a value from the state as a number. See [Value::as_number].
+  pub fn get_number(&self, name: &str) -> Option<Number> {
+    self.get(name).and_then(Value::as_number)
+  }
+
   /// Set a value in the state.
   pub fn set<N: Into<String> + AsRef<str>>(&mut self, name: N, replacement: Value) {
     self.values.insert(name.into(), replacement);
   }
+
+  /// Stores a result of the action with the given id as `{id}.{field}`, so later actions can
+  /// reference it like any other value.
+  pub fn capture(&mut self, id: &str, field: &str, value: Value) {
+    self.set(format!("{id}.{field}"), value);
+  }
+
+  /// Returns all values sorted by name.
+  pub fn values(&self) -> BTreeMap<&str, &Value> {
+    self
+      .values
+      .iter()
+      .map(|(name, value)| (name.as_str(), value))
+      .collect()
+  }
+
+  /// Replaces `{name}` and `{name:filter}` placeholders in the input with values from the state,
+  /// joining lists with the given separator. The `json` filter formats the value as JSON, casing
+  /// filters like `kebab` convert the joined value, see [convert_case]. The input is
+  /// scanned for placeholders once, so values are never substituted into each other, and literal
+  /// braces can be escaped as `{{` and `}}`. Placeholders that reference unknown values are left
+  /// as-is.
+  pub fn template(&self, input: &str, separator: &str) -> String {
+    fill_placeholders(input, |token| {
+      let (name, filter) = split_filter(token);
+      let value = self.get(name)?;
+
+      match filter {
+        | Some("json") => Some(value.to_json()),
+        | Some(case) => Some(convert_case(&value.join(separator), case)),
+        | None => Some(value.join(separator)),
+      }
+    })
+  }
 }
 
 impl Default for State {
@@ -48,65 +94,313 @@ impl Default for State {
   }
 }
 
+/// Name of the file listing globs of paths that `replace` and `cp` skip. It's removed after
+/// running actions.
+const IGNORE_NAME: &str = ".decaffignore";
+
+/// Reads glob patterns from the ignore file in the root, skipping empty lines and `#` comments.
+/// Returns nothing if there's no ignore file.
+fn read_ignore(root: &Path) -> Vec<String> {
+  fs::read_to_string(root.join(IGNORE_NAME))
+    .map(|contents| {
+      contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// Executor options. These are set from the CLI.
+#[derive(Debug, Default)]
+pub struct ExecutorOptions {
+  /// Whether to skip prompts and use their default values instead.
+  pub non_interactive: bool,
+  /// Verbosity level of the actions output.
+  pub verbosity: Verbosity,
+  /// Format of the actions output.
+  pub format: OutputFormat,
+  /// Whether network access of actions is disabled.
+  pub offline: bool,
+  /// Tags to select suites by. If not empty, only untagged suites and suites with any of these
+  /// tags are run.
+  pub tags: Vec<String>,
+  /// Time to wait for a prompt to be answered before falling back to its default value.
+  pub prompt_timeout: Option<Duration>,
+}
+
 /// An executor.
 #[derive(Debug)]
 pub struct Executor {
   /// The config to use for execution.
   config: Config,
+  /// Executor options.
+  options: ExecutorOptions,
+  /// Output sink for actions.
+  output: Output,
+  /// Backup of files overwritten or deleted by actions.
+  backup: Arc<Backup>,
+  /// Globs of paths to skip when replacing and copying, read from the ignore file.
+  ignore: Vec<String>,
+  /// HTTP client for actions that fetch files. If `None`, a client with the default timeout is
+  /// created when needed.
+  client: Option<Client>,
 }
 
 impl Executor {
   /// Create a new executor.
-  pub fn new(config: Config) -> Self {
-    Self { config }
+  pub fn new(config: Config, options: ExecutorOptions) -> Self {
+    Self {
+      output: Output::new(options.verbosity).with_format(options.format),
+      backup: Arc::new(Backup::disabled()),
+      ignore: read_ignore(&config.root),
+      client: None,
+      config,
+      options,
+    }
+  }
+
+  /// Set the backup to save files overwritten or deleted by actions to.
+  pub fn with_backup(mut self, backup: Arc<Backup>) -> Self {
+    self.backup = backup;
+    self
+  }
+
+  /// Set the HTTP client for actions that fetch files, so they share its user agent and timeout.
+  pub fn with_client(mut self, client: Client) -> Self {
+    self.client = Some(client);
+    self
   }
 
-  /// Execute the actions.
-  pub async fn execute(&self) -> miette::Result<()> {
+  /// Execute the actions using the given initial state. Returns the final state.
+  ///
+  /// The config file is left intact, so deleting it is up to the caller, which should be done
+  /// before calling [Executor::finish].
+  pub async fn execute(&self, mut state: State) -> miette::Result<State> {
+    self.check_requirements()?;
+    self.check_references(&state);
+
     match &self.config.actions {
-      | Actions::Suite(suites) => self.suite(suites).await?,
-      | Actions::Flat(actions) => self.flat(actions).await?,
-      | Actions::Empty => return Ok(()),
+      | Actions::Suite(suites) => self.suite(suites, &mut state).await?,
+      | Actions::Flat(actions) => {
+        if !self.options.tags.is_empty() {
+          self.output.warn(
+            "? Tags only select suites, so all actions are run."
+              .yellow()
+              .to_string(),
+          );
+        }
+
+        self.flat(actions, &mut state).await?
+      },
+      | Actions::Empty => (),
     };
 
-    // Delete the config file if needed.
-    if self.config.options.delete {
-      fs::remove_file(&self.config.config)
-        .await
-        .map_err(|source| {
-          ExecutorError::Io {
-            message: "Failed to delete config file.".to_string(),
+    Ok(state)
+  }
+
+  /// Finishes scaffolding after the actions were executed: initializes a git repository if needed,
+  /// prints a summary and messages of the `done` block.
+  pub async fn finish(&self, state: &State) -> miette::Result<()> {
+    self.remove_ignore()?;
+
+    if self.config.options.git_init {
+      self.git_init()?;
+    }
+
+    self.summary();
+    self.done(state).await
+  }
+
+  /// Prints the tally of executed actions and their effects, or emits it as an event.
+  fn summary(&self) {
+    let report = self.output.report();
+
+    self.output.info(format!(
+      "{} {}",
+      "Summary:".cyan(),
+      report.to_string().dim()
+    ));
+    self.output.newline();
+    self.output.event(Event::Summary(report));
+  }
+
+  /// Prints messages of the `done` block, e.g. next steps, once everything else is done.
+  async fn done(&self, state: &State) -> miette::Result<()> {
+    let auto_inject = self.config.options.auto_inject;
+
+    for echo in &self.config.done {
+      echo.execute(state, auto_inject, &self.output).await?;
+    }
+
+    Ok(())
+  }
+
+  /// Removes the ignore file, so it doesn't end up in the scaffolded project.
+  fn remove_ignore(&self) -> miette::Result<()> {
+    let path = self.config.root.join(IGNORE_NAME);
+
+    match fs::remove_file(&path) {
+      | Ok(()) => Ok(()),
+      | Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+      | Err(source) => {
+        Err(
+          ActionError::Io {
+            message: format!("Failed to remove '{}'.", path.display()),
             source,
           }
-        })?;
+          .into(),
+        )
+      },
+    }
+  }
+
+  /// Initializes a git repository in the root and makes the initial commit, unless the root is
+  /// already a repository.
+  fn git_init(&self) -> miette::Result<()> {
+    let message = &self.config.options.git_message;
+
+    if repository::init(&self.config.root, message)? {
+      self.output.info(format!(
+        "⋅ Initialized git repository: {}",
+        message.clone().dim()
+      ));
+      self.output.brief("⋅ Initialized git repository");
+    } else {
+      self.output.info(format!(
+        "⋅ Initializing git repository {}",
+        "skipped (exists)".dim()
+      ));
     }
 
     Ok(())
   }
 
-  /// Execute suites of actions.
-  async fn suite(&self, suites: &[ActionSuite]) -> miette::Result<()> {
-    let mut state = State::new();
+  /// Checks that tools required by the template are installed, failing with a single error that
+  /// lists every unmet requirement.
+  fn check_requirements(&self) -> miette::Result<()> {
+    let unmet = self
+      .config
+      .requirements
+      .iter()
+      .filter_map(|Requirement { bin, version }| {
+        let Some(path) = utils::bin::find(bin) else {
+          return Some(format!("`{bin}` is not installed"));
+        };
+
+        let required = version.as_ref()?;
+
+        match utils::bin::version(&path) {
+          | Some(installed) if required.matches(&installed) => None,
+          | Some(installed) => Some(format!("`{bin}` {installed} doesn't satisfy {required}")),
+          | None => {
+            Some(format!(
+              "`{bin}` version is unknown, but {required} is required"
+            ))
+          },
+        }
+      })
+      .collect::<Vec<_>>();
+
+    if !unmet.is_empty() {
+      miette::bail!(
+        code = "decaff::actions::executor::requirements",
+        help = "Install the missing tools and try again.",
+        "Requirements of the template are not met:\n{}",
+        unmet
+          .iter()
+          .map(|line| format!("  - {line}"))
+          .collect::<Vec<_>>()
+          .join("\n")
+      );
+    }
+
+    Ok(())
+  }
+
+  /// Warns about values that are referenced by actions, but neither prompted for nor preset.
+  fn check_references(&self, state: &State) {
+    let actions = &self.config.actions;
+    let prompted = actions.prompted();
+    let captured = actions.captured();
+
+    let unset = actions
+      .referenced(self.config.options.auto_inject)
+      .into_iter()
+      .filter(|name| {
+        !prompted.contains(name) && !captured.contains(name) && state.get(name).is_none()
+      })
+      .collect::<Vec<_>>();
+
+    for name in &unset {
+      self.output.warn(
+        format!("? `{name}` is referenced, but never prompted for.")
+          .yellow()
+          .to_string(),
+      );
+    }
 
-    for ActionSuite { name, actions, .. } in suites {
+    if !unset.is_empty() {
+      self.output.newline();
+    }
+  }
+
+  /// Execute suites of actions.
+  async fn suite(&self, suites: &[ActionSuite], state: &mut State) -> miette::Result<()> {
+    for ActionSuite { name, os, tags, actions } in suites {
       let hint = "Suite".cyan();
       let name = name.clone().green();
 
-      println!("[{hint}: {name}]\n");
+      // Skip the whole suite if it's tagged, but has none of the selected tags. Untagged suites are
+      // always run, so prompts and values that tagged suites rely on are still set.
+      if !self.options.tags.is_empty()
+        && !tags.is_empty()
+        && !tags.iter().any(|tag| self.options.tags.contains(tag))
+      {
+        self
+          .output
+          .info(format!("[{hint}: {name}] {}\n", "skipped (tags)".dim()));
+
+        for action in actions {
+          self.skipped(action, "tags");
+        }
+
+        continue;
+      }
+
+      // Skip the whole suite if neither the suite nor any of its actions match the platform.
+      if !actions
+        .iter()
+        .any(|action| action.os.as_ref().unwrap_or(os).is_current())
+      {
+        self
+          .output
+          .info(format!("[{hint}: {name}] {}\n", "skipped (os)".dim()));
+
+        for action in actions {
+          self.skipped(action, "os");
+        }
+
+        continue;
+      }
+
+      self.output.info(format!("[{hint}: {name}]\n"));
 
       let mut it = actions.iter().peekable();
 
       while let Some(action) = it.next() {
-        self.single(action, &mut state).await?;
+        self.single(action, os, state).await?;
 
         // Do not print a trailing newline if the current and the next actions are prompts to
         // slightly improve visual clarity. Essentially, this way prompts are grouped.
         if !matches!(
-          (action, it.peek()),
+          (&action.kind, it.peek().map(|next| &next.kind)),
           (ActionSingle::Prompt(_), Some(ActionSingle::Prompt(_)))
             | (ActionSingle::Unknown(_), Some(ActionSingle::Unknown(_)))
         ) {
-          println!();
+          self.output.newline();
         }
       }
     }
@@ -115,30 +409,471 @@ impl Executor {
   }
 
   /// Execute a flat list of actions.
-  async fn flat(&self, actions: &[ActionSingle]) -> miette::Result<()> {
-    let mut state = State::new();
-
+  async fn flat(&self, actions: &[Action], state: &mut State) -> miette::Result<()> {
     for action in actions {
-      self.single(action, &mut state).await?;
-      println!();
+      self.single(action, &Os::All, state).await?;
+      self.output.newline();
     }
 
     Ok(())
   }
 
-  /// Execute a single action.
-  async fn single(&self, action: &ActionSingle, state: &mut State) -> miette::Result<()> {
+  /// Execute a single action, unless it's restricted to another platform. Platform restriction of
+  /// the action itself takes precedence over the given inherited one.
+  async fn single(&self, action: &Action, os: &Os, state: &mut State) -> miette::Result<()> {
     let root = &self.config.root;
+    let output = &self.output;
+    let backup = &self.backup;
+    let ignore = &self.ignore;
+    let auto_inject = self.config.options.auto_inject;
+    let interactive = !self.options.non_interactive;
+    let id = action.id.as_deref();
+
+    if !action.os.as_ref().unwrap_or(os).is_current() {
+      self
+        .output
+        .info(format!("⋅ {} {}", action.kind.name(), "skipped (os)".dim()));
+      self.skipped(action, "os");
+      return Ok(());
+    }
+
+    self.started(action);
+
+    match &action.kind {
+      | ActionSingle::Copy(action) => {
+        action
+          .execute(root, state, interactive, ignore, backup, output)
+          .await
+      },
+      | ActionSingle::Move(action) => action.execute(root, interactive, backup, output).await,
+      | ActionSingle::Rename(action) => action.execute(root, backup, output).await,
+      | ActionSingle::Delete(action) => {
+        let confirm = self.config.options.confirm_destructive && !self.options.non_interactive;
+        action.execute(root, confirm, backup, output).await
+      },
+      | ActionSingle::Discard(action) => {
+        let confirm = self.config.options.confirm_destructive && !self.options.non_interactive;
+        action.execute(root, state, confirm, backup, output).await
+      },
+      | ActionSingle::Touch(action) => action.execute(root, backup, output).await,
+      | ActionSingle::Echo(action) => action.execute(state, auto_inject, output).await,
+      | ActionSingle::Run(action) => {
+        let stdout = action.execute(root, state, auto_inject, output).await?;
+
+        if let Some(id) = id {
+          state.capture(id, "stdout", Value::String(stdout.clone()));
+          state.capture(id, "value", Value::String(stdout));
+        }
+
+        Ok(())
+      },
+      | ActionSingle::Prompt(prompt) => self.prompt(prompt, id, state).await,
+      | ActionSingle::Group(group) => {
+        output.info(format!("{} {}", "▸".cyan(), group.title.clone().bold()));
+
+        // Prompts of the group are reported like standalone prompt actions.
+        for action in &group.prompts {
+          if !action.os.as_ref().unwrap_or(os).is_current() {
+            self.skipped(action, "os");
+            continue;
+          }
+
+          if let ActionSingle::Prompt(prompt) = &action.kind {
+            self.started(action);
+            self.prompt(prompt, action.id.as_deref(), state).await?;
+            self.finished(action);
+          }
+        }
+
+        Ok(())
+      },
+      | ActionSingle::Set(action) => action.execute(state, output).await,
+      | ActionSingle::Replace(action) => {
+        let diff = self.config.options.replace_diff;
+        action
+          .execute(root, state, diff, ignore, backup, output)
+          .await
+      },
+      | ActionSingle::Patch(action) => action.execute(root, state, backup, output).await,
+      | ActionSingle::Env(action) => action.execute(root, state, backup, output).await,
+      | ActionSingle::Fetch(action) => {
+        let offline = self.options.offline;
+        let client = match &self.client {
+          | Some(client) => client.clone(),
+          | None => Client::new(DEFAULT_TIMEOUT)?,
+        };
+
+        action
+          .execute(root, state, offline, &client, backup, output)
+          .await
+      },
+      | ActionSingle::Unknown(action) => action.execute(output).await,
+    }?;
+
+    self.finished(action);
+
+    Ok(())
+  }
+
+  /// Prints the description of the action, if any, and emits an event for its start.
+  fn started(&self, action: &Action) {
+    if let Some(description) = &action.description {
+      self
+        .output
+        .detail(format!("# {}", description.clone().dim()));
+    }
 
-    match action {
-      | ActionSingle::Copy(action) => action.execute(root).await,
-      | ActionSingle::Move(action) => action.execute(root).await,
-      | ActionSingle::Delete(action) => action.execute(root).await,
-      | ActionSingle::Echo(action) => action.execute(state).await,
-      | ActionSingle::Run(action) => action.execute(root, state).await,
-      | ActionSingle::Prompt(action) => action.execute(state).await,
-      | ActionSingle::Replace(action) => action.execute(root, state).await,
-      | ActionSingle::Unknown(action) => action.execute().await,
+    self.output.event(Event::ActionStarted {
+      action: action.kind.name().to_string(),
+      description: action.description.clone(),
+    });
+  }
+
+  /// Emits an event for an action that finished successfully.
+  fn finished(&self, action: &Action) {
+    self
+      .output
+      .event(Event::ActionFinished { action: action.kind.name().to_string() });
+  }
+
+  /// Executes a prompt and captures its answer if the action has an id.
+  async fn prompt(
+    &self,
+    prompt: &Prompt,
+    id: Option<&str>,
+    state: &mut State,
+  ) -> miette::Result<()> {
+    prompt.execute(state, &self.options, &self.output).await?;
+
+    if let Some(value) = state.get(prompt.name()).cloned() {
+      self.output.event(Event::PromptAnswered {
+        name: prompt.name().to_string(),
+        value: value.clone(),
+      });
+
+      if let Some(id) = id {
+        state.capture(id, "value", value);
+      }
     }
+
+    Ok(())
+  }
+
+  /// Emits an event for an action that was skipped for the given reason.
+  fn skipped(&self, action: &Action, reason: &str) {
+    self.output.event(Event::ActionSkipped {
+      action: action.kind.name().to_string(),
+      reason: reason.to_string(),
+    });
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::utils::temp::TempDir;
+
+  #[test]
+  fn template_known_values() {
+    let mut state = State::new();
+
+    state.set("NAME", Value::String("decaff".to_string()));
+    state.set("VERSION", Value::Number(Number::Integer(1)));
+
+    assert_eq!(state.template("{NAME}-core", " "), "decaff-core");
+    assert_eq!(state.template("{NAME}@{VERSION}", " "), "decaff@1");
+  }
+
+  #[test]
+  fn template_leaves_unknown_values() {
+    let mut state = State::new();
+
+    state.set("NAME", Value::String("decaff".to_string()));
+
+    assert_eq!(state.template("{NAME}-{UNKNOWN}", " "), "decaff-{UNKNOWN}");
+    assert_eq!(state.template("{}", " "), "{}");
+
+    // Values aren't substituted into each other.
+    state.set("OTHER", Value::String("{NAME}".to_string()));
+
+    assert_eq!(state.template("{OTHER}", " "), "{NAME}");
+  }
+
+  #[test]
+  fn template_with_escapes() {
+    let mut state = State::new();
+
+    state.set("NAME", Value::String("decaff".to_string()));
+    state.set(
+      "TAGS",
+      Value::List(vec!["cli".to_string(), "kdl".to_string()]),
+    );
+
+    assert_eq!(state.template("{NAME}: {TAGS}", " "), "decaff: cli kdl");
+    assert_eq!(state.template("{TAGS:json}", " "), r#"["cli","kdl"]"#);
+    assert_eq!(state.template("{TAGS:upper}", "-"), "CLI-KDL");
+    assert_eq!(
+      state.template("{{NAME}} {UNKNOWN}", " "),
+      "{NAME} {UNKNOWN}"
+    );
+  }
+
+  #[test]
+  fn template_with_captured_results() {
+    let mut state = State::new();
+
+    state.capture("version", "stdout", Value::String("1.2.3".to_string()));
+    state.capture("pm", "value", Value::String("pnpm".to_string()));
+
+    assert_eq!(
+      state.template("{pm.value} version {version.stdout:json}", " "),
+      r#"pnpm version "1.2.3""#
+    );
+    assert_eq!(state.template("{version.value}", " "), "{version.value}");
+  }
+
+  #[test]
+  fn typed_getters() {
+    let mut state = State::new();
+
+    state.set("FLAG", Value::Bool(true));
+    state.set("PORT", Value::String("8080".to_string()));
+    state.set("VERSION", Value::Number(Number::Float(1.5)));
+    state.set(
+      "TAGS",
+      Value::List(vec!["cli".to_string(), "kdl".to_string()]),
+    );
+
+    assert_eq!(state.get_string("FLAG").as_deref(), Some("true"));
+    assert_eq!(state.get_string("VERSION").as_deref(), Some("1.5"));
+    assert_eq!(state.get_string("TAGS").as_deref(), Some("cli, kdl"));
+    assert_eq!(state.get_bool("FLAG"), Some(true));
+    assert_eq!(state.get_bool("PORT"), None);
+    assert_eq!(
+      state.get_number("PORT").map(|it| it.to_string()).as_deref(),
+      Some("8080")
+    );
+    assert!(state.get_number("FLAG").is_none());
+    assert!(state.get_string("UNKNOWN").is_none());
+  }
+
+  #[test]
+  fn group_prompts_are_collected() {
+    let root = TempDir::new("group");
+
+    fs::write(
+      root.join("decaff.kdl"),
+      r#"actions {
+        group "Database settings" {
+          input "DB_HOST" id="host" { hint "Host"; }
+          number "DB_PORT" { hint "Port"; }
+        }
+        echo "{DB_HOST}:{DB_PORT}"
+      }"#,
+    )
+    .unwrap();
+
+    let mut config = Config::new(&root);
+    config.load().unwrap();
+
+    let prompted = config.actions.prompted().into_iter().collect::<Vec<_>>();
+    assert_eq!(prompted, ["DB_HOST", "DB_PORT"]);
+    assert!(config.actions.captured().contains("host.value"));
+
+    fs::write(
+      root.join("decaff.kdl"),
+      r#"actions {
+        group "Files" {
+          rm "dist"
+        }
+      }"#,
+    )
+    .unwrap();
+
+    assert!(Config::new(&root).load().is_err());
+  }
+
+  #[test]
+  fn copy_sources() {
+    let root = TempDir::new("sources");
+
+    fs::write(
+      root.join("decaff.kdl"),
+      r#"actions {
+        cp from="My Docs/*" to="docs"
+        cp to="dist" {
+          from "*.md" "LICENSE"
+        }
+      }"#,
+    )
+    .unwrap();
+
+    let mut config = Config::new(&root);
+    config.load().unwrap();
+
+    let sources = config
+      .actions
+      .iter()
+      .filter_map(|action| {
+        match &action.kind {
+          | ActionSingle::Copy(copy) => Some(copy.from.clone()),
+          | _ => None,
+        }
+      })
+      .collect::<Vec<_>>();
+
+    assert_eq!(sources, vec![vec!["My Docs/*"], vec!["*.md", "LICENSE"]]);
+  }
+
+  #[tokio::test]
+  async fn group_prompts_are_reported() {
+    let root = TempDir::new("group-report");
+
+    fs::write(
+      root.join("decaff.kdl"),
+      r#"actions {
+        group "Database settings" {
+          input "DB_HOST" { hint "Host"; default "localhost"; }
+          number "DB_PORT" { hint "Port"; default 5432; }
+        }
+      }"#,
+    )
+    .unwrap();
+
+    let mut config = Config::new(&root);
+    config.load().unwrap();
+
+    let options = ExecutorOptions {
+      non_interactive: true,
+      verbosity: Verbosity::Quiet,
+      ..Default::default()
+    };
+    let executor = Executor::new(config, options);
+
+    executor.execute(State::new()).await.unwrap();
+
+    let report = executor.output.report();
+
+    assert_eq!(report.prompts_answered, 2);
+    assert_eq!(report.actions.values().sum::<usize>(), 3);
+  }
+
+  #[tokio::test]
+  async fn tags_keep_untagged_suites() {
+    let root = TempDir::new("tags");
+
+    fs::write(
+      root.join("decaff.kdl"),
+      r#"actions {
+        suite "prompts" {
+          input "NAME" {
+            hint "Name"
+            default "My App"
+          }
+        }
+        suite "web" tags="web" {
+          let "SLUG" "{NAME:kebab}"
+        }
+        suite "api" tags="api" {
+          let "PORT" "8080"
+        }
+      }"#,
+    )
+    .unwrap();
+
+    let mut config = Config::new(&root);
+    config.load().unwrap();
+
+    let options = ExecutorOptions {
+      non_interactive: true,
+      verbosity: Verbosity::Quiet,
+      tags: vec!["web".to_string()],
+      ..Default::default()
+    };
+    let state = Executor::new(config, options)
+      .execute(State::new())
+      .await
+      .unwrap();
+
+    assert_eq!(
+      state.get("SLUG").map(|value| value.join(" ")),
+      Some("my-app".to_string())
+    );
+    assert!(state.get("PORT").is_none());
+  }
+
+  #[tokio::test]
+  async fn tags_warn_in_flat_mode() {
+    let root = TempDir::new("flat-tags");
+
+    fs::write(
+      root.join("decaff.kdl"),
+      r#"actions {
+        let "PORT" "8080"
+      }"#,
+    )
+    .unwrap();
+
+    let mut config = Config::new(&root);
+    config.load().unwrap();
+
+    let options = ExecutorOptions {
+      non_interactive: true,
+      tags: vec!["web".to_string()],
+      ..Default::default()
+    };
+    let mut executor = Executor::new(config, options);
+    executor.output = Output::capture(Verbosity::Quiet).with_format(OutputFormat::Json);
+
+    let state = executor.execute(State::new()).await.unwrap();
+
+    assert!(state.get("PORT").is_some());
+    assert!(executor.output.lines().iter().any(|line| {
+      line.contains(r#""event":"warning""#) && line.contains("Tags only select suites")
+    }));
+  }
+
+  #[tokio::test]
+  async fn unset_references_warn() {
+    let root = TempDir::new("unset");
+
+    fs::write(
+      root.join("decaff.kdl"),
+      r#"actions {
+        let "URL" "{HOST}:8080"
+      }"#,
+    )
+    .unwrap();
+
+    let mut config = Config::new(&root);
+    config.load().unwrap();
+
+    let options = ExecutorOptions {
+      non_interactive: true,
+      ..Default::default()
+    };
+    let mut executor = Executor::new(config, options);
+    executor.output = Output::capture(Verbosity::Quiet).with_format(OutputFormat::Json);
+
+    executor.execute(State::new()).await.unwrap();
+
+    assert!(executor.output.lines().iter().any(|line| {
+      line.contains(r#""event":"warning""#) && line.contains("`HOST` is referenced")
+    }));
+  }
+
+  #[test]
+  fn read_ignore_globs() {
+    let root = TempDir::new("ignore");
+
+    assert!(read_ignore(&root).is_empty());
+
+    fs::write(
+      root.join(IGNORE_NAME),
+      "# Vendored code\nvendor/**\n\n  *.min.js  \n",
+    )
+    .unwrap();
+
+    assert_eq!(read_ignore(&root), vec!["vendor/**", "*.min.js"]);
   }
 }
@@ -1,5 +1,11 @@
+pub use actions::ActionError;
+pub use backup::*;
 pub use executor::*;
+pub use output::*;
 
 mod actions;
+mod backup;
 mod executor;
+mod output;
+mod patch;
 mod prompts;
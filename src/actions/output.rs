@@ -0,0 +1,369 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::config::Value;
+
+/// Verbosity level of the actions output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub enum Verbosity {
+  /// Only warnings and brief summaries are printed.
+  Quiet,
+  /// Regular output.
+  #[default]
+  Normal,
+  /// Regular output plus additional details.
+  Verbose,
+}
+
+/// Format of the actions output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+  /// Decorated text for humans.
+  #[default]
+  Text,
+  /// Newline-delimited JSON events for machines.
+  Json,
+}
+
+/// Event emitted in the [OutputFormat::Json] format. Serialized as a JSON object with the `event`
+/// field holding the snake-cased variant name.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+  /// Action started executing.
+  ActionStarted {
+    action: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+  },
+  /// Action was skipped, e.g. because it's restricted to another platform.
+  ActionSkipped { action: String, reason: String },
+  /// Action finished successfully.
+  ActionFinished { action: String },
+  /// File was written, i.e. created, overwritten or modified.
+  FileWritten { path: PathBuf },
+  /// File or directory was deleted.
+  FileDeleted { path: PathBuf },
+  /// File or directory was moved or renamed.
+  FileMoved { from: PathBuf, to: PathBuf },
+  /// Prompt was answered, interactively or not.
+  PromptAnswered { name: String, value: Value },
+  /// Output of `echo` and `run` actions.
+  Output { text: String },
+  /// Warning.
+  Warning { message: String },
+  /// Error that stopped the scaffolding.
+  Error {
+    code: Option<String>,
+    message: String,
+  },
+  /// Summary of what was done, emitted once all actions finished.
+  Summary(Report),
+}
+
+/// Tally of executed actions and their effects, collected from events.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Report {
+  /// Number of finished actions by action name, e.g. `cp`.
+  pub actions: BTreeMap<String, usize>,
+  /// Number of skipped actions.
+  pub skipped: usize,
+  /// Number of files written, i.e. created, overwritten or modified.
+  pub files_written: usize,
+  /// Number of files and directories deleted.
+  pub files_deleted: usize,
+  /// Number of files and directories moved or renamed.
+  pub files_moved: usize,
+  /// Number of prompts answered, interactively or not.
+  pub prompts_answered: usize,
+}
+
+impl Report {
+  /// Counts the event if it's relevant.
+  fn record(&mut self, event: &Event) {
+    match event {
+      | Event::ActionFinished { action } => *self.actions.entry(action.clone()).or_default() += 1,
+      | Event::ActionSkipped { .. } => self.skipped += 1,
+      | Event::FileWritten { .. } => self.files_written += 1,
+      | Event::FileDeleted { .. } => self.files_deleted += 1,
+      | Event::FileMoved { .. } => self.files_moved += 1,
+      | Event::PromptAnswered { .. } => self.prompts_answered += 1,
+      | _ => (),
+    }
+  }
+}
+
+/// Formats the count with the noun, pluralized if needed.
+fn plural(count: usize, noun: &str) -> String {
+  match count {
+    | 1 => format!("{count} {noun}"),
+    | _ => format!("{count} {noun}s"),
+  }
+}
+
+impl fmt::Display for Report {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let total = self.actions.values().sum();
+
+    let kinds = self
+      .actions
+      .iter()
+      .map(|(action, count)| format!("{count} {action}"))
+      .collect::<Vec<_>>();
+
+    write!(f, "{}", plural(total, "action"))?;
+
+    if !kinds.is_empty() {
+      write!(f, " ({})", kinds.join(", "))?;
+    }
+
+    write!(
+      f,
+      ", {} written, {} deleted, {} moved, {} answered",
+      plural(self.files_written, "file"),
+      plural(self.files_deleted, "file"),
+      plural(self.files_moved, "file"),
+      plural(self.prompts_answered, "prompt")
+    )?;
+
+    if self.skipped > 0 {
+      write!(f, ", {} skipped", self.skipped)?;
+    }
+
+    Ok(())
+  }
+}
+
+/// Output sink for actions. Prints messages honoring the verbosity level, or captures them if
+/// created via [Output::capture]. In the [OutputFormat::Json] format only events and warnings are
+/// printed, the latter turned into [Event::Warning]. Events are tallied into a [Report] in any
+/// format.
+#[derive(Debug, Default)]
+pub struct Output {
+  /// Verbosity level.
+  verbosity: Verbosity,
+  /// Output format.
+  format: OutputFormat,
+  /// Captured lines. If `None`, lines are printed right away.
+  captured: Option<Mutex<Vec<String>>>,
+  /// Tally of the events so far.
+  report: Mutex<Report>,
+}
+
+impl Output {
+  /// Creates a new output sink that prints to stdout/stderr.
+  pub fn new(verbosity: Verbosity) -> Self {
+    Self {
+      verbosity,
+      format: OutputFormat::default(),
+      captured: None,
+      report: Mutex::default(),
+    }
+  }
+
+  /// Creates a new output sink that captures lines instead of printing them.
+  #[cfg(test)]
+  pub fn capture(verbosity: Verbosity) -> Self {
+    Self {
+      verbosity,
+      format: OutputFormat::default(),
+      captured: Some(Mutex::new(Vec::new())),
+      report: Mutex::default(),
+    }
+  }
+
+  /// Sets the output format.
+  pub fn with_format(mut self, format: OutputFormat) -> Self {
+    self.format = format;
+    self
+  }
+
+  /// Returns captured lines. Always empty if the sink doesn't capture.
+  #[cfg(test)]
+  pub fn lines(&self) -> Vec<String> {
+    self
+      .captured
+      .as_ref()
+      .map(|lines| lines.lock().unwrap().clone())
+      .unwrap_or_default()
+  }
+
+  /// Returns the tally of the events so far.
+  pub fn report(&self) -> Report {
+    self.report.lock().unwrap().clone()
+  }
+
+  /// Prints a regular message. Suppressed in quiet mode.
+  pub fn info<S: AsRef<str>>(&self, message: S) {
+    if self.verbosity >= Verbosity::Normal {
+      self.write(message.as_ref(), false);
+    }
+  }
+
+  /// Prints an empty line. Suppressed in quiet mode.
+  pub fn newline(&self) {
+    self.info("");
+  }
+
+  /// Prints a brief message that replaces the regular output in quiet mode. Printed only in quiet
+  /// mode.
+  pub fn brief<S: AsRef<str>>(&self, message: S) {
+    if self.verbosity == Verbosity::Quiet {
+      self.write(message.as_ref(), false);
+    }
+  }
+
+  /// Prints an additional detail. Printed only in verbose mode.
+  pub fn detail<S: AsRef<str>>(&self, message: S) {
+    if self.verbosity >= Verbosity::Verbose {
+      self.write(message.as_ref(), false);
+    }
+  }
+
+  /// Prints a message that has to be seen, e.g. before asking for a confirmation. Never suppressed.
+  pub fn notice<S: AsRef<str>>(&self, message: S) {
+    self.write(message.as_ref(), false);
+  }
+
+  /// Prints a warning to stderr. Never suppressed.
+  pub fn warn<S: AsRef<str>>(&self, message: S) {
+    self.write(message.as_ref(), true);
+  }
+
+  /// Prints an event as a single line of JSON. Printed only in the JSON format, but tallied in any
+  /// format.
+  pub fn event(&self, event: Event) {
+    self.report.lock().unwrap().record(&event);
+
+    if self.format == OutputFormat::Json {
+      let line = serde_json::to_string(&event).expect("Events are always serializable");
+      self.print(&line, false);
+    }
+  }
+
+  fn write(&self, message: &str, is_err: bool) {
+    match self.format {
+      | OutputFormat::Text => self.print(message, is_err),
+      | OutputFormat::Json if is_err => {
+        self.event(Event::Warning { message: message.to_string() });
+      },
+      | OutputFormat::Json => (),
+    }
+  }
+
+  fn print(&self, message: &str, is_err: bool) {
+    match &self.captured {
+      | Some(lines) => lines.lock().unwrap().push(message.to_string()),
+      | None if is_err => eprintln!("{message}"),
+      | None => println!("{message}"),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn emit(output: &Output) {
+    output.info("info");
+    output.brief("brief");
+    output.detail("detail");
+    output.notice("notice");
+    output.warn("warn");
+  }
+
+  #[test]
+  fn respects_verbosity() {
+    let cases = [
+      (Verbosity::Quiet, vec!["brief", "notice", "warn"]),
+      (Verbosity::Normal, vec!["info", "notice", "warn"]),
+      (Verbosity::Verbose, vec!["info", "detail", "notice", "warn"]),
+    ];
+
+    for (verbosity, expected) in cases {
+      let output = Output::capture(verbosity);
+
+      emit(&output);
+
+      assert_eq!(output.lines(), expected);
+    }
+  }
+
+  #[test]
+  fn emits_json_events() {
+    let output = Output::capture(Verbosity::Verbose).with_format(OutputFormat::Json);
+
+    emit(&output);
+
+    output.event(Event::ActionStarted {
+      action: "cp".to_string(),
+      description: Some("Copy sources".to_string()),
+    });
+    output.event(Event::FileWritten { path: PathBuf::from("dist/a.txt") });
+
+    assert_eq!(
+      output.lines(),
+      vec![
+        r#"{"event":"warning","message":"warn"}"#,
+        r#"{"event":"action_started","action":"cp","description":"Copy sources"}"#,
+        r#"{"event":"file_written","path":"dist/a.txt"}"#,
+      ]
+    );
+  }
+
+  #[test]
+  fn tallies_events() {
+    let output = Output::capture(Verbosity::Normal);
+
+    for action in ["cp", "run", "cp"] {
+      output.event(Event::ActionFinished { action: action.to_string() });
+    }
+
+    output.event(Event::FileWritten { path: PathBuf::from("a.txt") });
+    output.event(Event::FileWritten { path: PathBuf::from("b.txt") });
+    output.event(Event::FileDeleted { path: PathBuf::from("c.txt") });
+    output.event(Event::FileMoved {
+      from: PathBuf::from("d.txt"),
+      to: PathBuf::from("e.txt"),
+    });
+    output.event(Event::ActionSkipped {
+      action: "rm".to_string(),
+      reason: "os".to_string(),
+    });
+
+    let report = output.report();
+
+    assert_eq!(
+      report.to_string(),
+      "3 actions (2 cp, 1 run), 2 files written, 1 file deleted, 1 file moved, 0 prompts answered, \
+       1 skipped"
+    );
+
+    let json = Output::capture(Verbosity::Normal).with_format(OutputFormat::Json);
+    json.event(Event::Summary(report));
+
+    assert_eq!(
+      json.lines(),
+      vec![
+        r#"{"event":"summary","actions":{"cp":2,"run":1},"skipped":1,"files_written":2,"files_deleted":1,"files_moved":1,"prompts_answered":0}"#
+      ]
+    );
+  }
+
+  #[test]
+  fn skips_events_in_text_format() {
+    let output = Output::capture(Verbosity::Normal);
+
+    output.event(Event::ActionStarted {
+      action: "cp".to_string(),
+      description: None,
+    });
+
+    assert!(output.lines().is_empty());
+  }
+}
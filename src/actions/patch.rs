@@ -0,0 +1,605 @@
+use std::path::Path;
+
+use tokio::fs;
+
+use crate::actions::actions::{confine, ActionError};
+use crate::actions::{Backup, Event, Output, State};
+use crate::config::actions::{Patch, PatchSet};
+use crate::config::{Number, Value, DEFAULT_SEPARATOR};
+use crate::utils::style::Stylize;
+
+/// Supported formats of patched files.
+#[derive(Clone, Copy, Debug)]
+enum Format {
+  Json,
+  Toml,
+  Yaml,
+}
+
+impl Format {
+  /// Detects the format by file extension.
+  fn detect(path: &Path) -> Option<Self> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+
+    match extension.as_str() {
+      | "json" => Some(Self::Json),
+      | "toml" => Some(Self::Toml),
+      | "yaml" | "yml" => Some(Self::Yaml),
+      | _ => None,
+    }
+  }
+
+  /// Parses the contents, applies the given sets, and serializes the result back.
+  fn apply(self, contents: &str, sets: &[PatchSet]) -> Result<String, PatchFailure> {
+    match self {
+      | Self::Json => {
+        let mut document: serde_json::Value =
+          serde_json::from_str(contents).map_err(|err| PatchFailure::Parse(err.to_string()))?;
+
+        apply_sets(&mut document, sets)?;
+
+        // Keep the indentation of the file, falling back to two spaces.
+        let indent = contents
+          .lines()
+          .find_map(|line| {
+            let indent = &line[..line.len() - line.trim_start().len()];
+            (!indent.is_empty()).then_some(indent)
+          })
+          .unwrap_or("  ");
+
+        let mut output = Vec::new();
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+        let mut serializer = serde_json::Serializer::with_formatter(&mut output, formatter);
+
+        serde::Serialize::serialize(&document, &mut serializer)
+          .map_err(|err| PatchFailure::Serialize(err.to_string()))?;
+
+        Ok(String::from_utf8_lossy(&output).into_owned() + "\n")
+      },
+      | Self::Toml => {
+        let mut document = contents
+          .parse::<toml_edit::DocumentMut>()
+          .map_err(|err| PatchFailure::Parse(err.to_string()))?;
+
+        for PatchSet { path, value } in sets {
+          set_toml_table(
+            document.as_table_mut(),
+            false,
+            &segments(path),
+            &toml_value(value),
+          )
+          .ok_or_else(|| PatchFailure::InvalidPath(path.to_owned()))?;
+        }
+
+        Ok(document.to_string())
+      },
+      | Self::Yaml => {
+        let mut document: serde_yaml::Value =
+          serde_yaml::from_str(contents).map_err(|err| PatchFailure::Parse(err.to_string()))?;
+
+        apply_sets(&mut document, sets)?;
+
+        serde_yaml::to_string(&document).map_err(|err| PatchFailure::Serialize(err.to_string()))
+      },
+    }
+  }
+}
+
+/// Reasons patching may fail, turned into [ActionError::Patch] with the file path attached.
+#[derive(Debug)]
+enum PatchFailure {
+  Parse(String),
+  Serialize(String),
+  InvalidPath(String),
+}
+
+/// A structured document that can be navigated and patched with dotted or pointer paths.
+trait Document: Sized {
+  /// Returns the child by key or array index. Missing keys are created as empty tables.
+  fn child(&mut self, key: &str) -> Option<&mut Self>;
+
+  /// Sets the child by key or existing array index. Returns `None` if the value is not a container
+  /// or the index is out of bounds.
+  fn insert(&mut self, key: &str, value: &Value) -> Option<()>;
+}
+
+impl Document for serde_json::Value {
+  fn child(&mut self, key: &str) -> Option<&mut Self> {
+    match self {
+      | Self::Object(map) => {
+        Some(
+          map
+            .entry(key)
+            .or_insert_with(|| Self::Object(serde_json::Map::new())),
+        )
+      },
+      | Self::Array(items) => items.get_mut(key.parse::<usize>().ok()?),
+      | _ => None,
+    }
+  }
+
+  fn insert(&mut self, key: &str, value: &Value) -> Option<()> {
+    let value = match value {
+      | Value::String(string) => Self::String(string.to_owned()),
+      | Value::Number(Number::Integer(int)) => Self::from(*int),
+      | Value::Number(Number::Unsigned(int)) => Self::from(*int),
+      | Value::Number(Number::Float(float)) => Self::from(*float),
+      | Value::Bool(boolean) => Self::Bool(*boolean),
+      | Value::List(items) => Self::from(items.clone()),
+    };
+
+    match self {
+      | Self::Object(map) => {
+        map.insert(key.to_string(), value);
+      },
+      | Self::Array(items) => *items.get_mut(key.parse::<usize>().ok()?)? = value,
+      | _ => return None,
+    }
+
+    Some(())
+  }
+}
+
+/// Converts the value into a TOML value. TOML integers are limited to `i64`, so larger ones are
+/// kept as strings to not lose digits.
+fn toml_value(value: &Value) -> toml_edit::Value {
+  match value {
+    | Value::String(string) => string.into(),
+    | Value::Number(Number::Integer(int)) => (*int).into(),
+    | Value::Number(Number::Unsigned(int)) => int.to_string().into(),
+    | Value::Number(Number::Float(float)) => (*float).into(),
+    | Value::Bool(boolean) => (*boolean).into(),
+    | Value::List(items) => toml_edit::Value::Array(items.iter().collect()),
+  }
+}
+
+/// Replaces the value, keeping the comments and whitespace around the old one.
+fn replace_toml_value(old: &mut toml_edit::Value, value: &toml_edit::Value) {
+  let decor = old.decor().clone();
+
+  *old = value.clone();
+  *old.decor_mut() = decor;
+}
+
+// NOTE: TOML is patched in place with `toml_edit` to keep comments, key order and formatting. Its
+// tables, arrays of tables and inline values are distinct types, so it can't implement [Document]
+// and is navigated with the functions below instead.
+
+/// Sets the value at the path inside the item.
+fn set_toml_item(
+  item: &mut toml_edit::Item,
+  path: &[String],
+  value: &toml_edit::Value,
+) -> Option<()> {
+  match item {
+    | toml_edit::Item::Table(table) => set_toml_table(table, false, path, value),
+    | toml_edit::Item::Value(inner) => set_toml_value(inner, path, value),
+    | toml_edit::Item::ArrayOfTables(tables) => {
+      let (index, rest) = path.split_first()?;
+      let table = tables.get_mut(index.parse::<usize>().ok()?)?;
+
+      (!rest.is_empty()).then_some(())?;
+      set_toml_table(table, false, rest, value)
+    },
+    | toml_edit::Item::None => None,
+  }
+}
+
+/// Sets the value at the path inside the table, creating missing tables along the way. Tables
+/// created inside inline tables are inline too.
+fn set_toml_table(
+  table: &mut dyn toml_edit::TableLike,
+  inline: bool,
+  path: &[String],
+  value: &toml_edit::Value,
+) -> Option<()> {
+  let (key, rest) = path.split_first()?;
+
+  if rest.is_empty() {
+    match table.get_mut(key) {
+      | Some(toml_edit::Item::Value(old)) => replace_toml_value(old, value),
+      | _ => {
+        table.insert(key, toml_edit::Item::Value(value.clone()));
+      },
+    }
+
+    return Some(());
+  }
+
+  let child = table.entry(key).or_insert_with(|| {
+    if inline {
+      toml_edit::Item::Value(toml_edit::InlineTable::new().into())
+    } else {
+      let mut table = toml_edit::Table::new();
+      table.set_implicit(true);
+      toml_edit::Item::Table(table)
+    }
+  });
+
+  set_toml_item(child, rest, value)
+}
+
+/// Sets the value at the path inside an inline table or an array.
+fn set_toml_value(
+  node: &mut toml_edit::Value,
+  path: &[String],
+  value: &toml_edit::Value,
+) -> Option<()> {
+  match node {
+    | toml_edit::Value::InlineTable(table) => set_toml_table(table, true, path, value),
+    | toml_edit::Value::Array(items) => {
+      let (index, rest) = path.split_first()?;
+      let item = items.get_mut(index.parse::<usize>().ok()?)?;
+
+      if rest.is_empty() {
+        replace_toml_value(item, value);
+        Some(())
+      } else {
+        set_toml_value(item, rest, value)
+      }
+    },
+    | _ => None,
+  }
+}
+
+impl Document for serde_yaml::Value {
+  fn child(&mut self, key: &str) -> Option<&mut Self> {
+    match self {
+      | Self::Mapping(mapping) => {
+        Some(
+          mapping
+            .entry(Self::String(key.to_string()))
+            .or_insert_with(|| Self::Mapping(serde_yaml::Mapping::new())),
+        )
+      },
+      | Self::Sequence(items) => items.get_mut(key.parse::<usize>().ok()?),
+      | _ => None,
+    }
+  }
+
+  fn insert(&mut self, key: &str, value: &Value) -> Option<()> {
+    let value = match value {
+      | Value::String(string) => Self::String(string.to_owned()),
+      | Value::Number(Number::Integer(int)) => Self::Number((*int).into()),
+      | Value::Number(Number::Unsigned(int)) => Self::Number((*int).into()),
+      | Value::Number(Number::Float(float)) => Self::Number((*float).into()),
+      | Value::Bool(boolean) => Self::Bool(*boolean),
+      | Value::List(items) => Self::Sequence(items.iter().cloned().map(Self::String).collect()),
+    };
+
+    match self {
+      | Self::Mapping(mapping) => {
+        mapping.insert(Self::String(key.to_string()), value);
+      },
+      | Self::Sequence(items) => *items.get_mut(key.parse::<usize>().ok()?)? = value,
+      | _ => return None,
+    }
+
+    Some(())
+  }
+}
+
+/// Splits a path into segments. Paths starting with `/` are treated as JSON pointers (RFC 6901),
+/// otherwise segments are separated by dots.
+fn segments(path: &str) -> Vec<String> {
+  match path.strip_prefix('/') {
+    | Some(pointer) => {
+      pointer
+        .split('/')
+        .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+        .collect()
+    },
+    | None => path.split('.').map(str::to_string).collect(),
+  }
+}
+
+/// Sets the value at the given path, creating missing tables along the way.
+fn set<D: Document>(document: &mut D, path: &str, value: &Value) -> Option<()> {
+  let segments = segments(path);
+  let (last, parents) = segments.split_last()?;
+
+  let mut node = document;
+
+  for segment in parents {
+    node = node.child(segment)?;
+  }
+
+  node.insert(last, value)
+}
+
+fn apply_sets<D: Document>(document: &mut D, sets: &[PatchSet]) -> Result<(), PatchFailure> {
+  for PatchSet { path, value } in sets {
+    set(document, path, value).ok_or_else(|| PatchFailure::InvalidPath(path.to_owned()))?;
+  }
+
+  Ok(())
+}
+
+impl Patch {
+  pub async fn execute<P>(
+    &self,
+    root: P,
+    state: &State,
+    backup: &Backup,
+    output: &Output,
+  ) -> miette::Result<()>
+  where
+    P: AsRef<Path>,
+  {
+    let target = confine(root.as_ref(), &self.target)?;
+
+    output.info(format!("⋅ Patching: {}", &self.target.clone().dim()));
+
+    let patch_error = |message: String| ActionError::Patch { message };
+
+    let format = Format::detect(&target).ok_or_else(|| {
+      patch_error(format!(
+        "Unsupported file format of '{}'. Expected JSON, TOML or YAML.",
+        self.target
+      ))
+    })?;
+
+    if !target.is_file() {
+      return Err(
+        patch_error(format!(
+          "Nothing to patch: '{}' doesn't exist.",
+          self.target
+        ))
+        .into(),
+      );
+    }
+
+    let contents = fs::read_to_string(&target).await.map_err(|source| {
+      ActionError::Io {
+        message: format!("Failed to read '{}'.", target.display()),
+        source,
+      }
+    })?;
+
+    // Inject values into strings before applying.
+    let sets = self
+      .sets
+      .iter()
+      .map(|PatchSet { path, value }| {
+        let value = match value {
+          | Value::String(string) => Value::String(state.template(string, DEFAULT_SEPARATOR)),
+          | value => value.clone(),
+        };
+
+        PatchSet { path: path.to_owned(), value }
+      })
+      .collect::<Vec<_>>();
+
+    let patched = format.apply(&contents, &sets).map_err(|failure| {
+      match failure {
+        | PatchFailure::Parse(err) => {
+          patch_error(format!("Failed to parse '{}': {err}", self.target))
+        },
+        | PatchFailure::Serialize(err) => {
+          patch_error(format!("Failed to serialize '{}': {err}", self.target))
+        },
+        | PatchFailure::InvalidPath(path) => {
+          patch_error(format!(
+            "Invalid path `{path}` in '{}': it doesn't point into a table or an existing array \
+             item.",
+            self.target
+          ))
+        },
+      }
+    })?;
+
+    backup.save(&target).await?;
+
+    fs::write(&target, patched).await.map_err(|source| {
+      ActionError::Io {
+        message: format!("Failed to write '{}'.", target.display()),
+        source,
+      }
+    })?;
+
+    output.event(Event::FileWritten { path: target });
+
+    for PatchSet { path, value } in &sets {
+      output.info(format!("└─ {path} = {}", value.to_json()));
+    }
+
+    output.brief(format!(
+      "⋅ Patched {} value(s): {}",
+      sets.len(),
+      &self.target.clone().dim()
+    ));
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::actions::Verbosity;
+  use crate::utils::temp::TempDir;
+
+  fn sets(sets: &[(&str, Value)]) -> Vec<PatchSet> {
+    sets
+      .iter()
+      .map(|(path, value)| {
+        PatchSet {
+          path: path.to_string(),
+          value: value.clone(),
+        }
+      })
+      .collect()
+  }
+
+  #[test]
+  fn patch_json() {
+    let input = r#"{ "name": "template", "scripts": { "build": "tsc" }, "files": ["dist"] }"#;
+
+    let output = Format::Json
+      .apply(
+        input,
+        &sets(&[
+          ("name", Value::String("app".to_string())),
+          ("scripts.test", Value::String("vitest".to_string())),
+          ("/files/0", Value::String("lib".to_string())),
+          ("private", Value::Bool(true)),
+        ]),
+      )
+      .unwrap();
+
+    let document: serde_json::Value = serde_json::from_str(&output).unwrap();
+
+    assert_eq!(
+      document,
+      serde_json::json!({
+        "name": "app",
+        "scripts": { "build": "tsc", "test": "vitest" },
+        "files": ["lib"],
+        "private": true
+      })
+    );
+
+    // Key order is preserved.
+    assert!(output.find("\"name\"").unwrap() < output.find("\"scripts\"").unwrap());
+  }
+
+  #[test]
+  fn patch_toml() {
+    let input = "[package]\nname = \"template\"\nversion = \"0.0.0\"\n";
+
+    let output = Format::Toml
+      .apply(
+        input,
+        &sets(&[
+          ("package.name", Value::String("app".to_string())),
+          ("package.rust-version", Value::Number(Number::Float(1.7))),
+          ("profile.release.lto", Value::Bool(true)),
+        ]),
+      )
+      .unwrap();
+
+    let document: toml::Value = toml::from_str(&output).unwrap();
+
+    assert_eq!(document["package"]["name"].as_str(), Some("app"));
+    assert_eq!(document["package"]["version"].as_str(), Some("0.0.0"));
+    assert_eq!(document["package"]["rust-version"].as_float(), Some(1.7));
+    assert_eq!(document["profile"]["release"]["lto"].as_bool(), Some(true));
+  }
+
+  #[test]
+  fn patch_toml_preserves_format() {
+    let input = r#"# Template manifest.
+[package]
+name = "template" # Replaced on scaffolding.
+version    = "0.0.0"
+authors = ["someone", "else"]
+
+[[bin]]
+name = "cli"
+
+[dependencies]
+serde = { version = "1", features = ["derive"] }
+"#;
+
+    let output = Format::Toml
+      .apply(
+        input,
+        &sets(&[
+          ("package.name", Value::String("app".to_string())),
+          ("package.authors.1", Value::String("me".to_string())),
+          ("bin.0.path", Value::String("src/main.rs".to_string())),
+          (
+            "dependencies.serde.version",
+            Value::String("1.0".to_string()),
+          ),
+        ]),
+      )
+      .unwrap();
+
+    assert_eq!(
+      output,
+      r#"# Template manifest.
+[package]
+name = "app" # Replaced on scaffolding.
+version    = "0.0.0"
+authors = ["someone", "me"]
+
+[[bin]]
+name = "cli"
+path = "src/main.rs"
+
+[dependencies]
+serde = { version = "1.0", features = ["derive"] }
+"#
+    );
+  }
+
+  #[test]
+  fn patch_json_preserves_indent() {
+    let input = "{\n\t\"name\": \"template\"\n}\n";
+
+    let output = Format::Json
+      .apply(input, &sets(&[("name", Value::String("app".to_string()))]))
+      .unwrap();
+
+    assert_eq!(output, "{\n\t\"name\": \"app\"\n}\n");
+  }
+
+  #[test]
+  fn patch_yaml() {
+    let input = "name: template\nreplicas: 1\n";
+
+    let output = Format::Yaml
+      .apply(
+        input,
+        &sets(&[
+          ("name", Value::String("app".to_string())),
+          ("replicas", Value::Number(Number::Integer(3))),
+        ]),
+      )
+      .unwrap();
+
+    assert_eq!(output, "name: app\nreplicas: 3\n");
+  }
+
+  #[test]
+  fn patch_invalid_path() {
+    let input = r#"{ "name": "template", "files": [] }"#;
+
+    let cases = ["name.nested", "files.0", "/files/x"];
+
+    for path in cases {
+      let result = Format::Json.apply(input, &sets(&[(path, Value::Bool(true))]));
+
+      assert!(matches!(result, Err(PatchFailure::InvalidPath(_))));
+    }
+  }
+
+  #[tokio::test]
+  #[cfg(unix)]
+  async fn patch_stays_in_root() {
+    let base = TempDir::new("patch-root");
+    let root = base.join("root");
+    let outside = base.join("package.json");
+
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(&outside, "{}").unwrap();
+
+    let patch = Patch {
+      target: outside.display().to_string(),
+      sets: sets(&[("name", Value::String("app".to_string()))]),
+    };
+
+    let result = patch
+      .execute(
+        &root,
+        &State::new(),
+        &Backup::disabled(),
+        &Output::capture(Verbosity::Quiet),
+      )
+      .await;
+
+    assert!(result.is_err());
+    assert_eq!(std::fs::read_to_string(&outside).unwrap(), "{}");
+  }
+}
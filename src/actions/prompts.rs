@@ -1,117 +1,691 @@
-use inquire::{Confirm, CustomType, Editor, Select, Text};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
 
-use crate::actions::State;
+use inquire::autocompletion::{Autocomplete, Replacement};
+use inquire::validator::Validation;
+use inquire::{Confirm, CustomType, CustomUserError, Editor, Select, Text};
+
+use crate::actions::{Output, State};
+use crate::config::actions::Prompt;
 use crate::config::prompts::*;
-use crate::config::{Number, Value};
+use crate::config::{Number, Value, DEFAULT_SEPARATOR};
 use crate::utils::prompts as helpers;
 
+impl Prompt {
+  /// Answers the prompt with its default value without asking. Fails if there's no default value.
+  pub fn execute_default(&self, state: &mut State, output: &Output) -> miette::Result<()> {
+    let name = self.name();
+
+    let value = match self {
+      | Self::Confirm(prompt) => prompt.default_value()?.map(Value::Bool),
+      | Self::Editor(prompt) => prompt.default_value(state)?.map(Value::String),
+      | Self::Input(prompt) => prompt.default_value(state).map(Value::String),
+      | Self::Path(prompt) => {
+        prompt
+          .default_value(state)
+          .map(|path| prompt.validated(path))
+          .transpose()?
+          .map(Value::String)
+      },
+      | Self::Number(prompt) => prompt.default_value()?.map(Value::Number),
+      | Self::Select(prompt) => {
+        let options = prompt.interpolated_options(state);
+
+        prompt
+          .default_index(&options, state)
+          .map(|index| SelectPrompt::value_of(options[index].clone()))
+      },
+    };
+
+    let value = value.ok_or_else(|| {
+      miette::miette!(
+        code = "decaff::actions::prompts::non_interactive",
+        help = "Provide a default value for this prompt or run interactively.",
+        "Prompt `{name}` has no default value and can't be answered in non-interactive mode."
+      )
+    })?;
+
+    helpers::answer(state, name, value, output);
+
+    Ok(())
+  }
+
+  /// Answers the prompt with the value already present in the state (e.g. from `--set`) without
+  /// asking, coercing it to the type of the prompt.
+  pub fn execute_preset(&self, state: &mut State, output: &Output) -> miette::Result<()> {
+    let name = self.name();
+    let preset = state.get_string(name).unwrap_or_default();
+
+    let invalid = |expected: &str| {
+      miette::miette!(
+        code = "decaff::actions::prompts::preset",
+        "Preset value `{preset}` for prompt `{name}` is not {expected}."
+      )
+    };
+
+    let value = match self {
+      | Self::Confirm(_) => Value::Bool(state.get_bool(name).ok_or_else(|| invalid("a boolean"))?),
+      | Self::Number(_) => {
+        Value::Number(state.get_number(name).ok_or_else(|| invalid("a number"))?)
+      },
+      | Self::Select(prompt) => {
+        let option = preset.clone();
+
+        if !prompt
+          .options
+          .iter()
+          .any(|it| state.template(it, DEFAULT_SEPARATOR) == option)
+        {
+          return Err(invalid("one of the options"));
+        }
+
+        SelectPrompt::value_of(option)
+      },
+      | Self::Path(prompt) => Value::String(prompt.validated(preset)?),
+      | Self::Editor(_) | Self::Input(_) => Value::String(preset),
+    };
+
+    helpers::answer(state, name, value, output);
+
+    Ok(())
+  }
+}
+
+/// Reads the environment variable a prompt takes its default value from, if it's specified and set.
+fn env_default(env: &Option<String>) -> Option<(&str, String)> {
+  let var = env.as_deref()?;
+  std::env::var(var).ok().map(|value| (var, value))
+}
+
+/// Creates an error for an environment variable that can't be coerced to the type of the prompt.
+fn env_invalid(var: &str, value: &str, name: &str, expected: &str) -> miette::Report {
+  miette::miette!(
+    code = "decaff::actions::prompts::env",
+    "Environment variable `{var}` with value `{value}` for prompt `{name}` is not {expected}."
+  )
+}
+
 impl ConfirmPrompt {
-  pub async fn execute(&self, state: &mut State) -> miette::Result<()> {
+  /// Returns the default value, taking it from the environment variable if it's set.
+  fn default_value(&self) -> miette::Result<Option<bool>> {
+    match env_default(&self.env) {
+      | Some((var, value)) => {
+        value
+          .parse()
+          .map(Some)
+          .map_err(|_| env_invalid(var, &value, &self.name, "a boolean"))
+      },
+      | None => Ok(self.default),
+    }
+  }
+
+  /// Asks the prompt and stores the answer. Returns `false` if the prompt wasn't answered within
+  /// the timeout.
+  pub async fn execute(
+    &self,
+    state: &mut State,
+    timeout: Option<Duration>,
+  ) -> miette::Result<bool> {
     let (name, hint, help) = helpers::messages(&self.name, &self.hint);
 
-    let mut prompt = Confirm::new(&hint)
-      .with_help_message(&help)
-      .with_render_config(helpers::theme());
+    let default = self.default_value()?;
 
-    if let Some(default) = self.default {
-      prompt = prompt.with_default(default);
-    }
+    let answer = helpers::prompt_with_timeout(timeout, &hint, || {
+      let mut prompt = Confirm::new(&hint)
+        .with_help_message(&help)
+        .with_render_config(helpers::theme());
+
+      if let Some(default) = default {
+        prompt = prompt.with_default(default);
+      }
 
-    match prompt.prompt() {
-      | Ok(value) => state.set(name, Value::Bool(value)),
-      | Err(err) => helpers::interrupt(err),
+      prompt.prompt()
+    });
+
+    match answer {
+      | Ok(Some(value)) => state.set(name, Value::Bool(value)),
+      | Ok(None) => return Ok(false),
+      | Err(err) => return Err(helpers::interrupt(err).into()),
     }
 
-    Ok(())
+    Ok(true)
   }
 }
 
 impl InputPrompt {
-  pub async fn execute(&self, state: &mut State) -> miette::Result<()> {
+  /// Returns the default value, taking it from the environment variable if it's set. Only the
+  /// static default is interpolated.
+  fn default_value(&self, state: &State) -> Option<String> {
+    env_default(&self.env).map(|(_, value)| value).or_else(|| {
+      self
+        .default
+        .as_ref()
+        .map(|default| state.template(default, DEFAULT_SEPARATOR))
+    })
+  }
+
+  /// Asks the prompt and stores the answer. Returns `false` if the prompt wasn't answered within
+  /// the timeout.
+  pub async fn execute(
+    &self,
+    state: &mut State,
+    timeout: Option<Duration>,
+  ) -> miette::Result<bool> {
     let (name, hint, help) = helpers::messages(&self.name, &self.hint);
 
-    let mut prompt = Text::new(&hint)
-      .with_help_message(&help)
-      .with_formatter(helpers::empty_formatter())
-      .with_render_config(helpers::theme());
+    let default = self.default_value(state);
 
-    if let Some(default) = &self.default {
-      prompt = prompt.with_default(default);
-    } else {
-      prompt = prompt.with_validator(inquire::required!("This field is required."));
-    }
+    let answer = helpers::prompt_with_timeout(timeout, &hint, || {
+      let mut prompt = Text::new(&hint)
+        .with_help_message(&help)
+        .with_formatter(helpers::empty_formatter())
+        .with_render_config(helpers::theme());
+
+      if let Some(default) = &default {
+        prompt = prompt.with_default(default);
+      } else {
+        prompt = prompt.with_validator(inquire::required!("This field is required."));
+      }
 
-    match prompt.prompt() {
-      | Ok(value) => state.set(name, Value::String(value)),
-      | Err(err) => helpers::interrupt(err),
+      prompt.prompt()
+    });
+
+    match answer {
+      | Ok(Some(value)) => state.set(name, Value::String(value)),
+      | Ok(None) => return Ok(false),
+      | Err(err) => return Err(helpers::interrupt(err).into()),
     }
 
-    Ok(())
+    Ok(true)
   }
 }
 
 impl NumberPrompt {
-  pub async fn execute(&self, state: &mut State) -> miette::Result<()> {
+  /// Returns the default value, taking it from the environment variable if it's set.
+  fn default_value(&self) -> miette::Result<Option<Number>> {
+    match env_default(&self.env) {
+      | Some((var, value)) => {
+        value
+          .parse()
+          .map(Some)
+          .map_err(|_| env_invalid(var, &value, &self.name, "a number"))
+      },
+      | None => Ok(self.default.clone()),
+    }
+  }
+
+  /// Asks the prompt and stores the answer. Returns `false` if the prompt wasn't answered within
+  /// the timeout.
+  pub async fn execute(
+    &self,
+    state: &mut State,
+    timeout: Option<Duration>,
+  ) -> miette::Result<bool> {
     let (name, hint, help) = helpers::messages(&self.name, &self.hint);
 
-    let mut prompt = CustomType::<Number>::new(&hint)
-      .with_help_message(&help)
-      .with_formatter(&|input| input.to_string())
-      .with_render_config(helpers::theme());
-
-    if let Some(default) = &self.default {
-      prompt = prompt.with_default(default.to_owned());
-    } else {
-      // NOTE: This is a bit confusing, but essentially this message will be showed when no input
-      // was provided by the user.
-      prompt = prompt.with_error_message("This field is required.");
-    }
+    let default = self.default_value()?;
+
+    let answer = helpers::prompt_with_timeout(timeout, &hint, || {
+      let mut prompt = CustomType::<Number>::new(&hint)
+        .with_help_message(&help)
+        .with_formatter(&|input| input.to_string())
+        .with_render_config(helpers::theme());
+
+      if let Some(default) = default {
+        prompt = prompt.with_default(default);
+      } else {
+        // NOTE: This is a bit confusing, but essentially this message will be showed when no input
+        // was provided by the user.
+        prompt = prompt.with_error_message("This field is required.");
+      }
+
+      prompt.prompt()
+    });
 
-    match prompt.prompt() {
-      | Ok(value) => state.set(name, Value::Number(value)),
-      | Err(err) => helpers::interrupt(err),
+    match answer {
+      | Ok(Some(value)) => state.set(name, Value::Number(value)),
+      | Ok(None) => return Ok(false),
+      | Err(err) => return Err(helpers::interrupt(err).into()),
     }
 
-    Ok(())
+    Ok(true)
   }
 }
 
 impl SelectPrompt {
-  pub async fn execute(&self, state: &mut State) -> miette::Result<()> {
+  /// Converts the selected option into a value. Options that are numbers, e.g. ports, are stored
+  /// as [Value::Number], as long as they format back exactly the same (so `080` or `1.50` stay
+  /// strings). Everything else is stored as [Value::String].
+  fn value_of(option: String) -> Value {
+    match option.parse::<Number>() {
+      | Ok(Number::Float(float)) if !float.is_finite() => Value::String(option),
+      | Ok(number) if number.to_string() == option => Value::Number(number),
+      | _ => Value::String(option),
+    }
+  }
+
+  /// Returns options with values from the state interpolated.
+  fn interpolated_options(&self, state: &State) -> Vec<String> {
+    self
+      .options
+      .iter()
+      .map(|option| state.template(option, DEFAULT_SEPARATOR))
+      .collect()
+  }
+
+  /// Returns the index of the default option among the given (interpolated) options, if any. The
+  /// default option is taken from the environment variable if it's set.
+  fn default_index(&self, options: &[String], state: &State) -> Option<usize> {
+    let default = match env_default(&self.env) {
+      | Some((_, value)) => value,
+      | None => state.template(self.default.as_ref()?, DEFAULT_SEPARATOR),
+    };
+
+    options.iter().position(|option| *option == default)
+  }
+
+  /// Asks the prompt and stores the answer. Returns `false` if the prompt wasn't answered within
+  /// the timeout.
+  pub async fn execute(
+    &self,
+    state: &mut State,
+    timeout: Option<Duration>,
+  ) -> miette::Result<bool> {
     let (name, hint, help) = helpers::messages(&self.name, &self.hint);
 
-    let options = self.options.iter().map(String::to_string).collect();
+    let options = self.interpolated_options(state);
+    let default = self.default_index(&options, state);
+
+    let answer = helpers::prompt_with_timeout(timeout, &hint, || {
+      let mut prompt = Select::new(&hint, options)
+        .with_help_message(&help)
+        .with_render_config(helpers::theme());
 
-    let prompt = Select::new(&hint, options)
-      .with_help_message(&help)
-      .with_render_config(helpers::theme());
+      if let Some(index) = default {
+        prompt = prompt.with_starting_cursor(index);
+      }
 
-    match prompt.prompt() {
-      | Ok(value) => state.set(name, Value::String(value)),
-      | Err(err) => helpers::interrupt(err),
+      prompt.prompt()
+    });
+
+    match answer {
+      | Ok(Some(value)) => state.set(name, Self::value_of(value)),
+      | Ok(None) => return Ok(false),
+      | Err(err) => return Err(helpers::interrupt(err).into()),
     }
 
-    Ok(())
+    Ok(true)
   }
 }
 
 impl EditorPrompt {
-  pub async fn execute(&self, state: &mut State) -> miette::Result<()> {
+  /// Returns the interpolated default value, reading it from `default_file` if set. Fails if the
+  /// file can't be read.
+  fn default_value(&self, state: &State) -> miette::Result<Option<String>> {
+    let default = match &self.default_file {
+      | Some(path) => {
+        Some(fs::read_to_string(path).map_err(|err| {
+          miette::miette!(
+            code = "decaff::actions::prompts::default_file",
+            "Failed to read the default value of prompt `{}` from '{}': {err}.",
+            self.name,
+            path.display()
+          )
+        })?)
+      },
+      | None => self.default.clone(),
+    };
+
+    Ok(default.map(|default| state.template(&default, DEFAULT_SEPARATOR)))
+  }
+
+  /// Asks the prompt and stores the answer. Returns `false` if the prompt wasn't answered within
+  /// the timeout.
+  pub async fn execute(
+    &self,
+    state: &mut State,
+    timeout: Option<Duration>,
+  ) -> miette::Result<bool> {
+    let (name, hint, help) = helpers::messages(&self.name, &self.hint);
+
+    let default = self.default_value(state)?;
+
+    let answer = helpers::prompt_with_timeout(timeout, &hint, || {
+      let mut prompt = Editor::new(&hint)
+        .with_help_message(&help)
+        .with_render_config(helpers::theme());
+
+      if let Some(default) = &default {
+        prompt = prompt.with_predefined_text(default);
+      }
+
+      prompt.prompt()
+    });
+
+    match answer {
+      | Ok(Some(value)) => state.set(name, Value::String(value)),
+      | Ok(None) => return Ok(false),
+      | Err(err) => return Err(helpers::interrupt(err).into()),
+    }
+
+    Ok(true)
+  }
+}
+
+impl PathPrompt {
+  /// Returns the default value, taking it from the environment variable if it's set. Only the
+  /// static default is interpolated.
+  fn default_value(&self, state: &State) -> Option<String> {
+    env_default(&self.env).map(|(_, value)| value).or_else(|| {
+      self
+        .default
+        .as_ref()
+        .map(|default| state.template(default, DEFAULT_SEPARATOR))
+    })
+  }
+
+  /// Checks the path against the `must_exist` and `kind` attributes. Returns the reason if it
+  /// doesn't satisfy them.
+  fn check(path: &str, must_exist: bool, kind: PathKind) -> Result<(), String> {
+    let path = Path::new(path);
+
+    if !path.exists() {
+      return if must_exist {
+        Err("This path doesn't exist.".to_string())
+      } else {
+        Ok(())
+      };
+    }
+
+    match kind {
+      | PathKind::File if !path.is_file() => Err("This path is not a file.".to_string()),
+      | PathKind::Dir if !path.is_dir() => Err("This path is not a directory.".to_string()),
+      | _ => Ok(()),
+    }
+  }
+
+  /// Returns the path if it satisfies the `must_exist` and `kind` attributes, otherwise fails.
+  fn validated(&self, path: String) -> miette::Result<String> {
+    Self::check(&path, self.must_exist, self.kind).map_err(|reason| {
+      miette::miette!(
+        code = "decaff::actions::prompts::path",
+        "Invalid path `{path}` for prompt `{}`. {reason}",
+        self.name
+      )
+    })?;
+
+    Ok(path)
+  }
+
+  /// Asks the prompt and stores the answer. Returns `false` if the prompt wasn't answered within
+  /// the timeout.
+  pub async fn execute(
+    &self,
+    state: &mut State,
+    timeout: Option<Duration>,
+  ) -> miette::Result<bool> {
     let (name, hint, help) = helpers::messages(&self.name, &self.hint);
 
-    let mut prompt = Editor::new(&hint)
-      .with_help_message(&help)
-      .with_render_config(helpers::theme());
+    let default = self.default_value(state);
+    let must_exist = self.must_exist;
+    let kind = self.kind;
 
-    if let Some(default) = &self.default {
-      prompt = prompt.with_predefined_text(default);
+    let answer = helpers::prompt_with_timeout(timeout, &hint, || {
+      let mut prompt = Text::new(&hint)
+        .with_help_message(&help)
+        .with_formatter(helpers::empty_formatter())
+        .with_autocomplete(PathCompleter { kind })
+        .with_validator(move |input: &str| {
+          Ok(match Self::check(input, must_exist, kind) {
+            | Ok(()) => Validation::Valid,
+            | Err(reason) => Validation::Invalid(reason.into()),
+          })
+        })
+        .with_render_config(helpers::theme());
+
+      if let Some(default) = &default {
+        prompt = prompt.with_default(default);
+      } else {
+        prompt = prompt.with_validator(inquire::required!("This field is required."));
+      }
+
+      prompt.prompt()
+    });
+
+    match answer {
+      | Ok(Some(value)) => state.set(name, Value::String(value)),
+      | Ok(None) => return Ok(false),
+      | Err(err) => return Err(helpers::interrupt(err).into()),
     }
 
-    match prompt.prompt() {
-      | Ok(value) => state.set(name, Value::String(value)),
-      | Err(err) => helpers::interrupt(err),
+    Ok(true)
+  }
+}
+
+/// Suggests existing filesystem entries for the path typed so far.
+#[derive(Clone)]
+struct PathCompleter {
+  kind: PathKind,
+}
+
+impl PathCompleter {
+  /// Returns entries of the typed directory starting with the typed file name, sorted. Directories
+  /// end with a `/`, and files are left out if only directories are expected. Hidden entries are
+  /// only suggested once the file name starts with a dot.
+  fn suggestions(&self, input: &str) -> Vec<String> {
+    let (dir, prefix) = match input.rfind('/') {
+      | Some(index) => input.split_at(index + 1),
+      | None => ("", input),
+    };
+
+    let Ok(entries) = fs::read_dir(if dir.is_empty() { "." } else { dir }) else {
+      return Vec::new();
+    };
+
+    let mut suggestions = entries
+      .flatten()
+      .filter_map(|entry| {
+        let name = entry.file_name().into_string().ok()?;
+        let is_dir = entry.path().is_dir();
+
+        if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+          return None;
+        }
+
+        match (is_dir, self.kind) {
+          | (true, _) => Some(format!("{dir}{name}/")),
+          | (false, PathKind::Dir) => None,
+          | (false, _) => Some(format!("{dir}{name}")),
+        }
+      })
+      .collect::<Vec<_>>();
+
+    suggestions.sort();
+    suggestions
+  }
+}
+
+impl Autocomplete for PathCompleter {
+  fn get_suggestions(&mut self, input: &str) -> Result<Vec<String>, CustomUserError> {
+    Ok(self.suggestions(input))
+  }
+
+  /// Completes to the highlighted suggestion, or to the longest prefix shared by all suggestions.
+  fn get_completion(
+    &mut self,
+    input: &str,
+    highlighted: Option<String>,
+  ) -> Result<Replacement, CustomUserError> {
+    if highlighted.is_some() {
+      return Ok(highlighted);
     }
 
-    Ok(())
+    let suggestions = self.suggestions(input);
+
+    let common = suggestions.iter().skip(1).fold(
+      suggestions.first().cloned().unwrap_or_default(),
+      |common, suggestion| {
+        common
+          .chars()
+          .zip(suggestion.chars())
+          .take_while(|(a, b)| a == b)
+          .map(|(a, _)| a)
+          .collect()
+      },
+    );
+
+    Ok((common.len() > input.len()).then_some(common))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::path::PathBuf;
+
+  use super::*;
+  use crate::utils::temp::TempDir;
+
+  #[test]
+  fn select_value_preserves_numbers() {
+    let cases = [
+      ("8080", r#"8080"#),
+      ("0.5", r#"0.5"#),
+      ("-1", r#"-1"#),
+      ("080", r#""080""#),
+      ("1.50", r#""1.50""#),
+      ("inf", r#""inf""#),
+      ("pnpm", r#""pnpm""#),
+    ];
+
+    for (option, expected) in cases {
+      assert_eq!(
+        SelectPrompt::value_of(option.to_string()).to_json(),
+        expected
+      );
+    }
+  }
+
+  #[test]
+  fn select_default_index() {
+    let mut state = State::new();
+
+    state.set("PM", Value::String("pnpm".to_string()));
+
+    let prompt = |default: &str| {
+      SelectPrompt {
+        name: "pm".to_string(),
+        hint: "Package manager".to_string(),
+        options: vec!["npm".to_string(), "{PM}".to_string()],
+        default: Some(default.to_string()),
+        env: None,
+      }
+    };
+
+    let cases = [
+      ("npm", Some(0)),
+      ("pnpm", Some(1)),
+      ("{PM}", Some(1)),
+      ("bun", None),
+    ];
+
+    for (default, expected) in cases {
+      let prompt = prompt(default);
+      let options = prompt.interpolated_options(&state);
+
+      assert_eq!(prompt.default_index(&options, &state), expected);
+    }
+  }
+
+  #[test]
+  fn env_overrides_default() {
+    std::env::set_var("DECAFF_TEST_PROMPT_PORT", "8080");
+    std::env::set_var("DECAFF_TEST_PROMPT_INVALID", "eighty");
+
+    let prompt = |env: &str| {
+      NumberPrompt {
+        name: "port".to_string(),
+        hint: "Port".to_string(),
+        default: Some(Number::Integer(3000)),
+        env: Some(env.to_string()),
+      }
+    };
+
+    let value = |prompt: NumberPrompt| prompt.default_value().map(|it| it.map(|it| it.to_string()));
+
+    assert_eq!(
+      value(prompt("DECAFF_TEST_PROMPT_PORT")).unwrap(),
+      Some("8080".to_string())
+    );
+    assert_eq!(
+      value(prompt("DECAFF_TEST_PROMPT_UNSET")).unwrap(),
+      Some("3000".to_string())
+    );
+    assert!(value(prompt("DECAFF_TEST_PROMPT_INVALID")).is_err());
+  }
+
+  #[test]
+  fn editor_default_from_file() {
+    let root = TempDir::new("editor");
+    let path = root.join("README.md");
+    fs::write(&path, "# {NAME}\n\nDescription.\n").unwrap();
+
+    let prompt = |default_file: Option<PathBuf>| {
+      EditorPrompt {
+        name: "readme".to_string(),
+        hint: "Readme".to_string(),
+        default: Some("inline".to_string()),
+        default_file,
+      }
+    };
+
+    let mut state = State::new();
+    state.set("NAME", Value::String("decaff".to_string()));
+
+    assert_eq!(
+      prompt(Some(path.clone())).default_value(&state).unwrap(),
+      Some("# decaff\n\nDescription.\n".to_string())
+    );
+    assert_eq!(
+      prompt(None).default_value(&state).unwrap(),
+      Some("inline".to_string())
+    );
+
+    fs::remove_file(&path).unwrap();
+
+    assert!(prompt(Some(path)).default_value(&state).is_err());
+  }
+
+  #[test]
+  fn path_suggestions_and_checks() {
+    let root = TempDir::new("path");
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::create_dir_all(root.join(".git")).unwrap();
+    fs::write(root.join("setup.sh"), "").unwrap();
+
+    let dir = format!("{}/", root.display());
+    let completer = |kind| PathCompleter { kind };
+
+    assert_eq!(
+      completer(PathKind::Any).suggestions(&format!("{dir}s")),
+      vec![format!("{dir}setup.sh"), format!("{dir}src/")]
+    );
+    assert_eq!(
+      completer(PathKind::Dir).suggestions(&dir),
+      vec![format!("{dir}src/")]
+    );
+    assert_eq!(
+      completer(PathKind::Any).suggestions(&format!("{dir}.")),
+      vec![format!("{dir}.git/")]
+    );
+
+    let file = format!("{dir}setup.sh");
+    let missing = format!("{dir}missing");
+
+    assert!(PathPrompt::check(&file, true, PathKind::File).is_ok());
+    assert!(PathPrompt::check(&file, true, PathKind::Dir).is_err());
+    assert!(PathPrompt::check(&dir, true, PathKind::Dir).is_ok());
+    assert!(PathPrompt::check(&missing, false, PathKind::File).is_ok());
+    assert!(PathPrompt::check(&missing, true, PathKind::Any).is_err());
   }
 }
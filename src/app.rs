@@ -1,18 +1,40 @@
+use std::collections::HashMap;
+use std::env;
 use std::fs;
-use std::io;
+use std::io::{self, IsTerminal, Read};
+use std::iter;
+use std::mem;
 use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
 
-use clap::{Args, Parser, Subcommand};
-use crossterm::style::Stylize;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use inquire::validator::Validation;
+use inquire::{Select, Text};
 use miette::Diagnostic;
 use thiserror::Error;
+use tokio::sync::Semaphore;
+use tokio::task::{self, JoinSet};
 
-use crate::actions::Executor;
-use crate::cache::Cache;
-use crate::config::{Config, ConfigOptionsOverrides};
+use crate::actions::{
+  ActionError, Backup, Event, Executor, ExecutorOptions, Output, OutputFormat, Restored, State,
+  Verbosity,
+};
+use crate::cache::{format_size, Cache};
+use crate::config::{Config, ConfigError, ConfigOptionsOverrides, Value};
+use crate::path::{expand, PathClean};
 use crate::report;
-use crate::repository::{LocalRepository, RemoteRepository};
-use crate::unpacker::Unpacker;
+use crate::repository::{
+  Client, FetchError, LocalRepository, ParseError, Release, RemoteError, RemoteRepository,
+  RepositoryHost, RepositoryMeta, DEFAULT_TIMEOUT,
+};
+use crate::unpacker::{Unpacker, DEFAULT_STRIP_COMPONENTS};
+use crate::utils::net::{self, PROBE_TIMEOUT};
+use crate::utils::prompts::{self as helpers, PromptError};
+use crate::utils::style::{self, Stylize};
+use crate::utils::terminal;
 
 #[derive(Debug, Diagnostic, Error)]
 pub enum AppError {
@@ -23,6 +45,91 @@ pub enum AppError {
     #[source]
     source: io::Error,
   },
+  #[error("Failed to scaffold: '{path}' already exists.")]
+  #[diagnostic(
+    code(decaff::app::exists),
+    help("Use `--force` to scaffold into the existing directory.")
+  )]
+  DestinationExists { path: String },
+}
+
+/// Exit codes for different classes of failures. These are stable, so scripts can rely on them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExitCode {
+  /// Any failure that doesn't fall into other classes.
+  Failure = 1,
+  /// Invalid arguments, repository source or config. Same as the code of CLI usage errors.
+  Config = 2,
+  /// Network failures, e.g. being offline or failing to fetch the repository.
+  Network = 3,
+  /// Failure of an action.
+  Action = 4,
+  /// Destination already exists.
+  DestinationExists = 5,
+  /// A prompt was cancelled or interrupted. Same as the code of processes killed by `SIGINT`.
+  Cancelled = 130,
+}
+
+impl ExitCode {
+  /// Classifies the error by its type, falling back to its diagnostic code for errors that were
+  /// created ad hoc.
+  pub fn of(err: &miette::Report) -> Self {
+    if let Some(true) = err
+      .downcast_ref::<PromptError>()
+      .map(PromptError::is_cancellation)
+    {
+      return Self::Cancelled;
+    }
+
+    if let Some(AppError::DestinationExists { .. }) = err.downcast_ref::<AppError>() {
+      return Self::DestinationExists;
+    }
+
+    if err.is::<FetchError>() || err.is::<RemoteError>() {
+      return Self::Network;
+    }
+
+    if err.is::<ConfigError>() || err.is::<ParseError>() {
+      return Self::Config;
+    }
+
+    if err.is::<ActionError>() {
+      return Self::Action;
+    }
+
+    let code = err.code().map(|code| code.to_string()).unwrap_or_default();
+
+    match code.as_str() {
+      | "decaff::app::offline" => Self::Network,
+      | "decaff::app::values" | "decaff::app::check" | "decaff::app::path_spec" => Self::Config,
+      | code if code.starts_with("decaff::config::") => Self::Config,
+      | code if code.starts_with("decaff::actions::") => Self::Action,
+      | _ => Self::Failure,
+    }
+  }
+}
+
+/// When to use colors in the output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum ColorChoice {
+  /// Use colors if stdout is a terminal and `NO_COLOR` is not set.
+  #[default]
+  Auto,
+  /// Always use colors, even if `NO_COLOR` is set.
+  Always,
+  /// Never use colors.
+  Never,
+}
+
+impl ColorChoice {
+  /// Checks if colors should be used, given whether `NO_COLOR` is set and stdout is a terminal.
+  fn is_enabled(self, no_color: bool, is_terminal: bool) -> bool {
+    match self {
+      | Self::Auto => !no_color && is_terminal,
+      | Self::Always => true,
+      | Self::Never => false,
+    }
+  }
 }
 
 #[derive(Debug, Default)]
@@ -32,6 +139,13 @@ pub struct AppState {
   /// Clean up path, will be set to the destination acquired after creating [RemoteRepository] or
   /// [LocalRepository].
   pub cleanup_path: Option<PathBuf>,
+  /// Backup of files overwritten or deleted by actions. Only set when scaffolding into an existing
+  /// directory, and restored on failure.
+  pub backup: Option<Arc<Backup>>,
+  /// Output sink for messages of the app itself, set up from the scaffolding arguments.
+  pub output: Output,
+  /// Paths created when unpacking, copying or checking out the template, before running actions.
+  pub created: Vec<PathBuf>,
 }
 
 #[derive(Clone, Debug, Parser)]
@@ -39,7 +153,7 @@ pub struct AppState {
 pub enum Cli {
   /// Scaffold from a remote repository.
   #[command(visible_alias = "r")]
-  Remote(RepositoryArgs),
+  Remote(RemoteArgs),
   /// Scaffold from a local repository.
   #[command(visible_alias = "l")]
   Local(RepositoryArgs),
@@ -48,46 +162,231 @@ pub enum Cli {
   Cache {
     #[command(subcommand)]
     command: CacheCommand,
+    /// When to use colors in the output.
+    #[arg(long, value_enum, default_value_t, global = true)]
+    color: ColorChoice,
   },
+  /// Check a config for errors without running any actions.
+  #[command(visible_alias = "doctor")]
+  Check {
+    /// Directory containing the config. Defaults to the current directory.
+    path: Option<PathBuf>,
+    /// When to use colors in the output.
+    #[arg(long, value_enum, default_value_t)]
+    color: ColorChoice,
+  },
+}
+
+impl Cli {
+  /// Returns the color choice of the command.
+  fn color(&self) -> ColorChoice {
+    match self {
+      | Cli::Remote(RemoteArgs { repository, .. }) | Cli::Local(repository) => repository.color,
+      | Cli::Cache { color, .. } | Cli::Check { color, .. } => *color,
+    }
+  }
 }
 
 #[derive(Clone, Debug, Args)]
 pub struct RepositoryArgs {
-  /// Repository to use for scaffolding.
+  /// Repository to use for scaffolding. For remote scaffolding, a local tarball can be given as
+  /// `file:<path>`, or `-` to read it from stdin, which implies `--yes`. If omitted for remote
+  /// scaffolding, the repository is picked interactively.
+  #[arg(default_value_t)]
   src: String,
   /// Directory to scaffold to.
   path: Option<String>,
-  /// Scaffold from a specified ref (branch, tag, or commit).
+  /// Scaffold from a specified ref (branch, tag, or commit). For remote repositories, a glob like
+  /// `v*` picks the latest matching tag, skipping prereleases unless the glob contains a `-`, e.g.
+  /// `v2.0.0-*`. For GitHub, `latest` picks the tag of the latest release,
+  /// falling back to the default branch if there are no releases.
   #[arg(name = "REF", short = 'r', long = "ref")]
   meta: Option<String>,
-  /// Clean up on failure. No-op if failed because target directory already exists.
+  /// Clean up on failure. No-op if failed because target directory already exists, or if
+  /// scaffolding into an existing directory.
   #[arg(short = 'C', long)]
   cleanup: bool,
+  /// Scaffold into an existing directory, merging with its contents. Implied if the directory to
+  /// scaffold to is the current directory.
+  #[arg(short, long)]
+  force: bool,
+  /// Create missing parent directories of the directory to scaffold to. They are removed along
+  /// with the directory when cleaning up on failure.
+  #[arg(short, long)]
+  parents: bool,
   /// Delete config after scaffolding is complete.
   #[arg(short, long)]
   delete: Option<bool>,
-  /// Skip reading config and running actions.
-  #[arg(short, long)]
+  /// Initialize a git repository and make the initial commit after scaffolding is complete.
+  #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+  git_init: Option<bool>,
+  /// Keep the git history of a local repository, including its branches, instead of scaffolding
+  /// only the files of the checked out ref.
+  #[arg(long)]
+  keep_git: bool,
+  /// Scaffold only the given path of a local repository or directory, relative to its root, e.g.
+  /// `templates/service-a`. Can be repeated. The paths are kept as-is in the destination.
+  #[arg(long = "path-spec", value_name = "PATH")]
+  path_specs: Vec<PathBuf>,
+  /// Run only suites tagged with the given tag. Can be repeated to run suites with any of the
+  /// tags. Untagged suites are always run.
+  #[arg(long = "tag", value_name = "TAG")]
+  tags: Vec<String>,
+  /// Only fetch and unpack (or copy) the template, leaving its config intact. The config isn't
+  /// read, and actions are not run.
+  #[arg(
+    short,
+    long,
+    visible_alias = "extract-only",
+    conflicts_with_all = ["delete", "git_init", "tags", "presets", "values", "save_answers", "prompt_timeout"]
+  )]
   skip: bool,
+  /// Preset a prompt answer. Prompts with preset answers are not asked. Can be repeated.
+  #[arg(long = "set", value_name = "NAME=VALUE", value_parser = parse_preset)]
+  presets: Vec<(String, String)>,
+  /// Preset prompt answers from a TOML or JSON file. Values from `--set` take precedence.
+  #[arg(long, value_name = "FILE")]
+  values: Option<PathBuf>,
+  /// Save prompt answers to a file after running actions. Saved as TOML if the file has the
+  /// `.toml` extension, otherwise as JSON.
+  #[arg(long, value_name = "FILE")]
+  save_answers: Option<PathBuf>,
+  /// Skip prompts and use their default values. Fails if a prompt has no default value.
+  #[arg(short, long, visible_alias = "non-interactive")]
+  yes: bool,
+  /// Fall back to the default value of a prompt if answering it doesn't start within the given
+  /// number of seconds. Fails if a prompt has no default value.
+  #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+  prompt_timeout: Option<u64>,
+  /// Print only errors, prompts and brief summaries of actions.
+  #[arg(short, long, conflicts_with = "verbose")]
+  quiet: bool,
+  /// Print additional details of actions.
+  #[arg(short, long)]
+  verbose: bool,
+  /// Output format. The `json` format prints newline-delimited JSON events instead of text, and
+  /// implies `--yes`, so prompts have to be preset or have default values.
+  #[arg(long, value_enum, default_value_t)]
+  format: OutputFormat,
+  /// When to use colors in the output.
+  #[arg(long, value_enum, default_value_t)]
+  color: ColorChoice,
   /// Use cached template if available.
   #[arg(short = 'c', long, default_value = "true")]
   cache: bool,
+  /// Don't write a freshly fetched template to the cache. Cached templates can still be used.
+  #[arg(long)]
+  no_cache_write: bool,
+  /// Disable network access of actions, so actions like `fetch` fail instead.
+  #[arg(long)]
+  offline: bool,
+  /// Host and port to probe connectivity with if fetching refs of a remote template fails, to tell
+  /// if we're offline. Defaults to the repository host on port 443.
+  #[arg(long, value_name = "HOST:PORT", value_parser = parse_probe)]
+  probe: Option<(String, u16)>,
+  /// Number of leading path components to strip when unpacking a remote tarball.
+  #[arg(long, default_value_t = DEFAULT_STRIP_COMPONENTS)]
+  strip: usize,
+}
+
+#[derive(Clone, Debug, Args)]
+pub struct RemoteArgs {
+  #[command(flatten)]
+  repository: RepositoryArgs,
+  /// Scaffold another remote template after the first one, into a directory named after its
+  /// repository. Other options apply to every template, except for `--ref`, `--set`, `--values`
+  /// and `--save-answers`, which apply to the first one only. Can be repeated.
+  #[arg(long = "also", value_name = "SRC")]
+  also: Vec<String>,
+  /// Maximum number of tarballs to download concurrently when scaffolding multiple templates.
+  /// Prompts and actions are still run one template at a time.
+  #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+  max_concurrent_downloads: u64,
+  /// Timeout of network requests in seconds, including the ones of `fetch` actions: for
+  /// connecting to the host, and for the host to respond or send the next chunk of data.
+  #[arg(
+    long,
+    value_name = "SECONDS",
+    default_value_t = DEFAULT_TIMEOUT.as_secs(),
+    value_parser = clap::value_parser!(u64).range(1..)
+  )]
+  timeout: u64,
+  /// Print supported hosts along with their aliases, and exit.
+  #[arg(long, exclusive = true)]
+  list_hosts: bool,
 }
 
 #[derive(Clone, Debug, Subcommand)]
 pub enum CacheCommand {
   /// List cache entries.
   List,
+  /// Show cache statistics.
+  Info,
+  /// Export a cached tarball without scaffolding.
+  #[command(visible_alias = "get")]
+  Export {
+    /// Cache entry to export: source (e.g. github:foo/bar), ref name or hash. Must match exactly
+    /// one cached tarball.
+    entry: String,
+    /// File to write the tarball to. If omitted, the tarball is written to stdout.
+    out: Option<PathBuf>,
+  },
+  /// Print the cache key of a remote template and whether its tarball is cached. Useful for
+  /// debugging cache misses.
+  Key {
+    /// Repository to look up, e.g. github:foo/bar.
+    src: String,
+    /// Ref (branch, tag, or commit) to look up. Resolved the same way as when scaffolding.
+    #[arg(name = "REF", short = 'r', long = "ref")]
+    meta: Option<String>,
+  },
   /// Remove cache entries.
   Remove {
     /// List of cache entries to remove.
     entries: Vec<String>,
     /// Remove all cache entries.
-    #[arg(short, long, conflicts_with_all = ["entries", "interactive"])]
+    #[arg(short, long, conflicts_with = "entries")]
     all: bool,
+    /// Confirm removal when a ref name or hash matches entries of multiple templates.
+    #[arg(short, long)]
+    yes: bool,
   },
 }
 
+impl RepositoryArgs {
+  /// Returns the verbosity level of the actions output.
+  fn verbosity(&self) -> Verbosity {
+    if self.quiet {
+      Verbosity::Quiet
+    } else if self.verbose {
+      Verbosity::Verbose
+    } else {
+      Verbosity::Normal
+    }
+  }
+}
+
+impl RemoteArgs {
+  /// Returns arguments of every template to scaffold: the first one, followed by the ones given
+  /// with `--also`. Refs and answers are specific to the first template, so `--also` templates
+  /// only share the rest of the flags.
+  fn sources(&self) -> Vec<RepositoryArgs> {
+    let also = self.also.iter().map(|src| {
+      RepositoryArgs {
+        src: src.clone(),
+        path: None,
+        meta: None,
+        presets: Vec::new(),
+        values: None,
+        save_answers: None,
+        ..self.repository.clone()
+      }
+    });
+
+    iter::once(self.repository.clone()).chain(also).collect()
+  }
+}
+
 #[derive(Debug)]
 pub struct App {
   /// Parsed CLI options and commands.
@@ -107,9 +406,18 @@ impl App {
 
   /// Runs the app and prints any errors.
   pub async fn run(&mut self) {
-    miette::set_hook(Box::new(|_| {
+    let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    let colors = self
+      .cli
+      .color()
+      .is_enabled(no_color, io::stdout().is_terminal());
+
+    style::set_colors(colors);
+
+    miette::set_hook(Box::new(move |_| {
       Box::new(
         miette::MietteHandlerOpts::new()
+          .color(colors)
           .terminal_links(false)
           .context_lines(3)
           .tab_width(4)
@@ -118,97 +426,450 @@ impl App {
     }))
     .expect("Failed to set up the miette hook");
 
-    let scaffold_res = self.scaffold().await;
+    // Scoped, so the terminal is restored before exiting, as `process::exit` skips destructors.
+    let exit_code = {
+      let _guard = terminal::Guard;
+      self.scaffold().await.err().map(|err| self.fail(err))
+    };
+
+    if let Some(backup) = &self.state.backup {
+      backup.discard();
+    }
 
-    if scaffold_res.is_err() {
-      report::try_report(scaffold_res);
-      report::try_report(self.cleanup());
+    if let Some(code) = exit_code {
+      process::exit(code as i32);
     }
   }
 
+  /// Reports the error, cleans up after the failed scaffolding and returns the exit code for it.
+  fn fail(&mut self, err: miette::Report) -> ExitCode {
+    let exit_code = ExitCode::of(&err);
+
+    self.state.output.event(Event::Error {
+      code: err.code().map(|code| code.to_string()),
+      message: err.to_string(),
+    });
+
+    // Cancelled prompts are already marked as such by inquire, so only interruptions are noted.
+    match err.downcast_ref::<PromptError>() {
+      | Some(PromptError::Cancelled) => {},
+      | Some(PromptError::Interrupted) => {
+        self
+          .state
+          .output
+          .notice(format!("{}", "<interrupted>".red()))
+      },
+      | _ => report::try_report::<()>(Err(err)),
+    }
+
+    report::try_report(self.cleanup());
+
+    exit_code
+  }
+
   /// Kicks of the scaffolding process.
   pub async fn scaffold(&mut self) -> miette::Result<()> {
     match self.cli.clone() {
-      | Cli::Remote(args) => self.scaffold_remote(args).await,
+      | Cli::Remote(args) => self.scaffold_remotes(args).await,
       | Cli::Local(args) => self.scaffold_local(args).await,
-      | Cli::Cache { command } => self.handle_cache(command),
+      | Cli::Cache { command, .. } => self.handle_cache(command).await,
+      | Cli::Check { path, .. } => self.handle_check(path),
     }
   }
 
-  async fn scaffold_remote(&mut self, args: RepositoryArgs) -> miette::Result<()> {
-    let mut remote = RemoteRepository::new(args.src, args.meta)?;
+  /// Scaffolds one or more remote templates. Multiple templates are scaffolded one after another,
+  /// and a failed one doesn't stop the rest.
+  async fn scaffold_remotes(&mut self, mut args: RemoteArgs) -> miette::Result<()> {
+    if args.list_hosts {
+      for host in RepositoryHost::ALL {
+        println!("{host} {}", host.aliases()[1..].join(", ").dim());
+      }
 
-    // Try to fetch refs early. If we can't get them, there's no point in continuing.
-    remote.fetch_refs()?;
+      return Ok(());
+    }
 
-    // Try to resolve a ref to specific hash.
-    let hash = remote.resolve_hash()?;
+    if args.repository.src.is_empty() {
+      pick_source(&mut args.repository)?;
+    }
 
-    let name = args.path.as_ref().unwrap_or(&remote.repo);
-    let destination = PathBuf::from(name);
+    // A single client is shared by all requests, so connections are reused.
+    let client = Client::new(Duration::from_secs(args.timeout))?;
 
-    // Cleanup on failure.
-    self.state.cleanup = args.cleanup;
-    self.state.cleanup_path = Some(destination.clone());
+    if args.also.is_empty() {
+      return self.scaffold_remote(args.repository, None, &client).await;
+    }
 
-    // Check if destination already exists before downloading.
-    if let Ok(true) = &destination.try_exists() {
-      // We do not want to remove already existing directory.
-      self.state.cleanup = false;
+    let sources = args.sources();
+
+    let mut prefetched = if args.max_concurrent_downloads > 1 {
+      let cache = Cache::init()?;
+      let limit = args.max_concurrent_downloads as usize;
+      Self::prefetch(&sources, &cache, limit, &client).await
+    } else {
+      HashMap::new()
+    };
+
+    let mut results = Vec::new();
+
+    for (idx, source) in sources.into_iter().enumerate() {
+      let src = source.src.clone();
+
+      self.state = AppState::default();
+
+      let succeeded = match self
+        .scaffold_remote(source, prefetched.remove(&idx), &client)
+        .await
+      {
+        | Ok(()) => true,
+        | Err(err) => {
+          self.fail(err);
+          false
+        },
+      };
+
+      if let Some(backup) = &self.state.backup {
+        backup.discard();
+      }
+
+      self.state.output.newline();
+      results.push((src, succeeded));
+    }
 
+    // Everything is cleaned up already, so only the output is kept for the final error.
+    self.state = AppState {
+      output: mem::take(&mut self.state.output),
+      ..AppState::default()
+    };
+
+    for (src, succeeded) in &results {
+      let status = if *succeeded {
+        "✓".green()
+      } else {
+        "✗".red()
+      };
+      self.state.output.notice(format!("{status} {src}"));
+    }
+
+    let failed = results.iter().filter(|(_, succeeded)| !succeeded).count();
+
+    if failed > 0 {
       miette::bail!(
-        "Failed to scaffold: '{}' already exists.",
-        destination.display()
+        code = "decaff::app::multiple",
+        "Failed to scaffold {failed} of {} templates.",
+        results.len()
       );
     }
 
+    Ok(())
+  }
+
+  /// Downloads tarballs of the given templates concurrently, at most `limit` at a time. Templates
+  /// that are cached or fail to download are skipped, so they are read from the cache or fetched
+  /// (and errors are reported) when scaffolding. The cache itself is only touched when scaffolding.
+  async fn prefetch(
+    sources: &[RepositoryArgs],
+    cache: &Cache,
+    limit: usize,
+    client: &Client,
+  ) -> HashMap<usize, Vec<u8>> {
+    let semaphore = Arc::new(Semaphore::new(limit));
+    let mut tasks = JoinSet::new();
+
+    for (idx, args) in sources.iter().enumerate() {
+      if tarball_source(&args.src).is_some() {
+        continue;
+      }
+
+      let Ok(mut remote) = RemoteRepository::new(args.src.clone(), args.meta.clone()) else {
+        continue;
+      };
+
+      if args.cache && cache.has(&remote.get_source(), &remote.meta.0) {
+        continue;
+      }
+
+      let semaphore = Arc::clone(&semaphore);
+      let client = client.clone();
+
+      tasks.spawn(async move {
+        let _permit = semaphore.acquire_owned().await.ok()?;
+
+        // Refs are only needed to resolve a glob ref or the latest release to a tag.
+        remote.fetch_refs(&client).await.ok()?;
+        remote.resolve_release(&client).await.ok()?;
+        remote.resolve_tag().ok()?;
+
+        remote.fetch(&client).await.ok().map(|bytes| (idx, bytes))
+      });
+    }
+
+    let mut prefetched = HashMap::new();
+
+    while let Some(result) = tasks.join_next().await {
+      if let Ok(Some((idx, bytes))) = result {
+        prefetched.insert(idx, bytes);
+      }
+    }
+
+    prefetched
+  }
+
+  /// Scaffolds a remote template. Uses the prefetched tarball instead of fetching it, if given.
+  async fn scaffold_remote(
+    &mut self,
+    args: RepositoryArgs,
+    prefetched: Option<Vec<u8>>,
+    client: &Client,
+  ) -> miette::Result<()> {
+    self.state.output = Output::new(args.verbosity()).with_format(args.format);
+
+    // Tarballs on disk or piped to stdin skip fetching and caching altogether.
+    if let Some(tarball) = tarball_source(&args.src) {
+      return self.scaffold_tarball(tarball, args, client).await;
+    }
+
+    let mut remote = RemoteRepository::new(args.src.clone(), args.meta.clone())?;
     let mut cache = Cache::init()?;
-    let mut bytes = None;
 
     let source = remote.get_source();
+
+    // Try to fetch refs early. If we can't get them, fall back to the hash the ref was cached
+    // with, so cached templates can be scaffolded offline.
+    let hash = match remote.fetch_refs(client).await {
+      | Ok(()) => {
+        // Resolve the latest release to its tag, or fall back to the default branch if there are
+        // none.
+        match remote.resolve_release(client).await? {
+          | Some(Release::Tag(tag)) => {
+            self.state.output.notice(format!(
+              "{} {}",
+              "~ Resolved latest release:".dim(),
+              tag.dim()
+            ));
+          },
+          | Some(Release::Missing) => {
+            self.state.output.warn(
+              "? No releases found, falling back to the default branch."
+                .yellow()
+                .to_string(),
+            );
+          },
+          | None => {},
+        }
+
+        // Resolve a glob ref to the latest matching tag, so it's used for fetching and caching.
+        if let Some(tag) = remote.resolve_tag()? {
+          self
+            .state
+            .output
+            .notice(format!("{} {}", "~ Resolved ref to tag:".dim(), tag.dim()));
+        }
+
+        // Try to resolve a ref to specific hash.
+        remote.resolve_hash()?
+      },
+      | Err(err) => {
+        if let Some(hash) = args
+          .cache
+          .then(|| cache.cached_hash(&source, &remote.meta.0))
+          .flatten()
+        {
+          self.state.output.notice(format!(
+            "{} {}",
+            "~ Couldn't fetch refs, resolved ref from cache:".dim(),
+            hash.as_str().dim()
+          ));
+
+          hash
+        } else {
+          return Err(Self::explain_refs_error(err, &args, &remote).await);
+        }
+      },
+    };
+
+    let name = args.path.as_ref().unwrap_or(&remote.repo);
+    let destination = PathBuf::from(name);
+
+    // Check if destination already exists before downloading.
+    self.prepare_destination(&destination, &args)?;
+
+    let mut bytes = None;
     let mut should_fetch = !args.cache;
 
     if args.cache {
-      println!("{}", "~ Attempting to read from cache".dim());
+      self
+        .state
+        .output
+        .notice(format!("{}", "~ Attempting to read from cache".dim()));
 
       if let Some(cached) = cache.read(&source, &hash)? {
-        println!("{}", "~ Found in cache, reading".dim());
+        self
+          .state
+          .output
+          .notice(format!("{}", "~ Found in cache, reading".dim()));
         bytes = Some(cached);
       } else {
-        println!("{}", "~ Nothing found in cache, fetching".dim());
+        self
+          .state
+          .output
+          .notice(format!("{}", "~ Nothing found in cache, fetching".dim()));
         should_fetch = true;
       }
     }
 
     if should_fetch {
-      bytes = Some(remote.fetch().await?);
+      let fetched = match prefetched {
+        | Some(bytes) => bytes,
+        | None => remote.fetch(client).await?,
+      };
+
+      self.state.output.notice(format!(
+        "{}",
+        format!("~ Downloaded {}", format_size(fetched.len() as u64)).dim()
+      ));
+
+      bytes = Some(fetched);
     }
 
     // Decompress and unpack the tarball. If somehow the tarball is empty, bail.
     if let Some(bytes) = bytes {
-      if should_fetch {
-        cache.write(&source, &remote.meta.to_string(), &hash, &bytes)?;
+      // Store the ref as given by the user, so it's recognizable in the cache listing, along with
+      // the hash it resolved to.
+      if should_fetch && !args.no_cache_write {
+        cache.write(&source, &remote.meta.0, &hash, &bytes)?;
       }
 
-      let unpacker = Unpacker::new(bytes);
-      unpacker.unpack_to(&destination)?;
+      self.unpack(bytes, &destination, &args)?;
     } else {
       miette::bail!("Failed to scaffold: zero bytes.");
     }
 
-    self
-      .scaffold_execute(
-        &destination,
-        args.skip,
-        ConfigOptionsOverrides { delete: args.delete },
-      )
-      .await
+    self.scaffold_execute(&destination, &args, client).await
+  }
+
+  /// Turns a failure to fetch refs into an error that says if the host is unreachable, i.e. it
+  /// seems we're offline. The connectivity probe is blocking, so it runs on a blocking thread, and
+  /// it's skipped if network access is disabled anyway.
+  async fn explain_refs_error(
+    err: RemoteError,
+    args: &RepositoryArgs,
+    remote: &RemoteRepository,
+  ) -> miette::Report {
+    if args.offline {
+      return err.into();
+    }
+
+    let (host, port) = args
+      .probe
+      .clone()
+      .unwrap_or_else(|| (remote.get_domain().to_string(), 443));
+
+    let online = {
+      let (host, port) = (host.clone(), port);
+      task::spawn_blocking(move || net::is_online(&host, port, PROBE_TIMEOUT))
+        .await
+        .unwrap_or(true)
+    };
+
+    if online {
+      return err.into();
+    }
+
+    miette::miette!(
+      code = "decaff::app::offline",
+      help = "Check your connection, or scaffold a template that was cached earlier.",
+      "Failed to scaffold: {host}:{port} is unreachable, you seem to be offline."
+    )
+  }
+
+  /// Scaffolds from a tarball read from disk or stdin instead of fetching it.
+  async fn scaffold_tarball(
+    &mut self,
+    tarball: TarballSource,
+    mut args: RepositoryArgs,
+    client: &Client,
+  ) -> miette::Result<()> {
+    if let Some(meta) = &args.meta {
+      self.state.output.warn(
+        format!("? Tarballs have no refs, so the ref `{meta}` is ignored.")
+          .yellow()
+          .to_string(),
+      );
+    }
+
+    let (bytes, name) = match tarball {
+      | TarballSource::Stdin => {
+        let mut bytes = Vec::new();
+
+        io::stdin().read_to_end(&mut bytes).map_err(|source| {
+          AppError::Io {
+            message: "Failed to read the tarball from stdin.".to_string(),
+            source,
+          }
+        })?;
+
+        // Stdin is exhausted by now, so prompts can't be answered and must use their defaults.
+        args.yes = true;
+
+        (bytes, None)
+      },
+      | TarballSource::File(path) => {
+        let bytes = fs::read(&path).map_err(|source| {
+          AppError::Io {
+            message: format!("Failed to read the tarball '{}'.", path.display()),
+            source,
+          }
+        })?;
+
+        (bytes, tarball_name(&path))
+      },
+    };
+
+    let Some(name) = args.path.clone().or(name) else {
+      miette::bail!(
+        code = "decaff::app::missing_path",
+        help = "Pass the directory to scaffold to after the source.",
+        "Failed to scaffold: can't name the directory after a tarball read from stdin."
+      );
+    };
+
+    let destination = PathBuf::from(name);
+
+    self.prepare_destination(&destination, &args)?;
+
+    if bytes.is_empty() {
+      miette::bail!("Failed to scaffold: zero bytes.");
+    }
+
+    self.unpack(bytes, &destination, &args)?;
+    self.scaffold_execute(&destination, &args, client).await
+  }
+
+  /// Unpacks the tarball to the destination and reports created files.
+  fn unpack(
+    &mut self,
+    bytes: Vec<u8>,
+    destination: &Path,
+    args: &RepositoryArgs,
+  ) -> miette::Result<()> {
+    // The spinner is hidden in quiet mode and when printing JSON events.
+    let unpacker = Unpacker::new(bytes)
+      .with_strip(args.strip)
+      .with_progress(args.verbosity() != Verbosity::Quiet && args.format == OutputFormat::Text);
+
+    self.state.created = unpacker.unpack_to(destination)?;
+    self.report_created();
+
+    Ok(())
   }
 
   async fn scaffold_local(&mut self, args: RepositoryArgs) -> miette::Result<()> {
-    let local = LocalRepository::new(args.src, args.meta);
+    self.state.output = Output::new(args.verbosity()).with_format(args.format);
 
-    let destination = if let Some(destination) = args.path {
+    let local = LocalRepository::new(args.src.clone(), args.meta.clone())?
+      .with_paths(args.path_specs.clone());
+
+    let destination = if let Some(destination) = &args.path {
       PathBuf::from(destination)
     } else {
       local
@@ -218,66 +879,189 @@ impl App {
         .unwrap_or_default()
     };
 
-    // Cleanup on failure.
-    self.state.cleanup = args.cleanup;
-    self.state.cleanup_path = Some(destination.clone());
+    // Resolve the ref upfront, so an invalid one is reported before anything is written. Plain
+    // directories have nothing to check out, so the ref is ignored for them.
+    let hash = if local.is_repository() {
+      Some(local.resolve()?)
+    } else {
+      if local.meta != RepositoryMeta::default() {
+        self.state.output.warn(
+          format!(
+            "? '{}' is not a git repository, so the ref `{}` is ignored.",
+            local.source.display(),
+            local.meta.0
+          )
+          .yellow()
+          .to_string(),
+        );
+      }
 
-    // Check if destination already exists before performing local clone.
-    if let Ok(true) = &destination.try_exists() {
-      // We do not want to remove already existing directory.
-      self.state.cleanup = false;
+      if args.keep_git {
+        self.state.output.warn(
+          format!(
+            "? '{}' is not a git repository, so there's no history to keep.",
+            local.source.display()
+          )
+          .yellow()
+          .to_string(),
+        );
+      }
+
+      None
+    };
+
+    let missing = local.missing_paths(hash)?;
 
+    if !missing.is_empty() {
       miette::bail!(
-        "Failed to scaffold: '{}' already exists.",
-        destination.display()
+        code = "decaff::app::path_spec",
+        "Failed to scaffold: {} not found in '{}'.",
+        missing
+          .iter()
+          .map(|path| format!("'{}'", path.display()))
+          .collect::<Vec<_>>()
+          .join(", "),
+        local.source.display()
       );
     }
 
-    // Copy the directory.
-    local.copy(&destination)?;
+    // Check if destination already exists before performing local clone.
+    self.prepare_destination(&destination, &args)?;
 
-    // .git directory path.
-    let inner_git = destination.join(".git");
+    // If we have a repository, check out the ref, otherwise copy the directory as-is. Either way
+    // the .git directory is never written into the destination.
+    if let Some(hash) = hash {
+      self.state.created = local.checkout(hash, &destination)?;
 
-    // If we copied a repository, we also need to checkout the ref.
-    if let Ok(true) = inner_git.try_exists() {
-      println!("{}", "~ Cloned repository".dim());
+      self.state.output.notice(format!(
+        "{} {}",
+        "~ Checked out ref:".dim(),
+        local.meta.to_string().dim()
+      ));
 
-      // Checkout the ref.
-      local.checkout(&destination)?;
+      if args.keep_git {
+        if local.keep_history(hash, &destination)? {
+          self
+            .state
+            .output
+            .notice(format!("{}", "~ Preserved git history".dim()));
+        } else {
+          self.state.output.warn(
+            format!(
+              "? Couldn't keep git history: '{}' is already a repository, or the source's .git \
+               isn't a directory.",
+              destination.display()
+            )
+            .yellow()
+            .to_string(),
+          );
+        }
+      }
+    } else {
+      self.state.created = local.copy(&destination)?;
 
-      println!("{} {}", "~ Checked out ref:".dim(), local.meta.0.dim());
+      self
+        .state
+        .output
+        .notice(format!("{}", "~ Copied directory".dim()));
+    }
 
-      // At last, remove the inner .git directory.
-      fs::remove_dir_all(inner_git).map_err(|source| {
+    self.report_created();
+
+    let client = Client::new(DEFAULT_TIMEOUT)?;
+
+    self.scaffold_execute(&destination, &args, &client).await
+  }
+
+  /// Prints the number of created files. Unpacked paths include directories, so they are skipped.
+  fn report_created(&self) {
+    let files = self
+      .state
+      .created
+      .iter()
+      .filter(|path| path.is_file())
+      .count();
+
+    self
+      .state
+      .output
+      .notice(format!("{}", format!("~ Created {files} file(s)").dim()));
+  }
+
+  /// Sets up cleanup on failure and checks if the destination already exists. Existing destination
+  /// is only allowed when forced or when scaffolding into the current directory, in which case the
+  /// cleanup is disabled so that we never delete user's files. Missing parent directories are
+  /// either created, or reported upfront instead of failing deep in unpacking or copying.
+  fn prepare_destination(
+    &mut self,
+    destination: &Path,
+    args: &RepositoryArgs,
+  ) -> miette::Result<()> {
+    // Cleanup on failure.
+    self.state.cleanup = args.cleanup;
+    self.state.cleanup_path = Some(destination.to_path_buf());
+
+    let parent = destination
+      .parent()
+      .filter(|parent| !parent.as_os_str().is_empty());
+
+    if let Some(parent) = parent.filter(|parent| !parent.exists()) {
+      if !args.parents {
+        miette::bail!(
+          code = "decaff::app::missing_parent",
+          help = "Create the directory first, or pass `--parents` to create it automatically.",
+          "Failed to scaffold: parent directory '{}' doesn't exist.",
+          parent.display()
+        );
+      }
+
+      // Clean up from the topmost created directory, so pre-existing parents are left untouched.
+      self.state.cleanup_path = destination
+        .ancestors()
+        .take_while(|ancestor| !ancestor.as_os_str().is_empty() && !ancestor.exists())
+        .last()
+        .map(Path::to_path_buf);
+
+      fs::create_dir_all(parent).map_err(|source| {
         AppError::Io {
-          message: "Failed to remove inner .git directory.".to_string(),
+          message: format!("Failed to create directory: '{}'.", parent.display()),
           source,
         }
       })?;
+    }
 
-      println!("{}", "~ Removed inner .git directory".dim());
-    } else {
-      println!("{}", "~ Copied directory".dim());
+    if let Ok(true) = destination.try_exists() {
+      // We do not want to remove already existing directory. Instead, we restore files that were
+      // overwritten or deleted by actions.
+      self.state.cleanup = false;
+      self.state.backup = Some(Arc::new(Backup::new()));
+
+      let is_current_dir = destination.clean() == Path::new(".");
+
+      if !args.force && !is_current_dir {
+        return Err(AppError::DestinationExists { path: destination.display().to_string() }.into());
+      }
+
+      self.state.output.notice(format!(
+        "{}",
+        format!("~ Scaffolding into existing '{}'", destination.display()).dim()
+      ));
     }
 
-    self
-      .scaffold_execute(
-        &destination,
-        args.skip,
-        ConfigOptionsOverrides { delete: args.delete },
-      )
-      .await
+    Ok(())
   }
 
   async fn scaffold_execute(
     &mut self,
     destination: &Path,
-    should_skip: bool,
-    overrides: ConfigOptionsOverrides,
+    args: &RepositoryArgs,
+    client: &Client,
   ) -> miette::Result<()> {
-    if should_skip {
-      println!("{}", "~ Skipping running actions".dim());
+    if args.skip {
+      self
+        .state
+        .output
+        .notice(format!("{}", "~ Skipping running actions".dim()));
       return Ok(());
     }
 
@@ -285,36 +1069,175 @@ impl App {
     let mut config = Config::new(destination);
 
     if config.load()? {
-      println!();
+      self.state.output.notice("");
 
-      config.override_with(overrides);
+      config.override_with(ConfigOptionsOverrides {
+        delete: args.delete,
+        git_init: args.git_init,
+      });
 
-      // Create executor and kick off execution.
-      let executor = Executor::new(config);
+      // Collect preset answers.
+      let state = self.read_presets(args)?;
 
-      executor.execute().await
+      // Create executor and kick off execution. Prompts can't be asked when printing JSON events.
+      let options = ExecutorOptions {
+        non_interactive: args.yes || args.format == OutputFormat::Json,
+        verbosity: args.verbosity(),
+        format: args.format,
+        offline: args.offline,
+        tags: args.tags.clone(),
+        prompt_timeout: args.prompt_timeout.map(Duration::from_secs),
+      };
+      let delete = config.options.delete.then(|| config.config.clone());
+      let backup = self.state.backup.clone().unwrap_or_default();
+      let executor = Executor::new(config, options)
+        .with_backup(backup)
+        .with_client(client.clone());
+      let state = executor.execute(state).await?;
+
+      if let Some(path) = &args.save_answers {
+        write_answers(path, &state, &self.state.output)?;
+      }
+
+      // Delete the config only after actions succeeded, but before initializing a git repository,
+      // so it isn't committed.
+      if let Some(config) = delete {
+        self.delete_config(&config).await?;
+      }
+
+      executor.finish(&state).await
     } else {
       Ok(())
     }
   }
 
-  fn handle_cache(&mut self, command: CacheCommand) -> miette::Result<()> {
+  /// Deletes the config file. It's backed up first, so it can be restored if anything fails later,
+  /// even when scaffolding into a new directory.
+  async fn delete_config(&mut self, config: &Path) -> miette::Result<()> {
+    let backup = self
+      .state
+      .backup
+      .get_or_insert_with(|| Arc::new(Backup::new()));
+
+    backup.save(config).await?;
+
+    fs::remove_file(config).map_err(|source| {
+      AppError::Io {
+        message: "Failed to delete config file.".to_string(),
+        source,
+      }
+    })?;
+
+    Ok(())
+  }
+
+  /// Collects preset prompt answers from the values file and `--set` flags into a [State].
+  fn read_presets(&self, args: &RepositoryArgs) -> miette::Result<State> {
+    let mut state = State::new();
+
+    if let Some(path) = &args.values {
+      for (name, value) in read_values(path)? {
+        state.set(name, value);
+      }
+    }
+
+    for (name, value) in &args.presets {
+      state.set(name, Value::String(value.to_owned()));
+    }
+
+    Ok(state)
+  }
+
+  async fn handle_cache(&mut self, command: CacheCommand) -> miette::Result<()> {
     let mut cache = Cache::init()?;
 
     match command {
       | CacheCommand::List => Ok(cache.list()?),
-      | CacheCommand::Remove { entries, all } => {
+      | CacheCommand::Info => Ok(cache.info()?),
+      | CacheCommand::Export { entry, out } => cache.export(entry, out),
+      | CacheCommand::Key { src, meta } => {
+        let mut remote = RemoteRepository::new(src, meta)?;
+
+        let client = Client::new(DEFAULT_TIMEOUT)?;
+
+        remote.fetch_refs(&client).await?;
+        remote.resolve_release(&client).await?;
+        remote.resolve_tag()?;
+
+        let hash = remote.resolve_hash()?;
+
+        cache.key(&remote.get_source(), &remote.meta.0, &hash);
+
+        Ok(())
+      },
+      | CacheCommand::Remove { entries, all, yes } => {
         if all {
           cache.remove_all()
         } else {
-          cache.remove(entries)
+          cache.remove(entries, yes)
         }
       },
     }
   }
 
+  fn handle_check(&self, path: Option<PathBuf>) -> miette::Result<()> {
+    let root = path.unwrap_or_else(|| PathBuf::from("."));
+    let mut config = Config::new(&root);
+
+    if !config.load()? {
+      miette::bail!(
+        code = "decaff::app::check",
+        "Failed to check: no config found in '{}'.",
+        root.display()
+      );
+    }
+
+    for warning in check_references(&config) {
+      report::warn("decaff::app::check", warning);
+    }
+
+    println!("{} {}", "✓".green(), config.config.display());
+
+    // List actions, so the check doubles as a preview of the template.
+    for action in config.actions.iter() {
+      match &action.description {
+        | Some(description) => {
+          println!("└─ {} ╌╌ {}", action.kind.name(), description.clone().dim());
+        },
+        | None => println!("└─ {}", action.kind.name()),
+      }
+    }
+
+    Ok(())
+  }
+
   /// Clean up on failure.
   fn cleanup(&self) -> miette::Result<()> {
+    if let Some(backup) = &self.state.backup {
+      let Restored { restored, removed, removed_dirs } = backup.restore()?;
+
+      if restored > 0 {
+        self.state.output.notice(format!(
+          "{}",
+          format!("~ Restored {restored} file(s)").dim()
+        ));
+      }
+
+      if removed > 0 {
+        self.state.output.notice(format!(
+          "{}",
+          format!("~ Removed {removed} created file(s)").dim()
+        ));
+      }
+
+      if removed_dirs > 0 {
+        self.state.output.notice(format!(
+          "{}",
+          format!("~ Removed {removed_dirs} created directory(ies)").dim()
+        ));
+      }
+    }
+
     if self.state.cleanup {
       if let Some(destination) = &self.state.cleanup_path {
         fs::remove_dir_all(destination).map_err(|source| {
@@ -329,3 +1252,378 @@ impl App {
     Ok(())
   }
 }
+
+/// Lists problems with values the config references and prompts for: values that are referenced,
+/// but neither prompted for nor captured from actions with ids, and prompts whose answers are never
+/// referenced.
+fn check_references(config: &Config) -> Vec<String> {
+  let prompted = config.actions.prompted();
+  let captured = config.actions.captured();
+  let referenced = config.actions.referenced(config.options.auto_inject);
+
+  let unprompted = referenced
+    .iter()
+    .filter(|name| !prompted.contains(*name) && !captured.contains(*name))
+    .map(|name| format!("`{name}` is referenced, but never prompted for."));
+
+  let unreferenced = prompted
+    .difference(&referenced)
+    .map(|name| format!("`{name}` is prompted for, but never referenced."));
+
+  unprompted.chain(unreferenced).collect()
+}
+
+/// Asks for the host, repository and ref of a remote template to scaffold, and fills in the
+/// arguments with the answers.
+fn pick_source(args: &mut RepositoryArgs) -> miette::Result<()> {
+  if args.yes || args.format == OutputFormat::Json {
+    miette::bail!(
+      code = "decaff::app::missing_source",
+      help = "Pass the repository to scaffold, e.g. `github:user/repo`.",
+      "Failed to scaffold: no repository given, and it can't be picked non-interactively."
+    );
+  }
+
+  let hosts = RepositoryHost::ALL
+    .iter()
+    .map(ToString::to_string)
+    .collect();
+
+  let host = Select::new("Host:", hosts)
+    .with_render_config(helpers::theme())
+    .prompt()
+    .map_err(helpers::interrupt)?;
+
+  let validated = host.clone();
+
+  let repo = Text::new("Repository:")
+    .with_help_message("In the form of user/repo")
+    .with_render_config(helpers::theme())
+    .with_validator(move |input: &str| {
+      match RemoteRepository::from_str(&format!("{validated}:{input}")) {
+        | Ok(_) => Ok(Validation::Valid),
+        | Err(err) => Ok(Validation::Invalid(err.to_string().into())),
+      }
+    })
+    .prompt()
+    .map_err(helpers::interrupt)?;
+
+  if args.meta.is_none() {
+    let meta = Text::new("Ref:")
+      .with_help_message("Branch, tag or commit. Leave empty for the default branch")
+      .with_render_config(helpers::theme())
+      .prompt()
+      .map_err(helpers::interrupt)?;
+
+    args.meta = Some(meta.trim().to_string()).filter(|meta| !meta.is_empty());
+  }
+
+  args.src = format!("{host}:{}", repo.trim());
+
+  Ok(())
+}
+
+/// Source of a tarball to scaffold from.
+#[derive(Debug, PartialEq)]
+enum TarballSource {
+  /// Tarball file on disk.
+  File(PathBuf),
+  /// Tarball piped to stdin.
+  Stdin,
+}
+
+/// Checks if the source refers to a tarball: either `file:<path>`, or `-` for stdin.
+fn tarball_source(src: &str) -> Option<TarballSource> {
+  if src == "-" {
+    return Some(TarballSource::Stdin);
+  }
+
+  src
+    .strip_prefix("file:")
+    .map(|path| TarballSource::File(PathBuf::from(expand(path))))
+}
+
+/// Returns the file name of the tarball without archive extensions, e.g. `template` for
+/// `template.tar.gz`.
+fn tarball_name(path: &Path) -> Option<String> {
+  let name = path.file_name()?.to_str()?;
+
+  let name = [
+    ".tar.gz", ".tar.xz", ".tar.bz2", ".tgz", ".txz", ".tbz2", ".tar",
+  ]
+  .iter()
+  .find_map(|ext| name.strip_suffix(ext))
+  .unwrap_or(name);
+
+  (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Parses a `NAME=VALUE` pair.
+fn parse_preset(input: &str) -> Result<(String, String), String> {
+  match input.split_once('=') {
+    | Some((name, value)) if !name.trim().is_empty() => {
+      Ok((name.trim().to_string(), value.to_string()))
+    },
+    | _ => Err(format!("expected `NAME=VALUE`, got `{input}`")),
+  }
+}
+
+/// Parses a `HOST:PORT` pair.
+fn parse_probe(input: &str) -> Result<(String, u16), String> {
+  input
+    .rsplit_once(':')
+    .filter(|(host, _)| !host.is_empty())
+    .and_then(|(host, port)| port.parse().ok().map(|port| (host.to_string(), port)))
+    .ok_or_else(|| format!("expected `HOST:PORT`, got `{input}`"))
+}
+
+/// Reads preset values from a TOML or JSON file. The format is picked based on the extension.
+fn read_values(path: &Path) -> miette::Result<Vec<(String, Value)>> {
+  let contents = fs::read_to_string(path).map_err(|source| {
+    AppError::Io {
+      message: format!("Failed to read the values file '{}'.", path.display()),
+      source,
+    }
+  })?;
+
+  let extension = path
+    .extension()
+    .and_then(|ext| ext.to_str())
+    .unwrap_or_default();
+
+  let values: Vec<(String, Option<Value>)> = match extension {
+    | "json" => {
+      let table: serde_json::Map<String, serde_json::Value> = serde_json::from_str(&contents)
+        .map_err(|err| miette::miette!(code = "decaff::app::values", "{err}"))?;
+
+      table
+        .iter()
+        .map(|(name, value)| (name.to_owned(), Value::from_json(value)))
+        .collect()
+    },
+    | "toml" => {
+      let table: toml::Table = toml::from_str(&contents)
+        .map_err(|err| miette::miette!(code = "decaff::app::values", "{err}"))?;
+
+      table
+        .iter()
+        .map(|(name, value)| (name.to_owned(), Value::from_toml(value)))
+        .collect()
+    },
+    | _ => {
+      miette::bail!(
+        code = "decaff::app::values",
+        help = "Use a file with either `.toml` or `.json` extension.",
+        "Unsupported values file format: '{}'.",
+        path.display()
+      )
+    },
+  };
+
+  values
+    .into_iter()
+    .map(|(name, value)| {
+      value.map(|value| (name.clone(), value)).ok_or_else(|| {
+        miette::miette!(
+          code = "decaff::app::values",
+          help = "Only strings, numbers, booleans and arrays of them are supported.",
+          "Unsupported value type for `{name}` in '{}'.",
+          path.display()
+        )
+      })
+    })
+    .collect()
+}
+
+/// Writes prompt answers to a TOML or JSON file. The format is picked based on the extension.
+fn write_answers(path: &Path, state: &State, output: &Output) -> miette::Result<()> {
+  let values = state.values();
+
+  let contents = match path.extension().and_then(|ext| ext.to_str()) {
+    | Some("toml") => {
+      toml::to_string(&values)
+        .map_err(|err| miette::miette!(code = "decaff::app::answers", "{err}"))?
+    },
+    | _ => {
+      serde_json::to_string_pretty(&values)
+        .map_err(|err| miette::miette!(code = "decaff::app::answers", "{err}"))?
+    },
+  };
+
+  fs::write(path, contents).map_err(|source| {
+    AppError::Io {
+      message: format!("Failed to write answers to '{}'.", path.display()),
+      source,
+    }
+  })?;
+
+  output.notice(format!(
+    "{}",
+    format!("~ Saved answers to '{}'", path.display()).dim()
+  ));
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use clap::CommandFactory;
+
+  use super::*;
+  use crate::utils::temp::TempDir;
+
+  #[test]
+  fn verify_cli() {
+    Cli::command().debug_assert();
+  }
+
+  #[test]
+  fn color_choice() {
+    let cases = [
+      (ColorChoice::Auto, false, true, true),
+      (ColorChoice::Auto, true, true, false),
+      (ColorChoice::Auto, false, false, false),
+      (ColorChoice::Always, true, false, true),
+      (ColorChoice::Never, false, true, false),
+    ];
+
+    for (choice, no_color, is_terminal, expected) in cases {
+      assert_eq!(choice.is_enabled(no_color, is_terminal), expected);
+    }
+
+    let cli = Cli::parse_from(["decaff", "cache", "list", "--color", "never"]);
+    assert_eq!(cli.color(), ColorChoice::Never);
+  }
+
+  #[test]
+  fn also_sources_share_flags() {
+    let Cli::Remote(args) = Cli::parse_from([
+      "decaff",
+      "remote",
+      "foo/bar",
+      "app",
+      "--ref",
+      "dev",
+      "--set",
+      "NAME=app",
+      "--save-answers",
+      "answers.json",
+      "--yes",
+      "--also",
+      "foo/baz",
+    ]) else {
+      panic!("Expected the remote command.");
+    };
+
+    let sources = args.sources();
+
+    assert_eq!(sources.len(), 2);
+    assert_eq!(sources[0].meta.as_deref(), Some("dev"));
+    assert_eq!(sources[0].presets.len(), 1);
+
+    assert_eq!(sources[1].src, "foo/baz");
+    assert_eq!(sources[1].path, None);
+    assert_eq!(sources[1].meta, None);
+    assert!(sources[1].presets.is_empty());
+    assert_eq!(sources[1].save_answers, None);
+    assert!(sources[1].yes);
+  }
+
+  #[test]
+  fn check_skips_captured_references() {
+    let root = TempDir::new("check");
+
+    fs::write(
+      root.join("decaff.kdl"),
+      r#"actions {
+        input "NAME" { hint "Name"; }
+        run "git rev-parse HEAD" id="rev"
+        let "VERSION" "{NAME}-{rev.stdout}-{MISSING}"
+      }"#,
+    )
+    .unwrap();
+
+    let mut config = Config::new(&root);
+    config.load().unwrap();
+
+    assert_eq!(
+      check_references(&config),
+      [
+        "`MISSING` is referenced, but never prompted for.",
+        "`VERSION` is prompted for, but never referenced.",
+      ]
+    );
+  }
+
+  #[test]
+  fn detect_tarball_sources() {
+    assert_eq!(tarball_source("-"), Some(TarballSource::Stdin));
+    assert_eq!(
+      tarball_source("file:./template.tar.gz"),
+      Some(TarballSource::File(PathBuf::from("./template.tar.gz")))
+    );
+    assert_eq!(tarball_source("norskeld/serpent"), None);
+  }
+
+  #[test]
+  fn name_after_tarball() {
+    let cases = [
+      ("./template.tar.gz", Some("template")),
+      ("/tmp/serpent.tgz", Some("serpent")),
+      ("archive.tar", Some("archive")),
+      ("plain", Some("plain")),
+      (".tar.gz", None),
+    ];
+
+    for (path, expected) in cases {
+      assert_eq!(tarball_name(Path::new(path)).as_deref(), expected, "{path}");
+    }
+  }
+
+  #[test]
+  fn classify_exit_codes() {
+    let cases = [
+      (
+        miette::Report::from(AppError::DestinationExists { path: "foo".to_string() }),
+        ExitCode::DestinationExists,
+      ),
+      (
+        miette::Report::from(FetchError::RequestFailed),
+        ExitCode::Network,
+      ),
+      (
+        miette::Report::from(ActionError::RunFailed { code: 1 }),
+        ExitCode::Action,
+      ),
+      (
+        miette::miette!(code = "decaff::config::options", "Invalid option."),
+        ExitCode::Config,
+      ),
+      (
+        miette::miette!(code = "decaff::actions::prompts::preset", "Invalid preset."),
+        ExitCode::Action,
+      ),
+      (
+        miette::miette!(code = "decaff::app::offline", "Offline."),
+        ExitCode::Network,
+      ),
+      (
+        miette::Report::from(PromptError::Cancelled),
+        ExitCode::Cancelled,
+      ),
+      (
+        miette::Report::from(PromptError::Interrupted),
+        ExitCode::Cancelled,
+      ),
+      (
+        miette::Report::from(PromptError::Failed(inquire::InquireError::NotTTY)),
+        ExitCode::Failure,
+      ),
+      (miette::miette!("Unknown failure."), ExitCode::Failure),
+    ];
+
+    for (err, expected) in cases {
+      assert_eq!(ExitCode::of(&err), expected);
+    }
+  }
+}
@@ -1,19 +1,20 @@
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fs;
-use std::io;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use base32::Alphabet;
 use chrono::{DateTime, Utc};
-use crossterm::style::Stylize;
 use itertools::Itertools;
 use miette::{Diagnostic, Report};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::report;
 use crate::repository::RemoteRepository;
+use crate::utils::style::Stylize;
 
 /// Unpadded Base 32 alphabet.
 const BASE32_ALPHABET: Alphabet = Alphabet::RFC4648 { padding: false };
@@ -32,6 +33,11 @@ const CACHE_TARBALLS_DIR: &str = "tarballs";
 /// `<CACHE_ROOT>/manifest.toml`
 const CACHE_MANIFEST: &str = "manifest.toml";
 
+/// Minimum length of a hash prefix to be matched against longer hashes. Shorter hashes have to
+/// match exactly, so e.g. `cache remove a` doesn't remove every entry with a hash starting with
+/// `a`.
+const MIN_HASH_PREFIX: usize = 4;
+
 #[derive(Debug, Diagnostic, Error)]
 pub enum CacheError {
   #[error("{message}")]
@@ -55,11 +61,25 @@ pub enum CacheError {
 /// Entry name in the form of Base 32 encoded source string.
 type Entry = String;
 
+/// Cache entries selected by search terms.
+#[derive(Debug, Default)]
+struct Selection {
+  /// Selected items grouped by entry.
+  entries: HashMap<Entry, Vec<Item>>,
+  /// Search terms that matched nothing.
+  unmatched: Vec<String>,
+  /// Search terms that matched items of more than one entry.
+  spread: Vec<String>,
+}
+
 /// Cache manifest.
 ///
 /// # Structure
 ///
 /// ```toml
+/// [sources]
+/// <entry> = "<source>"
+///
 /// [templates.<entry>]
 /// name = "<name>"
 /// hash = "<hash>"
@@ -68,12 +88,16 @@ type Entry = String;
 ///
 /// Where:
 ///
-/// - `<entry>` - Base 32 encoded source string in the form of: `<host>:<user>/<repo>`.
+/// - `<entry>` - Base 32 encoded source string.
+/// - `<source>` - Source string in the form of: `<host>:<user>/<repo>`. Missing in manifests
+///   written by older versions, in which case it's decoded from `<entry>`.
 /// - `<name>` - Ref name or commit hash.
 /// - `<hash>` - Ref/commit hash, either short or full. Used in filenames.
 /// - `<timestamp>` - Unix timestamp in milliseconds.
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Manifest {
+  #[serde(default)]
+  sources: HashMap<Entry, String>,
   templates: HashMap<Entry, Vec<Item>>,
 }
 
@@ -82,6 +106,12 @@ impl Manifest {
   fn normalize(&mut self) {
     // Remove templates that are empty.
     self.templates.retain(|_, items| !items.is_empty());
+
+    // Remove sources of removed templates.
+    let templates = &self.templates;
+    self
+      .sources
+      .retain(|entry, _| templates.contains_key(entry));
   }
 
   /// Reads manifest from disk.
@@ -120,7 +150,7 @@ impl Manifest {
     // Serialize and write manifest.
     let manifest = toml::to_string(&self).map_err(CacheError::TomlSerialize)?;
 
-    fs::write(root.as_ref().join(CACHE_MANIFEST), manifest).map_err(|source| {
+    write_atomic(root.as_ref().join(CACHE_MANIFEST), manifest).map_err(|source| {
       CacheError::Io {
         message: "Failed to write the manifest to disk.".to_string(),
         source,
@@ -132,28 +162,63 @@ impl Manifest {
 
   /// Remove all cache entries.
   fn clear_entries(&mut self) {
+    self.sources.clear();
     self.templates.clear();
   }
 
-  /// Selects cache entries to remove based on the given search terms.
-  fn select_entries(&self, search: Vec<String>) -> HashMap<Entry, Vec<Item>> {
-    let mut selection = HashMap::new();
+  /// Selects cache entries based on the given search terms, keeping track of terms that matched
+  /// nothing or matched entries of multiple templates.
+  fn select_entries(&self, search: Vec<String>) -> Selection {
+    let mut selection = Selection::default();
 
     for term in search {
       let entry = base32::encode(BASE32_ALPHABET, term.as_bytes());
 
-      if let Some(items) = self.templates.get(&entry) {
-        selection.insert(entry, items.to_vec());
+      // Ref names take precedence over hash prefixes, so a ref named e.g. `cafe` doesn't also
+      // select entries whose hashes happen to start with `cafe`.
+      let is_name = self
+        .templates
+        .values()
+        .flatten()
+        .any(|item| item.name == term);
+
+      let matched: Vec<(Entry, Vec<Item>)> = if let Some(items) = self.templates.get(&entry) {
+        vec![(entry, items.to_vec())]
       } else {
-        for (entry, items) in &self.templates {
-          let droppable: Vec<_> = items
-            .iter()
-            .filter(|item| item.name == term || Cache::compare_hashes(&item.hash, &term))
-            .cloned()
-            .collect();
-
-          if !droppable.is_empty() {
-            selection.insert(entry.to_owned(), droppable);
+        self
+          .templates
+          .iter()
+          .map(|(entry, items)| {
+            let droppable = items
+              .iter()
+              .filter(|item| {
+                if is_name {
+                  item.name == term
+                } else {
+                  Cache::compare_hashes(&item.hash, &term)
+                }
+              })
+              .cloned()
+              .collect::<Vec<_>>();
+
+            (entry.to_owned(), droppable)
+          })
+          .filter(|(_, droppable)| !droppable.is_empty())
+          .collect()
+      };
+
+      match matched.len() {
+        | 0 => selection.unmatched.push(term),
+        | 1 => (),
+        | _ => selection.spread.push(term),
+      }
+
+      for (entry, items) in matched {
+        let selected = selection.entries.entry(entry).or_default();
+
+        for item in items {
+          if !selected.contains(&item) {
+            selected.push(item);
           }
         }
       }
@@ -181,6 +246,9 @@ pub struct Item {
   hash: String,
   /// Unix timestamp in milliseconds.
   timestamp: i64,
+  /// Size of the tarball in bytes. Missing in older manifests, in which case it's zero.
+  #[serde(default)]
+  size: u64,
 }
 
 #[derive(Debug)]
@@ -218,16 +286,20 @@ impl Cache {
     })
   }
 
-  /// Checks if two hashes match. Custom check needed because hashes may differ in length.
+  /// Checks if two hashes match. Custom check needed because hashes may differ in length. The
+  /// shorter hash is only treated as a prefix if it's at least [MIN_HASH_PREFIX] characters long.
   fn compare_hashes(left: &str, right: &str) -> bool {
-    match left.len().cmp(&right.len()) {
-      | Ordering::Less => right.starts_with(left),
-      | Ordering::Greater => left.starts_with(right),
-      | Ordering::Equal => left == right,
-    }
+    let (shorter, longer) = match left.len().cmp(&right.len()) {
+      | Ordering::Less => (left, right),
+      | Ordering::Greater => (right, left),
+      | Ordering::Equal => return left == right,
+    };
+
+    shorter.len() >= MIN_HASH_PREFIX && longer.starts_with(shorter)
   }
 
-  /// Writes contents to cache.
+  /// Writes contents to cache. The `name` is the ref as given by the user, e.g. `main`, and the
+  /// `hash` is the commit hash it resolved to.
   pub fn write(
     &mut self,
     source: &str,
@@ -237,6 +309,31 @@ impl Cache {
   ) -> miette::Result<()> {
     let entry = base32::encode(BASE32_ALPHABET, source.as_bytes());
     let timestamp = Utc::now().timestamp_millis();
+    let size = contents.len() as u64;
+
+    let tarballs_dir = self.root.join(CACHE_TARBALLS_DIR);
+    let tarball = tarballs_dir.join(format!("{hash}.tar.gz"));
+
+    fs::create_dir_all(&tarballs_dir).map_err(|source| {
+      CacheError::Io {
+        message: format!("Failed to create the '{CACHE_TARBALLS_DIR}' directory."),
+        source,
+      }
+    })?;
+
+    // Write the tarball first, so an interrupted write never leaves the manifest referencing a
+    // missing tarball.
+    write_atomic(tarball, contents).map_err(|source| {
+      CacheError::Io {
+        message: "Failed to write the tarball contents to disk.".to_string(),
+        source,
+      }
+    })?;
+
+    self
+      .manifest
+      .sources
+      .insert(entry.clone(), source.to_string());
 
     self
       .manifest
@@ -250,7 +347,7 @@ impl Cache {
           .iter()
           .any(|item| Self::compare_hashes(&hash, &item.hash))
         {
-          items.push(Item { name, hash, timestamp });
+          items.push(Item { name, hash, timestamp, size });
         }
       })
       .or_insert_with(|| {
@@ -258,94 +355,317 @@ impl Cache {
           name: name.to_string(),
           hash: hash.to_string(),
           timestamp,
+          size,
         }]
       });
 
-    self.manifest.write(&self.root)?;
-
-    let tarballs_dir = self.root.join(CACHE_TARBALLS_DIR);
-    let tarball = tarballs_dir.join(format!("{hash}.tar.gz"));
+    self.manifest.write(&self.root)
+  }
 
-    fs::create_dir_all(&tarballs_dir).map_err(|source| {
-      CacheError::Io {
-        message: format!("Failed to create the '{CACHE_TARBALLS_DIR}' directory."),
-        source,
-      }
-    })?;
+  /// Checks if there's a cached tarball for the given source and ref name.
+  pub fn has(&self, source: &str, name: &str) -> bool {
+    self.cached_hash(source, name).is_some()
+  }
 
-    fs::write(tarball, contents).map_err(|source| {
-      CacheError::Io {
-        message: "Failed to write the tarball contents to disk.".to_string(),
-        source,
-      }
-    })?;
+  /// Returns the hash of the most recently cached tarball for the given source and ref name, if
+  /// the tarball is still on disk.
+  pub fn cached_hash(&self, source: &str, name: &str) -> Option<String> {
+    let entry = base32::encode(BASE32_ALPHABET, source.as_bytes());
 
-    Ok(())
+    self
+      .manifest
+      .templates
+      .get(&entry)?
+      .iter()
+      .filter(|item| {
+        item.name == name
+          && self
+            .root
+            .join(CACHE_TARBALLS_DIR)
+            .join(format!("{}.tar.gz", item.hash))
+            .is_file()
+      })
+      .max_by_key(|item| item.timestamp)
+      .map(|item| item.hash.clone())
   }
 
   /// Reads from cache and returns the cached tarball bytes if any.
-  pub fn read(&self, source: &str, hash: &str) -> miette::Result<Option<Vec<u8>>> {
+  pub fn read(&mut self, source: &str, hash: &str) -> miette::Result<Option<Vec<u8>>> {
     let entry = base32::encode(BASE32_ALPHABET, source.as_bytes());
 
-    if let Some(items) = self.manifest.templates.get(&entry) {
-      let item = items
-        .iter()
-        .find(|item| Self::compare_hashes(hash, &item.hash));
-
-      if let Some(item) = item {
-        let tarball = self
-          .root
-          .join(CACHE_TARBALLS_DIR)
-          .join(format!("{}.tar.gz", item.hash));
-
-        let contents = fs::read(tarball).map_err(|source| {
+    let Some(items) = self.manifest.templates.get_mut(&entry) else {
+      return Ok(None);
+    };
+
+    let Some(idx) = items
+      .iter()
+      .position(|item| Self::compare_hashes(hash, &item.hash))
+    else {
+      return Ok(None);
+    };
+
+    let tarball = self
+      .root
+      .join(CACHE_TARBALLS_DIR)
+      .join(format!("{}.tar.gz", items[idx].hash));
+
+    match fs::read(tarball) {
+      | Ok(contents) => Ok(Some(contents)),
+      // The tarball is gone, e.g. removed by hand, so drop the dangling entry and treat the
+      // tarball as not cached.
+      | Err(err) if err.kind() == io::ErrorKind::NotFound => {
+        items.remove(idx);
+        self.manifest.write(&self.root)?;
+
+        Ok(None)
+      },
+      | Err(source) => {
+        Err(
           CacheError::Io {
             message: "Failed to read the cached tarball.".to_string(),
             source,
           }
-        })?;
+          .into(),
+        )
+      },
+    }
+  }
 
-        return Ok(Some(contents));
-      }
+  /// Returns a styled source string of the entry for printing. Uses the stored source string, and
+  /// falls back to decoding the entry for legacy manifests.
+  fn format_source(&self, key: &Entry) -> Result<String, CacheError> {
+    if let Some(source) = self.manifest.sources.get(key) {
+      return Ok(match source.split_once(':') {
+        | Some((host, name)) => format!("{}:{}", host.cyan(), name.green()),
+        | None => source.clone().green().to_string(),
+      });
     }
 
-    Ok(None)
+    let bytes = base32::decode(BASE32_ALPHABET, key).ok_or_else(|| {
+      CacheError::Diagnostic(miette::miette!(
+        code = "decaff::cache::malformed_entry",
+        help = "Manifest may be malformed, clear the cache and try again.",
+        "Couldn't decode entry: `{key}`."
+      ))
+    })?;
+
+    let entry = String::from_utf8(bytes).map_err(|_| {
+      CacheError::Diagnostic(miette::miette!(
+        code = "decaff::cache::invalid_utf8",
+        help = "Manifest may be malformed, clear the cache and try again.",
+        "Couldn't decode entry due to invalid UTF-8 in the string: `{key}`."
+      ))
+    })?;
+
+    let repo = Self::parse_repository(&entry)?;
+    let host = repo.host.to_string().cyan();
+    let name = format!("{}/{}", repo.user, repo.repo).green();
+
+    Ok(format!("{host}:{name}"))
   }
 
   /// Lists cache entries.
   pub fn list(&self) -> Result<(), CacheError> {
     for (key, items) in &self.manifest.templates {
-      if let Some(bytes) = base32::decode(BASE32_ALPHABET, key) {
-        let entry = String::from_utf8(bytes).map_err(|_| {
+      println!("⋅ {}", self.format_source(key)?);
+
+      for item in items.iter().sorted_by(|a, b| b.timestamp.cmp(&a.timestamp)) {
+        if let Some(date) = DateTime::from_timestamp_millis(item.timestamp) {
+          let date = date.format("%d/%m/%Y %H:%M").to_string().dim();
+          let name = item.name.clone().cyan();
+          let hash = item.hash.clone().yellow();
+          let size = self.item_size(item).map(format_size).unwrap_or_default();
+
+          println!("└─ {date} @ {name} ╌╌ {hash} {}", size.dim());
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Prints a summary: number of entries, total size of cached tarballs, and timestamps of the
+  /// oldest and newest entries.
+  pub fn info(&self) -> Result<(), CacheError> {
+    let items = self
+      .manifest
+      .templates
+      .values()
+      .flatten()
+      .collect::<Vec<_>>();
+
+    let size = items
+      .iter()
+      .filter_map(|item| self.item_size(item))
+      .sum::<u64>();
+
+    let format_date = |timestamp: Option<i64>| {
+      timestamp
+        .and_then(DateTime::from_timestamp_millis)
+        .map(|date| date.format("%d/%m/%Y %H:%M").to_string())
+        .unwrap_or_else(|| "-".to_string())
+    };
+
+    let oldest = format_date(items.iter().map(|item| item.timestamp).min());
+    let newest = format_date(items.iter().map(|item| item.timestamp).max());
+
+    let templates = self.manifest.templates.len();
+    let entries = items.len();
+
+    println!("⋅ Templates: {}", templates.to_string().green());
+    println!("⋅ Entries: {}", entries.to_string().green());
+    println!("⋅ Size: {}", format_size(size).yellow());
+    println!("⋅ Oldest: {}", oldest.cyan());
+    println!("⋅ Newest: {}", newest.cyan());
+    println!("{}", self.root.display().to_string().dim());
+
+    Ok(())
+  }
+
+  /// Returns the size of the cached tarball. Falls back to reading the tarball metadata for items
+  /// from older manifests that don't store the size.
+  fn item_size(&self, item: &Item) -> Option<u64> {
+    if item.size > 0 {
+      return Some(item.size);
+    }
+
+    let tarball = self
+      .root
+      .join(CACHE_TARBALLS_DIR)
+      .join(format!("{}.tar.gz", item.hash));
+
+    fs::metadata(tarball).ok().map(|metadata| metadata.len())
+  }
+
+  /// Returns the entry key of the source, and the path of the tarball the manifest lists for the
+  /// hash, if any. The tarball itself may be missing.
+  fn lookup(&self, source: &str, hash: &str) -> (Entry, Option<PathBuf>) {
+    let entry = base32::encode(BASE32_ALPHABET, source.as_bytes());
+
+    let tarball = self
+      .manifest
+      .templates
+      .get(&entry)
+      .and_then(|items| {
+        items
+          .iter()
+          .find(|item| Self::compare_hashes(hash, &item.hash))
+      })
+      .map(|item| {
+        self
+          .root
+          .join(CACHE_TARBALLS_DIR)
+          .join(format!("{}.tar.gz", item.hash))
+      });
+
+    (entry, tarball)
+  }
+
+  /// Prints what scaffolding would look up in the cache for the given source and ref: the entry
+  /// key, the resolved hash and the cached tarball, if any. Doesn't modify the cache.
+  pub fn key(&self, source: &str, name: &str, hash: &str) {
+    let (entry, tarball) = self.lookup(source, hash);
+
+    println!("⋅ {}", source.green());
+    println!("└─ Entry: {}", entry.cyan());
+    println!("└─ Ref: {} ╌╌ {}", name.cyan(), hash.yellow());
+
+    match tarball {
+      | Some(tarball) => {
+        let status = if tarball.is_file() {
+          "cached".green()
+        } else {
+          "missing tarball".red()
+        };
+
+        println!("└─ Tarball: {} {status}", tarball.display());
+      },
+      | None => println!("└─ Tarball: {}", "not in manifest".red()),
+    }
+  }
+
+  /// Exports a cached tarball to the given file, or to stdout if no file is given. The entry is
+  /// matched the same way as in [Cache::remove], and must match exactly one tarball.
+  pub fn export(&self, needle: String, out: Option<PathBuf>) -> miette::Result<()> {
+    let selection = self.manifest.select_entries(vec![needle.clone()]);
+
+    let matches = selection
+      .entries
+      .iter()
+      .flat_map(|(entry, items)| items.iter().map(move |item| (entry, item)))
+      .collect::<Vec<_>>();
+
+    let (entry, item) = match matches.as_slice() {
+      | [] => {
+        return Err(
           CacheError::Diagnostic(miette::miette!(
-            code = "decaff::cache::invalid_utf8",
-            help = "Manifest may be malformed, clear the cache and try again.",
-            "Couldn't decode entry due to invalid UTF-8 in the string: `{key}`."
+            code = "decaff::cache::no_match",
+            help = "Run `decaff cache list` to see cached entries.",
+            "No cache entries match `{needle}`."
           ))
-        })?;
+          .into(),
+        );
+      },
+      | [single] => *single,
+      | _ => {
+        let candidates = matches
+          .iter()
+          .map(|(entry, item)| {
+            let source = self
+              .format_source(entry)
+              .unwrap_or_else(|_| entry.to_string());
+            format!("{source} @ {} ╌╌ {}", item.name, item.hash)
+          })
+          .join("\n");
+
+        return Err(
+          CacheError::Diagnostic(miette::miette!(
+            code = "decaff::cache::ambiguous",
+            help = format!("Use a hash to pick one of:\n{candidates}"),
+            "`{needle}` matches {} cached tarballs.",
+            matches.len()
+          ))
+          .into(),
+        );
+      },
+    };
 
-        let repo = Self::parse_repository(&entry)?;
-        let host = repo.host.to_string().cyan();
-        let name = format!("{}/{}", repo.user, repo.repo).green();
+    let tarball = self
+      .root
+      .join(CACHE_TARBALLS_DIR)
+      .join(format!("{}.tar.gz", item.hash));
 
-        println!("⋅ {host}:{name}");
+    let contents = fs::read(&tarball).map_err(|source| {
+      CacheError::Io {
+        message: "Failed to read the cached tarball.".to_string(),
+        source,
+      }
+    })?;
 
-        for item in items.iter().sorted_by(|a, b| b.timestamp.cmp(&a.timestamp)) {
-          if let Some(date) = DateTime::from_timestamp_millis(item.timestamp) {
-            let date = date.format("%d/%m/%Y %H:%M").to_string().dim();
-            let name = item.name.clone().cyan();
-            let hash = item.hash.clone().yellow();
+    match out {
+      | Some(out) => {
+        fs::write(&out, contents).map_err(|source| {
+          CacheError::Io {
+            message: format!("Failed to write the tarball to '{}'.", out.display()),
+            source,
+          }
+        })?;
 
-            println!("└─ {date} @ {name} ╌╌ {hash}");
+        eprintln!(
+          "⋅ {} @ {} ╌╌ {}",
+          self.format_source(entry)?,
+          item.name.clone().cyan(),
+          out.display()
+        );
+      },
+      | None => {
+        io::stdout().write_all(&contents).map_err(|source| {
+          CacheError::Io {
+            message: "Failed to write the tarball to stdout.".to_string(),
+            source,
           }
-        }
-      } else {
-        return Err(CacheError::Diagnostic(miette::miette!(
-          code = "decaff::cache::malformed_entry",
-          help = "Manifest may be malformed, clear the cache and try again.",
-          "Couldn't decode entry: `{key}`."
-        )));
-      }
+        })?;
+      },
     }
 
     Ok(())
@@ -356,20 +676,49 @@ impl Cache {
   /// - entry name, e.g. github:foo/bar -- this will delete all cached entries under that name;
   /// - entry hash, e.g. 4a5a56fd -- this will delete specific cached entry;
   /// - ref name, e.g. feat/some-feature-name -- same as entry hash.
-  pub fn remove(&mut self, needles: Vec<String>) -> miette::Result<()> {
+  ///
+  /// Terms that match nothing are reported. If a ref name or hash matches entries of multiple
+  /// templates, nothing is removed unless `confirmed` is set.
+  pub fn remove(&mut self, needles: Vec<String>, confirmed: bool) -> miette::Result<()> {
     let selection = self.manifest.select_entries(needles);
 
-    // Actually remove the files and print their names (<hash>.tar.gz).
-    for (entry, items) in &selection {
-      let entry = base32::decode(BASE32_ALPHABET, entry.as_str())
-        .and_then(|bytes| String::from_utf8(bytes).ok())
-        .unwrap();
+    for term in &selection.unmatched {
+      report::warn(
+        "decaff::cache::no_match",
+        format!("No cache entries match `{term}`."),
+      );
+    }
+
+    if !selection.spread.is_empty() && !confirmed {
+      for (entry, items) in &selection.entries {
+        println!("⋅ {}", self.format_source(entry)?);
+
+        for item in items.iter().sorted_by(|a, b| b.timestamp.cmp(&a.timestamp)) {
+          println!(
+            "└─ {} ╌╌ {}",
+            item.name.clone().cyan(),
+            item.hash.clone().yellow()
+          );
+        }
+      }
 
-      let repo = Self::parse_repository(&entry)?;
-      let host = repo.host.to_string().cyan();
-      let name = format!("{}/{}", repo.user, repo.repo).green();
+      let terms = selection
+        .spread
+        .iter()
+        .map(|term| format!("`{term}`"))
+        .join(", ");
+
+      miette::bail!(
+        code = "decaff::cache::ambiguous",
+        help =
+          "Pass `--yes` to remove all of the above, or use a source or hash to narrow it down.",
+        "Refusing to remove: {terms} matched entries of multiple templates."
+      );
+    }
 
-      println!("⋅ {host}:{name}");
+    // Actually remove the files and print their names (<hash>.tar.gz).
+    for (entry, items) in &selection.entries {
+      println!("⋅ {}", self.format_source(entry)?);
 
       for item in items.iter().sorted_by(|a, b| b.timestamp.cmp(&a.timestamp)) {
         let tarball = self
@@ -389,7 +738,7 @@ impl Cache {
       }
     }
 
-    self.manifest.remove_entries(&selection);
+    self.manifest.remove_entries(&selection.entries);
     self.manifest.write(&self.root)?;
 
     Ok(())
@@ -410,3 +759,272 @@ impl Cache {
     Ok(())
   }
 }
+
+/// Formats a size in bytes using binary units, e.g. `1.5 MiB`.
+pub fn format_size(bytes: u64) -> String {
+  const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+  if bytes < 1024 {
+    return format!("{bytes} B");
+  }
+
+  let mut size = bytes as f64 / 1024.0;
+  let mut unit = 0;
+
+  while size >= 1024.0 && unit < UNITS.len() - 1 {
+    size /= 1024.0;
+    unit += 1;
+  }
+
+  format!("{size:.1} {}", UNITS[unit])
+}
+
+/// Writes contents to a temporary file next to the path and renames it into place, so the file is
+/// either fully written or left untouched.
+fn write_atomic(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+  let path = path.as_ref();
+
+  let mut temp = path.as_os_str().to_owned();
+  temp.push(".tmp");
+
+  let temp = PathBuf::from(temp);
+
+  let result = fs::write(&temp, contents).and_then(|_| fs::rename(&temp, path));
+
+  if result.is_err() {
+    let _ = fs::remove_file(&temp);
+  }
+
+  result
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::utils::temp::TempDir;
+
+  #[test]
+  fn format_sizes() {
+    let cases = [
+      (0, "0 B"),
+      (1023, "1023 B"),
+      (1024, "1.0 KiB"),
+      (1536, "1.5 KiB"),
+      (5 * 1024 * 1024, "5.0 MiB"),
+    ];
+
+    for (bytes, expected) in cases {
+      assert_eq!(format_size(bytes), expected);
+    }
+  }
+
+  #[test]
+  fn read_legacy_manifest() {
+    let manifest: Manifest = toml::from_str(
+      r#"
+      [[templates.ENTRY]]
+      name = "main"
+      hash = "4a5a56fd"
+      timestamp = 0
+      "#,
+    )
+    .unwrap();
+
+    assert!(manifest.sources.is_empty());
+    assert_eq!(manifest.templates["ENTRY"].len(), 1);
+    assert_eq!(manifest.templates["ENTRY"][0].size, 0);
+  }
+
+  #[test]
+  fn select_entries_reports_unmatched_and_spread_terms() {
+    let item = |name: &str, hash: &str| {
+      Item {
+        name: name.to_string(),
+        hash: hash.to_string(),
+        timestamp: 0,
+        size: 0,
+      }
+    };
+
+    let mut manifest = Manifest::default();
+
+    manifest.templates.insert(
+      "FOO".to_string(),
+      vec![item("main", "aaa"), item("dev", "bbb")],
+    );
+
+    manifest
+      .templates
+      .insert("BAR".to_string(), vec![item("main", "ccc")]);
+
+    let selection = manifest.select_entries(vec![
+      "main".to_string(),
+      "bbb".to_string(),
+      "typo".to_string(),
+    ]);
+
+    assert_eq!(selection.unmatched, vec!["typo"]);
+    assert_eq!(selection.spread, vec!["main"]);
+    assert_eq!(selection.entries["FOO"].len(), 2);
+    assert_eq!(selection.entries["BAR"].len(), 1);
+  }
+
+  #[test]
+  fn write_stores_ref_name_and_hash() {
+    let root = TempDir::new("cache");
+
+    let mut cache = Cache {
+      root: root.to_path_buf(),
+      manifest: Manifest::default(),
+    };
+
+    let hash = "4a5a56fd0c1e2b3a4d5e6f708192a3b4c5d6e7f8";
+
+    cache
+      .write("github:foo/bar", "main", hash, b"tarball")
+      .unwrap();
+    cache
+      .write("github:foo/bar", "main", hash, b"tarball")
+      .unwrap();
+
+    assert!(cache.has("github:foo/bar", "main"));
+    assert!(!cache.has("github:foo/bar", hash));
+    assert_eq!(
+      cache.cached_hash("github:foo/bar", "main").as_deref(),
+      Some(hash)
+    );
+    assert_eq!(
+      cache.read("github:foo/bar", "4a5a56fd").unwrap(),
+      Some(b"tarball".to_vec())
+    );
+
+    let items = cache
+      .manifest
+      .templates
+      .values()
+      .flatten()
+      .collect::<Vec<_>>();
+
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].name, "main");
+    assert_eq!(items[0].hash, hash);
+  }
+
+  #[test]
+  fn read_heals_missing_tarball() {
+    let root = TempDir::new("cache-heal");
+
+    let mut cache = Cache {
+      root: root.to_path_buf(),
+      manifest: Manifest::default(),
+    };
+
+    let hash = "0c1e2b3a4d5e6f708192a3b4c5d6e7f84a5a56fd";
+
+    cache
+      .write("github:foo/bar", "main", hash, b"tarball")
+      .unwrap();
+
+    assert!(!root
+      .join(CACHE_TARBALLS_DIR)
+      .join(format!("{hash}.tar.gz.tmp"))
+      .exists());
+
+    fs::remove_file(root.join(CACHE_TARBALLS_DIR).join(format!("{hash}.tar.gz"))).unwrap();
+
+    assert_eq!(cache.read("github:foo/bar", hash).unwrap(), None);
+    assert!(cache.manifest.templates.is_empty());
+    assert!(Manifest::read(&root).unwrap().templates.is_empty());
+  }
+
+  #[test]
+  fn compare_hashes_requires_minimum_prefix() {
+    assert!(Cache::compare_hashes("a1b2", "a1b2c3d4"));
+    assert!(Cache::compare_hashes("a1b2c3d4", "a1b2"));
+    assert!(Cache::compare_hashes("a1b", "a1b"));
+    assert!(!Cache::compare_hashes("a1b", "a1b2c3d4"));
+    assert!(!Cache::compare_hashes("a", "a1b2c3d4"));
+  }
+
+  #[test]
+  fn select_entries_ignores_short_ambiguous_terms() {
+    let item = |name: &str, hash: &str| {
+      Item {
+        name: name.to_string(),
+        hash: hash.to_string(),
+        timestamp: 0,
+        size: 0,
+      }
+    };
+
+    let mut manifest = Manifest::default();
+
+    manifest.templates.insert(
+      "FOO".to_string(),
+      vec![item("main", "a1b2c3d"), item("a", "a9f8e7d")],
+    );
+
+    manifest
+      .templates
+      .insert("BAR".to_string(), vec![item("main", "a1b2c3d4e5")]);
+
+    let selection = manifest.select_entries(vec!["a1".to_string()]);
+
+    assert_eq!(selection.unmatched, vec!["a1"]);
+    assert!(selection.entries.is_empty());
+
+    let selection = manifest.select_entries(vec!["a".to_string()]);
+
+    assert!(selection.unmatched.is_empty());
+    assert_eq!(selection.entries["FOO"], vec![item("a", "a9f8e7d")]);
+    assert!(!selection.entries.contains_key("BAR"));
+
+    let selection = manifest.select_entries(vec!["a1b2".to_string()]);
+
+    assert_eq!(selection.spread, vec!["a1b2"]);
+    assert_eq!(selection.entries["FOO"], vec![item("main", "a1b2c3d")]);
+    assert_eq!(selection.entries["BAR"].len(), 1);
+  }
+
+  #[test]
+  fn normalize_removes_orphaned_sources() {
+    let mut manifest = Manifest::default();
+
+    manifest
+      .sources
+      .insert("ENTRY".to_string(), "github:foo/bar".to_string());
+    manifest.templates.insert("ENTRY".to_string(), Vec::new());
+
+    manifest.normalize();
+
+    assert!(manifest.sources.is_empty());
+    assert!(manifest.templates.is_empty());
+  }
+
+  #[test]
+  fn lookup_key_and_tarball() {
+    let root = TempDir::new("cache-key");
+
+    let mut cache = Cache {
+      root: root.to_path_buf(),
+      manifest: Manifest::default(),
+    };
+
+    let hash = "4a5a56fd0c1e2b3a4d5e6f708192a3b4c5d6e7f8";
+
+    cache
+      .write("github:foo/bar", "main", hash, b"tarball")
+      .unwrap();
+
+    let (entry, tarball) = cache.lookup("github:foo/bar", "4a5a56fd");
+
+    assert_eq!(entry, "M5UXI2DVMI5GM33PF5RGC4Q");
+    assert_eq!(
+      tarball,
+      Some(root.join(CACHE_TARBALLS_DIR).join(format!("{hash}.tar.gz")))
+    );
+
+    assert_eq!(cache.lookup("github:foo/bar", "ffffffff").1, None);
+    assert_eq!(cache.lookup("github:foo/baz", hash).1, None);
+  }
+}
@@ -1,27 +1,63 @@
 use std::collections::HashSet;
 
 use crate::config::prompts::*;
+use crate::config::{Action, Value};
 
 /// Copies a file or directory. Glob-friendly. Overwrites by default.
 #[derive(Debug)]
 pub struct Copy {
-  /// Source(s) to copy.
-  pub from: String,
+  /// Source(s) to copy. Each one is a glob pattern.
+  pub from: Vec<String>,
   /// Where to copy to.
   pub to: String,
-  /// Whether to overwrite or not. Defaults to `true`.
-  pub overwrite: bool,
+  /// When to overwrite existing files. Defaults to [OverwritePolicy::Always].
+  pub overwrite: OverwritePolicy,
+  /// Whether to preserve permissions and timestamps or not. Defaults to `true`.
+  pub preserve: bool,
+  /// Optional glob to exclude matching sources from copying.
+  pub exclude: Option<String>,
+  /// Whether to render placeholders in copied files using values from the state. Defaults to
+  /// `false`, so files are copied byte for byte.
+  pub render: bool,
+  /// Whether to copy all matched files right into `to`, dropping their directory structure.
+  /// Defaults to `false`, so the structure below the non-glob part of each pattern is preserved.
+  pub flatten: bool,
 }
 
 /// Moves a file or directory. Glob-friendly. Overwrites by default.
 #[derive(Debug)]
 pub struct Move {
-  /// Source(s) to move.
-  pub from: String,
+  /// Source(s) to move. Each one is a glob pattern.
+  pub from: Vec<String>,
   /// Where to move to.
   pub to: String,
-  /// Whether to overwrite or not. Defaults to `true`.
-  pub overwrite: bool,
+  /// When to overwrite existing files. Defaults to [OverwritePolicy::Always].
+  pub overwrite: OverwritePolicy,
+  /// Whether to preserve permissions and timestamps or not. Defaults to `true`.
+  pub preserve: bool,
+}
+
+/// When to overwrite existing files when copying or moving.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum OverwritePolicy {
+  /// Always overwrite. Same as `overwrite=true`.
+  #[default]
+  Always,
+  /// Never overwrite. Same as `overwrite=false`.
+  Never,
+  /// Overwrite only if the source was modified later than the existing file.
+  IfNewer,
+  /// Ask for every existing file. Never overwrites when running non-interactively.
+  Prompt,
+}
+
+/// Renames a single file or directory. Both paths are exact, i.e. not globs.
+#[derive(Debug)]
+pub struct Rename {
+  /// Path to rename.
+  pub from: String,
+  /// New path.
+  pub to: String,
 }
 
 /// Deletes a file or directory. Glob-friendly.
@@ -31,6 +67,45 @@ pub struct Delete {
   pub target: String,
 }
 
+/// Deletes a file or directory depending on a boolean value, e.g. a confirm prompt's answer.
+/// Glob-friendly.
+///
+/// ```kdl
+/// drop "docker/**" unless="use_docker"
+/// ```
+#[derive(Debug)]
+pub struct Discard {
+  /// Target to delete.
+  pub target: String,
+  /// Condition under which the target is deleted.
+  pub condition: Condition,
+}
+
+/// Condition of the [Discard] action, referencing a boolean value by name.
+#[derive(Debug)]
+pub enum Condition {
+  /// Holds if the value is `true`.
+  If(String),
+  /// Holds if the value is `false` or not set. Values that aren't booleans are an error.
+  Unless(String),
+}
+
+impl Condition {
+  /// Returns the name of the referenced value.
+  pub fn name(&self) -> &str {
+    match self {
+      | Self::If(name) | Self::Unless(name) => name,
+    }
+  }
+}
+
+/// Creates an empty file or updates its modification time. Globs only update existing files.
+#[derive(Debug)]
+pub struct Touch {
+  /// Target to create or update.
+  pub target: String,
+}
+
 /// Echoes a message to stdout.
 #[derive(Debug)]
 pub struct Echo {
@@ -48,6 +123,8 @@ pub struct Echo {
   pub injects: Option<HashSet<String>>,
   /// Whether to trim multiline message or not. Defaults to `true`.
   pub trim: bool,
+  /// Separator used to join list values. Defaults to `", "`.
+  pub separator: String,
 }
 
 /// Runs an arbitrary command in the shell.
@@ -72,6 +149,8 @@ pub struct Run {
   ///
   /// All placeholders are processed _before_ running a command.
   pub injects: Option<HashSet<String>>,
+  /// Working directory relative to the root. Defaults to the root itself.
+  pub cwd: Option<String>,
 }
 
 /// Prompt actions.
@@ -82,6 +161,21 @@ pub enum Prompt {
   Select(SelectPrompt),
   Confirm(ConfirmPrompt),
   Editor(EditorPrompt),
+  Path(PathPrompt),
+}
+
+impl Prompt {
+  /// Returns the name of the variable that will store the answer.
+  pub fn name(&self) -> &str {
+    match self {
+      | Self::Confirm(prompt) => &prompt.name,
+      | Self::Editor(prompt) => &prompt.name,
+      | Self::Path(prompt) => &prompt.name,
+      | Self::Input(prompt) => &prompt.name,
+      | Self::Number(prompt) => &prompt.name,
+      | Self::Select(prompt) => &prompt.name,
+    }
+  }
 }
 
 /// Execute given replacements using values provided by prompts. Optionally, only apply
@@ -92,6 +186,93 @@ pub struct Replace {
   pub replacements: HashSet<String>,
   /// Optional glob to limit files to apply replacements to.
   pub glob: Option<String>,
+  /// Optional glob to exclude files from replacements.
+  pub exclude: Option<String>,
+  /// Separator used to join list values. Defaults to `", "`.
+  pub separator: String,
+  /// Opening and closing delimiters of placeholders. Default to `{` and `}`.
+  pub delimiters: (String, String),
+}
+
+/// Sets values in a JSON, TOML or YAML file. The format is detected by file extension.
+#[derive(Debug)]
+pub struct Patch {
+  /// File to patch.
+  pub target: String,
+  /// Values to set, in order.
+  pub sets: Vec<PatchSet>,
+}
+
+/// A single value to set in a patched file.
+#[derive(Debug)]
+pub struct PatchSet {
+  /// Dotted path (`scripts.build`) or JSON pointer (`/scripts/build`) to set the value at.
+  pub path: String,
+  /// Value to set. Strings have placeholders injected.
+  pub value: Value,
+}
+
+/// Downloads a file from the URL and writes it to a path under the root.
+#[derive(Debug)]
+pub struct Fetch {
+  /// URL to download. Placeholders are injected.
+  pub url: String,
+  /// Path to write to. Placeholders are injected.
+  pub to: String,
+  /// Whether to overwrite an existing file.
+  pub overwrite: bool,
+}
+
+/// Writes variables to a dotenv file, updating existing ones and appending missing ones.
+#[derive(Debug)]
+pub struct Env {
+  /// Dotenv file to write to. Created if it doesn't exist.
+  pub target: String,
+  /// Variables to write, in order.
+  pub vars: Vec<EnvVar>,
+  /// Whether to wrap values in double quotes.
+  pub quote: bool,
+}
+
+/// A single variable to write to a dotenv file.
+#[derive(Debug)]
+pub struct EnvVar {
+  /// Variable name.
+  pub key: String,
+  /// Variable value. Placeholders are injected.
+  pub value: String,
+}
+
+/// Groups related prompts under a common header.
+///
+/// ```kdl
+/// group "Database settings" {
+///   input "DB_HOST" { hint "Host"; }
+///   number "DB_PORT" { hint "Port"; }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Group {
+  /// Header printed before the prompts.
+  pub title: String,
+  /// Prompts to run. Only prompt actions are allowed.
+  pub prompts: Vec<Action>,
+}
+
+/// Sets a value derived from other values without prompting. Placeholders in the expression are
+/// filled in, casing filters included.
+///
+/// ```kdl
+/// let "SLUG" "{NAME:kebab}"
+/// ```
+#[derive(Debug)]
+pub struct Set {
+  /// Name of the variable that will store the value.
+  pub name: String,
+  /// Expression to evaluate.
+  pub value: String,
+  /// Separator used to join list values. Defaults to `", "`.
+  pub separator: String,
 }
 
 /// Fallback action for pattern matching ergonomics and reporting purposes.
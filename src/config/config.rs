@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
@@ -6,15 +6,36 @@ use std::sync::Arc;
 
 use kdl::{KdlDocument, KdlNode};
 use miette::{Diagnostic, LabeledSpan, NamedSource, Report};
+use semver::{Version, VersionReq};
 use thiserror::Error;
 
 use crate::config::actions::*;
 use crate::config::prompts::*;
 use crate::config::value::*;
-use crate::config::KdlUtils;
+use crate::config::{placeholders, suggest, KdlUtils};
 
 const CONFIG_NAME: &str = "decaff.kdl";
 
+/// Names of all known actions. Used to suggest the closest name for unknown actions.
+const KNOWN_ACTIONS: [&str; 21] = [
+  "cp", "mv", "rename", "rm", "drop", "touch", "echo", "run", "input", "number", "editor",
+  "select", "confirm", "path", "group", "let", "set", "replace", "patch", "env", "fetch",
+];
+
+/// Names of all known platforms as reported by [std::env::consts::OS].
+const KNOWN_PLATFORMS: [&str; 10] = [
+  "linux",
+  "macos",
+  "windows",
+  "ios",
+  "android",
+  "freebsd",
+  "dragonfly",
+  "netbsd",
+  "openbsd",
+  "solaris",
+];
+
 /// Helper macro to create a [ConfigError::Diagnostic] in a slightly less verbose way.
 macro_rules! diagnostic {
   ($source:ident = $code:expr, $($key:ident = $value:expr,)* $fmt:literal $($arg:tt)*) => {
@@ -43,16 +64,39 @@ pub enum ConfigError {
   Diagnostic(Report),
 }
 
+/// Default message of the initial commit made when `git_init` is enabled.
+pub const DEFAULT_GIT_MESSAGE: &str = "Initial commit";
+
 /// Config options. These may be overriden from the CLI.
 #[derive(Debug)]
 pub struct ConfigOptions {
   /// Whether to delete the config after we (successfully) done running.
   pub delete: bool,
+  /// Whether to fail on unknown actions. If `false`, unknown actions are reported and skipped.
+  pub strict: bool,
+  /// Whether to inject values into `echo` and `run` actions that don't declare injects explicitly.
+  pub auto_inject: bool,
+  /// Whether to list entries matched by `rm` and ask for confirmation before deleting them.
+  pub confirm_destructive: bool,
+  /// Whether to initialize a git repository and commit all files after running actions.
+  pub git_init: bool,
+  /// Message of the initial commit.
+  pub git_message: String,
+  /// Whether to print a diff of every file changed by `replace`.
+  pub replace_diff: bool,
 }
 
 impl Default for ConfigOptions {
   fn default() -> Self {
-    Self { delete: true }
+    Self {
+      delete: true,
+      strict: true,
+      auto_inject: false,
+      confirm_destructive: false,
+      git_init: false,
+      git_message: DEFAULT_GIT_MESSAGE.to_string(),
+      replace_diff: false,
+    }
   }
 }
 
@@ -61,6 +105,28 @@ impl Default for ConfigOptions {
 pub struct ConfigOptionsOverrides {
   /// Whether to delete the config after we (successfully) done running.
   pub delete: Option<bool>,
+  /// Whether to initialize a git repository after running actions.
+  pub git_init: Option<bool>,
+}
+
+/// Platform an action or a suite is restricted to.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Os {
+  /// Runs on all platforms.
+  #[default]
+  All,
+  /// Runs only on the given platform, e.g. `linux`, `macos` or `windows`.
+  Only(String),
+}
+
+impl Os {
+  /// Checks if the current platform matches.
+  pub fn is_current(&self) -> bool {
+    match self {
+      | Self::All => true,
+      | Self::Only(os) => os == std::env::consts::OS,
+    }
+  }
 }
 
 /// Represents a config actions set that can be a vec of [ActionSuite] *or* [ActionSingle].
@@ -89,18 +155,131 @@ pub enum Actions {
   /// Suites of actions to run.
   Suite(Vec<ActionSuite>),
   /// Flat list of actions to run.
-  Flat(Vec<ActionSingle>),
+  Flat(Vec<Action>),
   /// No actions to run.
   Empty,
 }
 
+impl Actions {
+  /// Returns an iterator over all actions, including actions inside suites and groups.
+  pub fn iter(&self) -> Box<dyn Iterator<Item = &Action> + '_> {
+    let actions: Box<dyn Iterator<Item = &Action>> = match self {
+      | Self::Suite(suites) => Box::new(suites.iter().flat_map(|suite| suite.actions.iter())),
+      | Self::Flat(actions) => Box::new(actions.iter()),
+      | Self::Empty => Box::new(std::iter::empty()),
+    };
+
+    Box::new(actions.flat_map(|action| {
+      let prompts = match &action.kind {
+        | ActionSingle::Group(group) => group.prompts.as_slice(),
+        | _ => &[],
+      };
+
+      std::iter::once(action).chain(prompts)
+    }))
+  }
+
+  /// Returns names of values produced by prompts and `let` actions.
+  pub fn prompted(&self) -> BTreeSet<String> {
+    self
+      .iter()
+      .filter_map(|action| {
+        match &action.kind {
+          | ActionSingle::Prompt(prompt) => Some(prompt.name().to_string()),
+          | ActionSingle::Set(action) => Some(action.name.clone()),
+          | _ => None,
+        }
+      })
+      .collect()
+  }
+
+  /// Returns names of values captured from actions with ids, e.g. `build.stdout`.
+  pub fn captured(&self) -> BTreeSet<String> {
+    self
+      .iter()
+      .filter_map(|action| action.id.as_ref().map(|id| (id, action.kind.captures())))
+      .flat_map(|(id, fields)| fields.iter().map(move |field| format!("{id}.{field}")))
+      .collect()
+  }
+
+  /// Returns names of values referenced by replacements and injections. If `auto_inject` is set,
+  /// placeholders of `echo` and `run` actions without explicit injects are considered too.
+  pub fn referenced(&self, auto_inject: bool) -> BTreeSet<String> {
+    self
+      .iter()
+      .flat_map(|action| {
+        match &action.kind {
+          | ActionSingle::Replace(action) => action.replacements.iter().cloned().collect(),
+          | ActionSingle::Patch(action) => {
+            action
+              .sets
+              .iter()
+              .filter_map(|set| {
+                match &set.value {
+                  | Value::String(string) => Some(placeholders(string)),
+                  | _ => None,
+                }
+              })
+              .flatten()
+              .collect()
+          },
+          | ActionSingle::Discard(action) => vec![action.condition.name().to_string()],
+          | ActionSingle::Set(action) => placeholders(&action.value),
+          | ActionSingle::Fetch(action) => {
+            [&action.url, &action.to]
+              .into_iter()
+              .flat_map(|input| placeholders(input))
+              .collect()
+          },
+          | ActionSingle::Env(action) => {
+            action
+              .vars
+              .iter()
+              .flat_map(|var| placeholders(&var.value))
+              .collect()
+          },
+          | ActionSingle::Echo(Echo { injects: Some(injects), .. })
+          | ActionSingle::Run(Run { injects: Some(injects), .. }) => {
+            injects.iter().cloned().collect()
+          },
+          | ActionSingle::Echo(Echo { message: input, injects: None, .. })
+          | ActionSingle::Run(Run { command: input, injects: None, .. })
+            if auto_inject =>
+          {
+            placeholders(input)
+          },
+          | _ => Vec::new(),
+        }
+      })
+      .collect()
+  }
+}
+
 /// A suite of actions that contains a flat list of [ActionSingle].
 #[derive(Debug)]
 pub struct ActionSuite {
   /// Suite name.
   pub name: String,
+  /// Platform the suite is restricted to. Defaults to [Os::All].
+  pub os: Os,
+  /// Tags to select the suite by. Empty if not set.
+  pub tags: Vec<String>,
   /// Suite actions to run.
-  pub actions: Vec<ActionSingle>,
+  pub actions: Vec<Action>,
+}
+
+/// A single action along with attributes common to all actions.
+#[derive(Debug)]
+pub struct Action {
+  /// Identifier of the action. Results of `run` and prompt actions with an id are stored in the
+  /// state as `{id}.stdout` and `{id}.value`, so later actions can reference them.
+  pub id: Option<String>,
+  /// Platform the action is restricted to. If not set, the suite's platform is used.
+  pub os: Option<Os>,
+  /// Human-readable description of the action. Doesn't affect execution.
+  pub description: Option<String>,
+  /// The action itself.
+  pub kind: ActionSingle,
 }
 
 /// A single "atomic" action.
@@ -110,21 +289,75 @@ pub enum ActionSingle {
   Copy(Copy),
   /// Moves a file or directory. Glob-friendly. Overwrites by default.
   Move(Move),
+  /// Renames a single file or directory. Both paths are exact, i.e. not globs.
+  Rename(Rename),
   /// Deletes a file or directory. Glob-friendly.
   Delete(Delete),
+  /// Deletes a file or directory if a boolean value is `true` (`if`) or `false` (`unless`).
+  Discard(Discard),
+  /// Creates an empty file or updates its modification time. Glob-friendly.
+  Touch(Touch),
   /// Echoes a message to stdout.
   Echo(Echo),
   /// Runs an arbitrary command in the shell.
   Run(Run),
   /// Executes a prompt asking a declaratively defined "question".
   Prompt(Prompt),
+  /// Runs a group of prompts under a common header.
+  Group(Group),
+  /// Sets a value derived from other values without prompting.
+  Set(Set),
   /// Execute given replacements using values provided by prompts. Optionally, only apply
   /// replacements to files matching the provided glob.
   Replace(Replace),
+  /// Sets values in a JSON, TOML or YAML file.
+  Patch(Patch),
+  /// Writes variables to a dotenv file.
+  Env(Env),
+  /// Downloads a file from the URL.
+  Fetch(Fetch),
   /// Fallback action for pattern matching ergonomics and reporting purposes.
   Unknown(Unknown),
 }
 
+impl ActionSingle {
+  /// Returns the name of the action as written in the config.
+  pub fn name(&self) -> &str {
+    match self {
+      | Self::Copy(_) => "cp",
+      | Self::Move(_) => "mv",
+      | Self::Rename(_) => "rename",
+      | Self::Delete(_) => "rm",
+      | Self::Discard(_) => "drop",
+      | Self::Touch(_) => "touch",
+      | Self::Echo(_) => "echo",
+      | Self::Run(_) => "run",
+      | Self::Prompt(Prompt::Input(_)) => "input",
+      | Self::Prompt(Prompt::Number(_)) => "number",
+      | Self::Prompt(Prompt::Select(_)) => "select",
+      | Self::Prompt(Prompt::Confirm(_)) => "confirm",
+      | Self::Prompt(Prompt::Editor(_)) => "editor",
+      | Self::Prompt(Prompt::Path(_)) => "path",
+      | Self::Group(_) => "group",
+      | Self::Set(_) => "let",
+      | Self::Replace(_) => "replace",
+      | Self::Patch(_) => "patch",
+      | Self::Env(_) => "env",
+      | Self::Fetch(_) => "fetch",
+      | Self::Unknown(action) => &action.name,
+    }
+  }
+
+  /// Returns fields of the results captured from the action if it has an id, e.g. `stdout`.
+  pub fn captures(&self) -> &'static [&'static str] {
+    match self {
+      | Self::Run(_) => &["stdout", "value"],
+      | Self::Prompt(_) => &["value"],
+      | _ => &[],
+    }
+  }
+}
+
 /// decaff config.
 #[derive(Debug)]
 pub struct Config {
@@ -138,6 +371,19 @@ pub struct Config {
   pub options: ConfigOptions,
   /// Actions.
   pub actions: Actions,
+  /// Messages to print once everything is done, even if the config file was deleted.
+  pub done: Vec<Echo>,
+  /// Tools that have to be installed to run the actions.
+  pub requirements: Vec<Requirement>,
+}
+
+/// A tool required by the template.
+#[derive(Debug)]
+pub struct Requirement {
+  /// Name of the binary that has to be on `PATH`.
+  pub bin: String,
+  /// Optional version requirement, checked against the output of `<bin> --version`.
+  pub version: Option<VersionReq>,
 }
 
 impl Config {
@@ -158,6 +404,8 @@ impl Config {
       config,
       options: ConfigOptions::default(),
       actions: Actions::Empty,
+      done: Vec::new(),
+      requirements: Vec::new(),
       source,
       root,
     }
@@ -168,6 +416,10 @@ impl Config {
     if let Some(delete) = overrides.delete {
       self.options.delete = delete;
     }
+
+    if let Some(git_init) = overrides.git_init {
+      self.options.git_init = git_init;
+    }
   }
 
   /// Tries to load and parse the config.
@@ -176,6 +428,8 @@ impl Config {
       let doc = self.parse()?;
       self.options = self.get_config_options(&doc)?;
       self.actions = self.get_config_actions(&doc)?;
+      self.done = self.get_config_done(&doc)?;
+      self.requirements = self.get_config_requirements(&doc)?;
 
       Ok(true)
     } else {
@@ -221,17 +475,29 @@ impl Config {
 
           match option.as_str() {
             | "delete" => {
-              defaults.delete = node.get_bool(0).ok_or_else(|| {
-                diagnostic!(
-                  source = &self.source,
-                  code = "decaff::config::options",
-                  labels = vec![LabeledSpan::at(
-                    node.span().to_owned(),
-                    "this node requires a boolean argument"
-                  )],
-                  "Missing required argument."
-                )
-              })?;
+              defaults.delete = self.get_option_bool(node)?;
+            },
+            | "strict" => {
+              defaults.strict = self.get_option_bool(node)?;
+            },
+            | "auto_inject" => {
+              defaults.auto_inject = self.get_option_bool(node)?;
+            },
+            | "confirm_destructive" => {
+              defaults.confirm_destructive = self.get_option_bool(node)?;
+            },
+            | "replace_diff" => {
+              defaults.replace_diff = self.get_option_bool(node)?;
+            },
+            | "min_version" => {
+              self.check_min_version(node)?;
+            },
+            | "git_init" => {
+              defaults.git_init = self.get_option_bool(node)?;
+
+              if let Some(message) = node.get_string("message") {
+                defaults.git_message = message;
+              }
             },
             | _ => {
               continue;
@@ -249,6 +515,57 @@ impl Config {
     }
   }
 
+  /// Checks that the running version satisfies the `min_version` option, so templates relying on
+  /// newer features fail early instead of having unknown actions.
+  fn check_min_version(&self, node: &KdlNode) -> Result<(), ConfigError> {
+    let required = node
+      .get_string(0)
+      .and_then(|version| Version::parse(&version).ok())
+      .ok_or_else(|| {
+        diagnostic!(
+          source = &self.source,
+          code = "decaff::config::options",
+          labels = vec![LabeledSpan::at(
+            node.span().to_owned(),
+            "this node requires a version argument, e.g. \"0.5.0\""
+          )],
+          "Invalid minimum version."
+        )
+      })?;
+
+    let current = Version::parse(env!("CARGO_PKG_VERSION")).expect("Crate version is valid");
+
+    if current < required {
+      return Err(diagnostic!(
+        source = &self.source,
+        code = "decaff::config::min_version",
+        labels = vec![LabeledSpan::at(
+          node.span().to_owned(),
+          format!("requires {required}")
+        )],
+        help = format!("Upgrade decaff to {required} or newer."),
+        "This template requires decaff {required}, but {current} is installed."
+      ));
+    }
+
+    Ok(())
+  }
+
+  /// Tries to get a boolean argument of an option node.
+  fn get_option_bool(&self, node: &KdlNode) -> Result<bool, ConfigError> {
+    node.get_bool(0).ok_or_else(|| {
+      diagnostic!(
+        source = &self.source,
+        code = "decaff::config::options",
+        labels = vec![LabeledSpan::at(
+          node.span().to_owned(),
+          "this node requires a boolean argument"
+        )],
+        "Missing required argument."
+      )
+    })
+  }
+
   /// Tries to parse actions from the config.
   fn get_config_actions(&self, doc: &KdlDocument) -> Result<Actions, ConfigError> {
     #[inline]
@@ -283,7 +600,7 @@ impl Config {
           let mut actions = Vec::new();
 
           for node in nodes.iter() {
-            let action = self.get_action_single(node)?;
+            let action = self.get_action(node)?;
             actions.push(action);
           }
 
@@ -299,11 +616,99 @@ impl Config {
         }
       });
 
-    match actions {
-      | Some(Ok(action)) => Ok(action),
-      | Some(Err(err)) => Err(err),
-      | None => Ok(Actions::Empty),
+    let actions = match actions {
+      | Some(Ok(action)) => action,
+      | Some(Err(err)) => return Err(err),
+      | None => Actions::Empty,
+    };
+
+    let mut ids = HashSet::new();
+
+    for id in actions.iter().filter_map(|action| action.id.as_ref()) {
+      if !ids.insert(id) {
+        return Err(ConfigError::Diagnostic(miette::miette!(
+          code = "decaff::config::actions",
+          "Action id `{id}` is used more than once. Ids must be unique across all actions."
+        )));
+      }
     }
+
+    Ok(actions)
+  }
+
+  fn get_config_done(&self, doc: &KdlDocument) -> Result<Vec<Echo>, ConfigError> {
+    let mut messages = Vec::new();
+
+    let nodes = doc
+      .get("done")
+      .and_then(KdlNode::children)
+      .map(KdlDocument::nodes)
+      .unwrap_or_default();
+
+    for node in nodes {
+      match self.get_action_single(node)? {
+        | ActionSingle::Echo(echo) => messages.push(echo),
+        | _ => {
+          return Err(diagnostic!(
+            source = &self.source,
+            code = "decaff::config::done",
+            labels = vec![LabeledSpan::at(
+              node.span().to_owned(),
+              "only `echo` is allowed here"
+            )],
+            "Invalid `done` block."
+          ));
+        },
+      }
+    }
+
+    Ok(messages)
+  }
+
+  /// Tries to parse the `requirements` block, which lists tools needed by actions.
+  fn get_config_requirements(&self, doc: &KdlDocument) -> Result<Vec<Requirement>, ConfigError> {
+    let nodes = doc
+      .get("requirements")
+      .and_then(KdlNode::children)
+      .map(KdlDocument::nodes)
+      .unwrap_or_default();
+
+    let mut requirements = Vec::new();
+
+    for node in nodes {
+      if node.name().value() != "bin" {
+        return Err(diagnostic!(
+          source = &self.source,
+          code = "decaff::config::requirements",
+          labels = vec![LabeledSpan::at(
+            node.span().to_owned(),
+            "only `bin` is allowed here"
+          )],
+          "Invalid `requirements` block."
+        ));
+      }
+
+      let version = node
+        .get_string("version")
+        .map(|version| {
+          VersionReq::parse(&version).map_err(|_| {
+            diagnostic!(
+              source = &self.source,
+              code = "decaff::config::requirements",
+              labels = vec![LabeledSpan::at(
+                node.span().to_owned(),
+                "expected a version requirement, e.g. \">=8.0\""
+              )],
+              "Invalid version requirement: `{version}`."
+            )
+          })
+        })
+        .transpose()?;
+
+      requirements.push(Requirement { bin: self.get_arg_string(node)?, version });
+    }
+
+    Ok(requirements)
   }
 
   fn get_action_suite(&self, node: &KdlNode) -> Result<ActionSuite, ConfigError> {
@@ -311,15 +716,30 @@ impl Config {
 
     // Fail if we stumbled upon a nameless suite.
     let name = self.get_arg_string(node)?;
+    let os = self.get_os(node)?.unwrap_or_default();
+
+    let tags = node
+      .get_string("tags")
+      .map(|tags| tags.split_whitespace().map(str::to_string).collect())
+      .unwrap_or_default();
 
     if let Some(children) = node.children() {
       for children in children.nodes() {
-        let action = self.get_action_single(children)?;
+        let action = self.get_action(children)?;
         actions.push(action);
       }
     }
 
-    Ok(ActionSuite { name, actions })
+    Ok(ActionSuite { name, os, tags, actions })
+  }
+
+  fn get_action(&self, node: &KdlNode) -> Result<Action, ConfigError> {
+    Ok(Action {
+      id: self.get_action_id(node)?,
+      os: self.get_os(node)?,
+      description: node.get_string("description"),
+      kind: self.get_action_single(node)?,
+    })
   }
 
   fn get_action_single(&self, node: &KdlNode) -> Result<ActionSingle, ConfigError> {
@@ -329,25 +749,44 @@ impl Config {
       // Actions for manipulating files and directories.
       | "cp" => {
         ActionSingle::Copy(Copy {
-          from: self.get_attr_string(node, "from")?,
+          from: self.get_sources(node)?,
           to: self.get_attr_string(node, "to")?,
-          overwrite: node.get_bool("overwrite").unwrap_or(true),
+          overwrite: self.get_overwrite_policy(node)?,
+          preserve: node.get_bool("preserve").unwrap_or(true),
+          exclude: node.get_string("exclude"),
+          render: node.get_bool("render").unwrap_or(false),
+          flatten: node.get_bool("flatten").unwrap_or(false),
         })
       },
       | "mv" => {
         ActionSingle::Move(Move {
+          from: self.get_sources(node)?,
+          to: self.get_attr_string(node, "to")?,
+          overwrite: self.get_overwrite_policy(node)?,
+          preserve: node.get_bool("preserve").unwrap_or(true),
+        })
+      },
+      | "rename" => {
+        ActionSingle::Rename(Rename {
           from: self.get_attr_string(node, "from")?,
           to: self.get_attr_string(node, "to")?,
-          overwrite: node.get_bool("overwrite").unwrap_or(true),
         })
       },
       | "rm" => ActionSingle::Delete(Delete { target: self.get_arg_string(node)? }),
+      | "drop" => {
+        ActionSingle::Discard(Discard {
+          target: self.get_arg_string(node)?,
+          condition: self.get_condition(node)?,
+        })
+      },
+      | "touch" => ActionSingle::Touch(Touch { target: self.get_arg_string(node)? }),
       // Actions for running commands and echoing output.
       | "echo" => {
         ActionSingle::Echo(Echo {
           message: self.get_arg_string(node)?,
           injects: self.get_injects(node),
           trim: node.get_bool("trim").unwrap_or(true),
+          separator: self.get_separator(node),
         })
       },
       | "run" => {
@@ -355,6 +794,7 @@ impl Config {
           name: node.get_string("name"),
           command: self.get_arg_string(node)?,
           injects: self.get_injects(node),
+          cwd: node.get_string("cwd"),
         })
       },
       // Actions for prompts and replacements.
@@ -365,6 +805,7 @@ impl Config {
           name: self.get_arg_string(node)?,
           hint: self.get_hint(node, nodes)?,
           default: self.get_default_string(nodes),
+          env: node.get_string("env"),
         }))
       },
       | "number" => {
@@ -374,6 +815,7 @@ impl Config {
           name: self.get_arg_string(node)?,
           hint: self.get_hint(node, nodes)?,
           default: self.get_default_number(nodes),
+          env: node.get_string("env"),
         }))
       },
       | "editor" => {
@@ -383,6 +825,9 @@ impl Config {
           name: self.get_arg_string(node)?,
           hint: self.get_hint(node, nodes)?,
           default: self.get_default_string(nodes),
+          default_file: node
+            .get_string("default_file")
+            .map(|path| self.root.join(path)),
         }))
       },
       | "select" => {
@@ -392,6 +837,8 @@ impl Config {
           name: self.get_arg_string(node)?,
           hint: self.get_hint(node, nodes)?,
           options: self.get_options(node, nodes)?,
+          default: self.get_default_option(nodes),
+          env: node.get_string("env"),
         }))
       },
       | "confirm" => {
@@ -401,8 +848,54 @@ impl Config {
           name: self.get_arg_string(node)?,
           hint: self.get_hint(node, nodes)?,
           default: self.get_default_bool(nodes),
+          env: node.get_string("env"),
+        }))
+      },
+      | "path" => {
+        let nodes = self.get_children(node, vec!["hint"])?;
+
+        ActionSingle::Prompt(Prompt::Path(PathPrompt {
+          name: self.get_arg_string(node)?,
+          hint: self.get_hint(node, nodes)?,
+          default: self.get_default_string(nodes),
+          env: node.get_string("env"),
+          must_exist: node.get_bool("must_exist").unwrap_or(false),
+          kind: self.get_path_kind(node)?,
         }))
       },
+      | "group" => {
+        let mut prompts = Vec::new();
+
+        for child in node.children().map(KdlDocument::nodes).unwrap_or_default() {
+          let action = self.get_action(child)?;
+
+          if !matches!(action.kind, ActionSingle::Prompt(_)) {
+            return Err(diagnostic!(
+              source = &self.source,
+              code = "decaff::config::actions",
+              labels = vec![LabeledSpan::at(
+                child.span().to_owned(),
+                "only prompts are allowed here"
+              )],
+              "Invalid `group` block."
+            ));
+          }
+
+          prompts.push(action);
+        }
+
+        ActionSingle::Group(Group {
+          title: self.get_arg_string(node)?,
+          prompts,
+        })
+      },
+      | "let" | "set" => {
+        ActionSingle::Set(Set {
+          name: self.get_arg_string(node)?,
+          value: self.get_set_value(node)?,
+          separator: self.get_separator(node),
+        })
+      },
       | "replace" => {
         let replacements = node
           .children()
@@ -416,11 +909,61 @@ impl Config {
           .unwrap_or_default();
 
         let glob = node.get_string("in");
+        let exclude = node.get_string("exclude");
+        let separator = self.get_separator(node);
+        let delimiters = self.get_delimiters(node)?;
+
+        ActionSingle::Replace(Replace {
+          replacements,
+          glob,
+          exclude,
+          separator,
+          delimiters,
+        })
+      },
+      | "patch" => {
+        let nodes = self.get_children(node, vec!["set"])?;
 
-        ActionSingle::Replace(Replace { replacements, glob })
+        ActionSingle::Patch(Patch {
+          target: self.get_arg_string(node)?,
+          sets: self.get_patch_sets(nodes)?,
+        })
+      },
+      | "fetch" => {
+        ActionSingle::Fetch(Fetch {
+          url: self.get_attr_string(node, "url")?,
+          to: self.get_attr_string(node, "to")?,
+          overwrite: node.get_bool("overwrite").unwrap_or(true),
+        })
+      },
+      | "env" => {
+        ActionSingle::Env(Env {
+          target: self.get_arg_string(node)?,
+          vars: self.get_env_vars(node)?,
+          quote: node.get_bool("quote").unwrap_or(false),
+        })
       },
       // Fallback.
-      | action => ActionSingle::Unknown(Unknown { name: action.to_string() }),
+      | action if !self.options.strict => {
+        ActionSingle::Unknown(Unknown { name: action.to_string() })
+      },
+      | action => {
+        let start = node.span().offset();
+        let end = start + node.name().len();
+
+        let help = suggest(action, &KNOWN_ACTIONS).map_or_else(
+          || format!("Known actions are: {}.", KNOWN_ACTIONS.join(", ")),
+          |suggestion| format!("Did you mean `{suggestion}`?"),
+        );
+
+        return Err(diagnostic!(
+          source = &self.source,
+          code = "decaff::config::actions",
+          labels = vec![LabeledSpan::at(start..end, "unknown action")],
+          help = help,
+          "Unknown action: `{action}`."
+        ));
+      },
     };
 
     Ok(action)
@@ -443,6 +986,21 @@ impl Config {
     })
   }
 
+  /// Returns the expression of a `let` action, i.e. its second argument.
+  fn get_set_value(&self, node: &KdlNode) -> Result<String, ConfigError> {
+    node.get_string(1).ok_or_else(|| {
+      diagnostic!(
+        source = &self.source,
+        code = "decaff::config::actions",
+        labels = vec![LabeledSpan::at(
+          node.span().to_owned(),
+          "this node requires a name and a value, e.g. `let \"SLUG\" \"{NAME:kebab}\"`"
+        )],
+        "Missing value."
+      )
+    })
+  }
+
   fn get_attr_string(&self, node: &KdlNode, key: &str) -> Result<String, ConfigError> {
     node.get_string(key).ok_or_else(|| {
       diagnostic!(
@@ -457,6 +1015,156 @@ impl Config {
     })
   }
 
+  /// Returns the kind of the entry a `path` prompt expects. Defaults to [PathKind::Any].
+  fn get_path_kind(&self, node: &KdlNode) -> Result<PathKind, ConfigError> {
+    let Some(entry) = node.get("kind") else {
+      return Ok(PathKind::default());
+    };
+
+    match entry.value().as_string() {
+      | Some("any") => Ok(PathKind::Any),
+      | Some("file") => Ok(PathKind::File),
+      | Some("dir") => Ok(PathKind::Dir),
+      | _ => {
+        Err(diagnostic!(
+          source = &self.source,
+          code = "decaff::config::actions",
+          labels = vec![LabeledSpan::at(
+            entry.span().to_owned(),
+            "invalid path kind"
+          )],
+          help = "Use one of: any, file, dir.",
+          "Invalid value of the `kind` attribute."
+        ))
+      },
+    }
+  }
+
+  /// Returns the condition from either the `if` or the `unless` attribute. Exactly one is required.
+  fn get_condition(&self, node: &KdlNode) -> Result<Condition, ConfigError> {
+    match (node.get_string("if"), node.get_string("unless")) {
+      | (Some(name), None) => Ok(Condition::If(name)),
+      | (None, Some(name)) => Ok(Condition::Unless(name)),
+      | _ => {
+        Err(diagnostic!(
+          source = &self.source,
+          code = "decaff::config::actions",
+          labels = vec![LabeledSpan::at(
+            node.span().to_owned(),
+            "this node requires either the `if` or the `unless` attribute"
+          )],
+          "Missing or ambiguous condition."
+        ))
+      },
+    }
+  }
+
+  /// Returns sources from either the `from` attribute, which is a single pattern, or the arguments
+  /// of the `from` child node.
+  fn get_sources(&self, node: &KdlNode) -> Result<Vec<String>, ConfigError> {
+    let sources = node
+      .children()
+      .map(|children| {
+        children
+          .get_args("from")
+          .into_iter()
+          .filter_map(|arg| arg.as_string().map(str::to_string))
+          .collect::<Vec<_>>()
+      })
+      .unwrap_or_default();
+
+    if !sources.is_empty() {
+      return Ok(sources);
+    }
+
+    Ok(vec![self.get_attr_string(node, "from")?])
+  }
+
+  /// Returns the overwrite policy from the `overwrite` attribute, which is either a boolean or one
+  /// of `always`, `never`, `if-newer` and `prompt`.
+  fn get_overwrite_policy(&self, node: &KdlNode) -> Result<OverwritePolicy, ConfigError> {
+    if let Some(overwrite) = node.get_bool("overwrite") {
+      return Ok(if overwrite {
+        OverwritePolicy::Always
+      } else {
+        OverwritePolicy::Never
+      });
+    }
+
+    let Some(entry) = node.get("overwrite") else {
+      return Ok(OverwritePolicy::default());
+    };
+
+    match entry.value().as_string() {
+      | Some("always") => Ok(OverwritePolicy::Always),
+      | Some("never") => Ok(OverwritePolicy::Never),
+      | Some("if-newer") => Ok(OverwritePolicy::IfNewer),
+      | Some("prompt") => Ok(OverwritePolicy::Prompt),
+      | _ => {
+        Err(diagnostic!(
+          source = &self.source,
+          code = "decaff::config::actions",
+          labels = vec![LabeledSpan::at(
+            entry.span().to_owned(),
+            "invalid overwrite policy"
+          )],
+          help = "Use a boolean, or one of: always, never, if-newer, prompt.",
+          "Invalid value of the `overwrite` attribute."
+        ))
+      },
+    }
+  }
+
+  /// Returns `set` child nodes of the `patch` action as path and value pairs.
+  fn get_patch_sets(&self, nodes: &KdlDocument) -> Result<Vec<PatchSet>, ConfigError> {
+    nodes
+      .nodes()
+      .iter()
+      .filter(|node| node.name().value() == "set")
+      .map(|node| {
+        let path = self.get_arg_string(node)?;
+
+        let value = node
+          .get_string(1)
+          .map(Value::String)
+          .or_else(|| node.get_number(1).map(Value::Number))
+          .or_else(|| node.get_bool(1).map(Value::Bool))
+          .ok_or_else(|| {
+            diagnostic!(
+              source = &self.source,
+              code = "decaff::config::actions",
+              labels = vec![LabeledSpan::at(
+                node.span().to_owned(),
+                "this node requires a string, number or boolean value after the path"
+              )],
+              "Missing value to set."
+            )
+          })?;
+
+        Ok(PatchSet { path, value })
+      })
+      .collect()
+  }
+
+  /// Returns child nodes of the `env` action as key and value pairs.
+  fn get_env_vars(&self, node: &KdlNode) -> Result<Vec<EnvVar>, ConfigError> {
+    node
+      .children()
+      .map(|children| {
+        children
+          .nodes()
+          .iter()
+          .map(|node| {
+            Ok(EnvVar {
+              key: node.name().value().to_string(),
+              value: self.get_arg_string(node)?,
+            })
+          })
+          .collect()
+      })
+      .unwrap_or_else(|| Ok(Vec::new()))
+  }
+
   fn get_children<'kdl>(
     &self,
     node: &'kdl KdlNode,
@@ -510,6 +1218,96 @@ impl Config {
     })
   }
 
+  fn get_action_id(&self, node: &KdlNode) -> Result<Option<String>, ConfigError> {
+    let Some(entry) = node.get("id") else {
+      return Ok(None);
+    };
+
+    let id = entry
+      .value()
+      .as_string()
+      .filter(|id| {
+        !id.is_empty()
+          && id
+            .chars()
+            .all(|ch| ch.is_alphanumeric() || matches!(ch, '_' | '-'))
+      })
+      .ok_or_else(|| {
+        diagnostic!(
+          source = &self.source,
+          code = "decaff::config::actions",
+          labels = vec![LabeledSpan::at(
+            entry.span().to_owned(),
+            "must consist of letters, digits, `_` and `-`"
+          )],
+          "Invalid `id` attribute."
+        )
+      })?;
+
+    Ok(Some(id.to_string()))
+  }
+
+  fn get_os(&self, node: &KdlNode) -> Result<Option<Os>, ConfigError> {
+    let Some(entry) = node.get("os") else {
+      return Ok(None);
+    };
+
+    let os = entry
+      .value()
+      .as_string()
+      .map(str::to_ascii_lowercase)
+      .filter(|os| os == "all" || KNOWN_PLATFORMS.contains(&os.as_str()))
+      .ok_or_else(|| {
+        diagnostic!(
+          source = &self.source,
+          code = "decaff::config::actions",
+          labels = vec![LabeledSpan::at(
+            entry.span().to_owned(),
+            format!("must be `all` or one of: {}", KNOWN_PLATFORMS.join(", "))
+          )],
+          "Invalid `os` attribute."
+        )
+      })?;
+
+    if os == "all" {
+      Ok(Some(Os::All))
+    } else {
+      Ok(Some(Os::Only(os)))
+    }
+  }
+
+  fn get_separator(&self, node: &KdlNode) -> String {
+    node
+      .get_string("separator")
+      .unwrap_or_else(|| DEFAULT_SEPARATOR.to_string())
+  }
+
+  fn get_delimiters(&self, node: &KdlNode) -> Result<(String, String), ConfigError> {
+    let (default_open, default_close) = DEFAULT_DELIMITERS;
+
+    let open = node
+      .get_string("open")
+      .unwrap_or_else(|| default_open.to_string());
+
+    let close = node
+      .get_string("close")
+      .unwrap_or_else(|| default_close.to_string());
+
+    if open.is_empty() || close.is_empty() {
+      return Err(diagnostic!(
+        source = &self.source,
+        code = "decaff::config::actions",
+        labels = vec![LabeledSpan::at(
+          node.span().to_owned(),
+          "`open` and `close` can't be empty"
+        )],
+        "Empty placeholder delimiters."
+      ));
+    }
+
+    Ok((open, close))
+  }
+
   fn get_options(&self, parent: &KdlNode, nodes: &KdlDocument) -> Result<Vec<String>, ConfigError> {
     let options = nodes.get("options").ok_or_else(|| {
       diagnostic!(
@@ -559,9 +1357,33 @@ impl Config {
         )
       })?;
 
+      // Options are normalized by trimming, so e.g. "npm" and " npm" are considered duplicates.
+      let option = option.trim().to_string();
+
+      if variants.contains(&option) {
+        return Err(diagnostic!(
+          source = &self.source,
+          code = "decaff::config::actions",
+          labels = vec![LabeledSpan::at(span, "this option is already listed")],
+          "Duplicate select option: `{option}`."
+        ));
+      }
+
       variants.push(option);
     }
 
+    if variants.is_empty() {
+      return Err(diagnostic!(
+        source = &self.source,
+        code = "decaff::config::actions",
+        labels = vec![LabeledSpan::at(
+          options.span().to_owned(),
+          "select prompts require at least one option"
+        )],
+        "Empty select prompt options."
+      ));
+    }
+
     Ok(variants)
   }
 
@@ -569,6 +1391,14 @@ impl Config {
     nodes.get("default").and_then(|node| node.get_string(0))
   }
 
+  fn get_default_option(&self, nodes: &KdlDocument) -> Option<String> {
+    self.get_default_string(nodes).or_else(|| {
+      self
+        .get_default_number(nodes)
+        .map(|number| number.to_string())
+    })
+  }
+
   fn get_default_bool(&self, nodes: &KdlDocument) -> Option<bool> {
     nodes.get("default").and_then(|node| node.get_bool(0))
   }
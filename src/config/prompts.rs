@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use crate::config::value::Number;
 
 #[derive(Debug)]
@@ -8,6 +10,8 @@ pub struct InputPrompt {
   pub hint: String,
   /// Default value if input is empty.
   pub default: Option<String>,
+  /// Name of the environment variable to take the default value from. Overrides `default` if set.
+  pub env: Option<String>,
 }
 
 #[derive(Debug)]
@@ -18,6 +22,8 @@ pub struct NumberPrompt {
   pub hint: String,
   /// Default value if input is empty.
   pub default: Option<Number>,
+  /// Name of the environment variable to take the default value from. Overrides `default` if set.
+  pub env: Option<String>,
 }
 
 #[derive(Debug)]
@@ -28,6 +34,10 @@ pub struct SelectPrompt {
   pub hint: String,
   /// List of options.
   pub options: Vec<String>,
+  /// Default option. Ignored if it doesn't match any option.
+  pub default: Option<String>,
+  /// Name of the environment variable to take the default value from. Overrides `default` if set.
+  pub env: Option<String>,
 }
 
 #[derive(Debug)]
@@ -38,6 +48,8 @@ pub struct ConfirmPrompt {
   pub hint: String,
   /// Default value.
   pub default: Option<bool>,
+  /// Name of the environment variable to take the default value from. Overrides `default` if set.
+  pub env: Option<String>,
 }
 
 #[derive(Debug)]
@@ -48,4 +60,35 @@ pub struct EditorPrompt {
   pub hint: String,
   /// Default value if input is empty.
   pub default: Option<String>,
+  /// File to read the default value from, resolved against the root. Takes precedence over
+  /// `default` if set.
+  pub default_file: Option<PathBuf>,
+}
+
+/// Kind of the filesystem entry a [PathPrompt] expects.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PathKind {
+  /// Either a file or a directory.
+  #[default]
+  Any,
+  /// A file.
+  File,
+  /// A directory.
+  Dir,
+}
+
+#[derive(Debug)]
+pub struct PathPrompt {
+  /// Name of the variable that will store the answer.
+  pub name: String,
+  /// Short description.
+  pub hint: String,
+  /// Default value if input is empty.
+  pub default: Option<String>,
+  /// Name of the environment variable to take the default value from. Overrides `default` if set.
+  pub env: Option<String>,
+  /// Whether the path has to exist. Defaults to `false`.
+  pub must_exist: bool,
+  /// Kind of the entry the path has to point to if it exists. Defaults to [PathKind::Any].
+  pub kind: PathKind,
 }
@@ -38,3 +38,266 @@ where
     self.get(key).and_then(|entry| entry.value().as_bool())
   }
 }
+
+/// Suggests the closest candidate to the given input using the Levenshtein distance. Returns `None`
+/// if no candidate is close enough.
+pub fn suggest<'c>(input: &str, candidates: &[&'c str]) -> Option<&'c str> {
+  candidates
+    .iter()
+    .map(|candidate| (candidate, distance(input, candidate)))
+    .filter(|(candidate, distance)| *distance <= (candidate.len() / 2).max(1))
+    .min_by_key(|(_, distance)| *distance)
+    .map(|(candidate, _)| *candidate)
+}
+
+/// Filters that can follow the name of a placeholder, e.g. `{name:kebab}`.
+pub const FILTERS: [&str; 7] = [
+  "json", "kebab", "snake", "camel", "pascal", "upper", "lower",
+];
+
+/// Splits the contents of a placeholder into the name and the filter, if it's one of [FILTERS].
+pub fn split_filter(token: &str) -> (&str, Option<&str>) {
+  match token.rsplit_once(':') {
+    | Some((name, filter)) if FILTERS.contains(&filter) => (name, Some(filter)),
+    | _ => (token, None),
+  }
+}
+
+/// Splits the input into words on non-alphanumeric characters and case changes, so `myApp`,
+/// `my-app` and `HTTPServer` become `my app`, `my app` and `HTTP Server`.
+fn words(input: &str) -> Vec<String> {
+  let chars = input.chars().collect::<Vec<_>>();
+  let mut words = Vec::new();
+  let mut word = String::new();
+
+  for (index, &ch) in chars.iter().enumerate() {
+    if !ch.is_alphanumeric() {
+      if !word.is_empty() {
+        words.push(std::mem::take(&mut word));
+      }
+      continue;
+    }
+
+    if !word.is_empty() && ch.is_uppercase() {
+      let prev = chars[index - 1];
+      let next = chars.get(index + 1);
+
+      if !prev.is_uppercase() || next.is_some_and(|next| next.is_lowercase()) {
+        words.push(std::mem::take(&mut word));
+      }
+    }
+
+    word.push(ch);
+  }
+
+  if !word.is_empty() {
+    words.push(word);
+  }
+
+  words
+}
+
+/// Uppercases the first character of the word and lowercases the rest.
+fn capitalize(word: &str) -> String {
+  let mut chars = word.chars();
+
+  match chars.next() {
+    | Some(first) => {
+      first
+        .to_uppercase()
+        .chain(chars.flat_map(char::to_lowercase))
+        .collect()
+    },
+    | None => String::new(),
+  }
+}
+
+/// Converts the input to the case named by a filter, e.g. `kebab`. Unknown filters leave the input
+/// as-is.
+pub fn convert_case(input: &str, filter: &str) -> String {
+  let words = words(input);
+  let lower = || words.iter().map(|word| word.to_lowercase());
+
+  match filter {
+    | "kebab" => lower().collect::<Vec<_>>().join("-"),
+    | "snake" => lower().collect::<Vec<_>>().join("_"),
+    | "camel" => {
+      lower()
+        .take(1)
+        .chain(words.iter().skip(1).map(|word| capitalize(word)))
+        .collect()
+    },
+    | "pascal" => words.iter().map(|word| capitalize(word)).collect(),
+    | "upper" => input.to_uppercase(),
+    | "lower" => input.to_lowercase(),
+    | _ => input.to_string(),
+  }
+}
+
+/// Replaces `{name}` and `{name:filter}` placeholders in the input with whatever `resolve` returns
+/// for the placeholder's contents, e.g. `name` or `name:json`. Placeholders `resolve` returns
+/// `None` for are left as-is. Literal braces can be escaped by doubling them: `{{` and `}}`.
+pub fn fill_placeholders<F>(input: &str, mut resolve: F) -> String
+where
+  F: FnMut(&str) -> Option<String>,
+{
+  let mut output = String::with_capacity(input.len());
+  let mut rest = input;
+
+  while let Some(start) = rest.find(['{', '}']) {
+    output.push_str(&rest[..start]);
+    rest = &rest[start..];
+
+    // Escaped braces.
+    if rest.starts_with("{{") || rest.starts_with("}}") {
+      output.push_str(&rest[..1]);
+      rest = &rest[2..];
+      continue;
+    }
+
+    if rest.starts_with('{') {
+      if let Some(end) = rest[1..].find('}').map(|end| end + 1) {
+        let token = &rest[1..end];
+
+        if is_placeholder(token) {
+          if let Some(replacement) = resolve(token) {
+            output.push_str(&replacement);
+            rest = &rest[end + 1..];
+            continue;
+          }
+        }
+      }
+    }
+
+    output.push_str(&rest[..1]);
+    rest = &rest[1..];
+  }
+
+  output.push_str(rest);
+  output
+}
+
+/// Returns names of `{name}` and `{name:filter}` placeholders found in the input, skipping escaped
+/// braces.
+pub fn placeholders(input: &str) -> Vec<String> {
+  let mut names = Vec::new();
+
+  fill_placeholders(input, |token| {
+    names.push(split_filter(token).0.to_string());
+    None
+  });
+
+  names
+}
+
+/// Checks if the contents of braces look like a placeholder, so things like shell brace expansion
+/// (`{a,b}`) or code blocks are not mistaken for one.
+fn is_placeholder(token: &str) -> bool {
+  let (name, _) = split_filter(token);
+
+  !name.is_empty()
+    && name
+      .chars()
+      .all(|ch| ch.is_alphanumeric() || matches!(ch, '_' | '-' | '.'))
+}
+
+/// Computes the Levenshtein distance between two strings.
+fn distance(left: &str, right: &str) -> usize {
+  let right = right.chars().collect::<Vec<_>>();
+  let mut row = (0..=right.len()).collect::<Vec<_>>();
+
+  for (i, lch) in left.chars().enumerate() {
+    let mut diagonal = row[0];
+    row[0] = i + 1;
+
+    for (j, rch) in right.iter().enumerate() {
+      let cost = usize::from(lch != *rch);
+      let next = (row[j] + 1).min(row[j + 1] + 1).min(diagonal + cost);
+
+      diagonal = row[j + 1];
+      row[j + 1] = next;
+    }
+  }
+
+  row[right.len()]
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn levenshtein_distance() {
+    let cases = [
+      ("", "", 0),
+      ("cp", "cp", 0),
+      ("cpy", "cp", 1),
+      ("reaplce", "replace", 2),
+      ("kitten", "sitting", 3),
+      ("", "echo", 4),
+    ];
+
+    for (left, right, expected) in cases {
+      assert_eq!(distance(left, right), expected);
+    }
+  }
+
+  #[test]
+  fn suggest_closest() {
+    let candidates = ["cp", "mv", "rm", "echo", "run", "replace"];
+
+    assert_eq!(suggest("cpy", &candidates), Some("cp"));
+    assert_eq!(suggest("reaplce", &candidates), Some("replace"));
+    assert_eq!(suggest("ehco", &candidates), Some("echo"));
+    assert_eq!(suggest("definitely", &candidates), None);
+  }
+
+  #[test]
+  fn fill_known_placeholders() {
+    let resolve = |token: &str| {
+      match token {
+        | "name" => Some("decaff".to_string()),
+        | "name:json" => Some(r#""decaff""#.to_string()),
+        | _ => None,
+      }
+    };
+
+    let cases = [
+      ("{name}-core", "decaff-core"),
+      ("{name:json}", r#""decaff""#),
+      ("{unknown} {name}", "{unknown} decaff"),
+      ("{{name}} {name}", "{name} decaff"),
+      ("echo {a,b} {}", "echo {a,b} {}"),
+      ("{ {name} }", "{ decaff }"),
+    ];
+
+    for (input, expected) in cases {
+      assert_eq!(fill_placeholders(input, resolve), expected);
+    }
+  }
+
+  #[test]
+  fn find_placeholders() {
+    assert_eq!(
+      placeholders("{name} {{escaped}} {list:json} {slug:kebab} {a,b}"),
+      vec!["name", "list", "slug"]
+    );
+  }
+
+  #[test]
+  fn convert_cases() {
+    let cases = [
+      ("my cool-app", "kebab", "my-cool-app"),
+      ("myCoolApp", "snake", "my_cool_app"),
+      ("HTTPServer v2", "kebab", "http-server-v2"),
+      ("my_cool_app", "camel", "myCoolApp"),
+      ("my-cool-app", "pascal", "MyCoolApp"),
+      ("my-app", "upper", "MY-APP"),
+      ("My App", "lower", "my app"),
+    ];
+
+    for (input, filter, expected) in cases {
+      assert_eq!(convert_case(input, filter), expected);
+    }
+  }
+}
@@ -2,18 +2,24 @@ use std::fmt::{self, Display};
 use std::str::FromStr;
 
 use miette::Diagnostic;
+use serde::Serialize;
 use thiserror::Error;
 
+use crate::config::{convert_case, FILTERS};
+
 #[derive(Debug, Diagnostic, Error)]
 #[error("`{0}` is not a valid number.")]
 #[diagnostic(code(decaff::config::prompts::parse))]
 pub struct NumberParseError(pub String);
 
 /// Value of a number prompt.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
+#[serde(untagged)]
 pub enum Number {
   /// Integer value.
   Integer(i64),
+  /// Integer value that doesn't fit into `i64`, but fits into `u64`.
+  Unsigned(u64),
   /// Floating point value.
   Float(f64),
 }
@@ -22,6 +28,8 @@ impl Display for Number {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     match self {
       | Self::Integer(int) => write!(f, "{int}"),
+      | Self::Unsigned(int) => write!(f, "{int}"),
+      // Unlike `{:e}`, `{}` never uses scientific notation, so `1e20` is written in full.
       | Self::Float(float) => write!(f, "{float}"),
     }
   }
@@ -30,16 +38,26 @@ impl Display for Number {
 impl FromStr for Number {
   type Err = NumberParseError;
 
+  /// Parses the number as `i64`, then as `u64`, and only then as `f64`, so integers up to
+  /// `u64::MAX` are preserved exactly. Larger integers are parsed as (lossy) floats.
   fn from_str(s: &str) -> Result<Self, Self::Err> {
     s.parse::<i64>()
       .map(Self::Integer)
+      .or_else(|_| s.parse::<u64>().map(Self::Unsigned))
       .or_else(|_| s.parse::<f64>().map(Self::Float))
       .map_err(|_| NumberParseError(s.to_string()))
   }
 }
 
+/// Default separator used to join list values.
+pub const DEFAULT_SEPARATOR: &str = ", ";
+
+/// Default opening and closing delimiters of placeholders.
+pub const DEFAULT_DELIMITERS: (&str, &str) = ("{", "}");
+
 /// Replacement value.
-#[derive(Debug)]
+#[derive(Clone, Debug, Serialize)]
+#[serde(untagged)]
 pub enum Value {
   /// A string value.
   String(String),
@@ -47,14 +65,304 @@ pub enum Value {
   Number(Number),
   /// A boolean value.
   Bool(bool),
+  /// A list of string values.
+  List(Vec<String>),
 }
 
 impl Display for Value {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.join(DEFAULT_SEPARATOR))
+  }
+}
+
+impl Value {
+  /// Formats the value, joining list values with the given separator.
+  pub fn join(&self, separator: &str) -> String {
+    match self {
+      | Self::String(string) => string.to_owned(),
+      | Self::Number(number) => number.to_string(),
+      | Self::Bool(boolean) => boolean.to_string(),
+      | Self::List(list) => list.join(separator),
+    }
+  }
+
+  /// Coerces the value into a boolean. Strings are parsed, so `"true"` and `"false"` are accepted.
+  pub fn as_bool(&self) -> Option<bool> {
+    match self {
+      | Self::Bool(boolean) => Some(*boolean),
+      | Self::String(string) => string.parse().ok(),
+      | _ => None,
+    }
+  }
+
+  /// Coerces the value into a number. Strings are parsed the same way as number prompt answers.
+  pub fn as_number(&self) -> Option<Number> {
+    match self {
+      | Self::Number(number) => Some(number.clone()),
+      | Self::String(string) => string.parse().ok(),
+      | _ => None,
+    }
+  }
+
+  /// Returns the value with line breaks in strings normalized to the given line ending.
+  pub fn with_line_ending(&self, ending: &str) -> Self {
+    let normalize = |string: &str| string.replace("\r\n", "\n").replace('\n', ending);
+
     match self {
-      | Self::String(string) => write!(f, "{string}"),
-      | Self::Number(number) => write!(f, "{number}"),
-      | Self::Bool(boolean) => write!(f, "{boolean}"),
+      | Self::String(string) => Self::String(normalize(string)),
+      | Self::List(list) => Self::List(list.iter().map(|item| normalize(item)).collect()),
+      | _ => self.clone(),
+    }
+  }
+
+  /// Formats the value as JSON, e.g. list values are formatted as JSON arrays.
+  pub fn to_json(&self) -> String {
+    let value = match self {
+      | Self::String(string) => serde_json::Value::from(string.as_str()),
+      | Self::Number(Number::Integer(int)) => serde_json::Value::from(*int),
+      | Self::Number(Number::Unsigned(int)) => serde_json::Value::from(*int),
+      | Self::Number(Number::Float(float)) => serde_json::Value::from(*float),
+      | Self::Bool(boolean) => serde_json::Value::from(*boolean),
+      | Self::List(list) => serde_json::Value::from(list.as_slice()),
+    };
+
+    value.to_string()
+  }
+
+  /// Replaces `{name}`, `{name:json}` and casing filter placeholders like `{name:kebab}` in the
+  /// input with this value. List values are joined with the given separator, except for `json`.
+  pub fn substitute(&self, input: &str, name: &str, separator: &str) -> String {
+    self.substitute_delimited(input, name, separator, DEFAULT_DELIMITERS)
+  }
+
+  /// Same as [Value::substitute], but placeholders are enclosed with the given opening and closing
+  /// delimiters instead of braces, e.g. `<<name>>` and `<<name:json>>`.
+  pub fn substitute_delimited(
+    &self,
+    input: &str,
+    name: &str,
+    separator: &str,
+    (open, close): (&str, &str),
+  ) -> String {
+    let joined = self.join(separator);
+
+    let output = FILTERS.iter().fold(input.to_string(), |output, &filter| {
+      let placeholder = format!("{open}{name}:{filter}{close}");
+
+      if !output.contains(&placeholder) {
+        return output;
+      }
+
+      match filter {
+        | "json" => output.replace(&placeholder, &self.to_json()),
+        | case => output.replace(&placeholder, &convert_case(&joined, case)),
+      }
+    });
+
+    output.replace(&format!("{open}{name}{close}"), &joined)
+  }
+
+  /// Checks if the value is not a list.
+  fn is_scalar(&self) -> bool {
+    !matches!(self, Self::List(_))
+  }
+
+  /// Tries to convert a TOML value into a [Value]. Only scalar values and arrays of them are
+  /// supported.
+  pub fn from_toml(value: &toml::Value) -> Option<Self> {
+    match value {
+      | toml::Value::String(string) => Some(Self::String(string.to_owned())),
+      | toml::Value::Integer(int) => Some(Self::Number(Number::Integer(*int))),
+      | toml::Value::Float(float) => Some(Self::Number(Number::Float(*float))),
+      | toml::Value::Boolean(boolean) => Some(Self::Bool(*boolean)),
+      | toml::Value::Array(array) => {
+        array
+          .iter()
+          .map(|item| Self::from_toml(item).filter(Self::is_scalar))
+          .map(|item| item.map(|item| item.to_string()))
+          .collect::<Option<_>>()
+          .map(Self::List)
+      },
+      | _ => None,
+    }
+  }
+
+  /// Tries to convert a JSON value into a [Value]. Only scalar values and arrays of them are
+  /// supported.
+  pub fn from_json(value: &serde_json::Value) -> Option<Self> {
+    match value {
+      | serde_json::Value::String(string) => Some(Self::String(string.to_owned())),
+      | serde_json::Value::Number(number) => {
+        number
+          .as_i64()
+          .map(Number::Integer)
+          .or_else(|| number.as_u64().map(Number::Unsigned))
+          .or_else(|| number.as_f64().map(Number::Float))
+          .map(Self::Number)
+      },
+      | serde_json::Value::Bool(boolean) => Some(Self::Bool(*boolean)),
+      | serde_json::Value::Array(array) => {
+        array
+          .iter()
+          .map(|item| Self::from_json(item).filter(Self::is_scalar))
+          .map(|item| item.map(|item| item.to_string()))
+          .collect::<Option<_>>()
+          .map(Self::List)
+      },
+      | _ => None,
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn list_display() {
+    let list = Value::List(vec!["foo".to_string(), "bar".to_string()]);
+
+    assert_eq!(list.to_string(), "foo, bar");
+    assert_eq!(list.join(" | "), "foo | bar");
+  }
+
+  #[test]
+  fn list_to_json() {
+    let list = Value::List(vec!["foo".to_string(), "bar".to_string()]);
+
+    assert_eq!(list.to_json(), r#"["foo","bar"]"#);
+    assert_eq!(Value::String("foo".to_string()).to_json(), r#""foo""#);
+    assert_eq!(Value::Number(Number::Integer(42)).to_json(), "42");
+  }
+
+  #[test]
+  fn substitute_placeholders() {
+    let list = Value::List(vec!["foo".to_string(), "bar".to_string()]);
+
+    assert_eq!(
+      list.substitute("[{items}] {items:json}", "items", ";"),
+      r#"[foo;bar] ["foo","bar"]"#
+    );
+  }
+
+  #[test]
+  fn substitute_with_delimiters() {
+    let value = Value::String("decaff".to_string());
+
+    assert_eq!(
+      value.substitute_delimited("<<NAME>> {NAME} <<NAME:json>>", "NAME", ", ", ("<<", ">>")),
+      r#"decaff {NAME} "decaff""#
+    );
+  }
+
+  #[test]
+  fn substitute_with_case_filters() {
+    let value = Value::String("My App".to_string());
+
+    assert_eq!(
+      value.substitute(
+        "{NAME:kebab} {NAME:pascal} {NAME:upper} {NAME}",
+        "NAME",
+        " "
+      ),
+      "my-app MyApp MY APP My App"
+    );
+  }
+
+  #[test]
+  fn coerce_to_bool() {
+    assert_eq!(Value::Bool(false).as_bool(), Some(false));
+    assert_eq!(Value::String("true".to_string()).as_bool(), Some(true));
+    assert_eq!(Value::String("yes".to_string()).as_bool(), None);
+    assert_eq!(Value::Number(Number::Integer(1)).as_bool(), None);
+    assert_eq!(Value::List(vec!["true".to_string()]).as_bool(), None);
+  }
+
+  #[test]
+  fn coerce_to_number() {
+    let number = |value: Value| value.as_number().map(|number| number.to_string());
+
+    assert_eq!(
+      number(Value::Number(Number::Integer(42))),
+      Some("42".to_string())
+    );
+    assert_eq!(
+      number(Value::String("4.2".to_string())),
+      Some("4.2".to_string())
+    );
+    assert_eq!(number(Value::String("four".to_string())), None);
+    assert_eq!(number(Value::Bool(true)), None);
+    assert_eq!(number(Value::List(vec!["1".to_string()])), None);
+  }
+
+  #[test]
+  fn coerce_to_string() {
+    assert_eq!(Value::Bool(true).to_string(), "true");
+    assert_eq!(
+      Value::Number(Number::Unsigned(u64::MAX)).to_string(),
+      u64::MAX.to_string()
+    );
+  }
+
+  #[test]
+  fn serialize_untagged() {
+    let cases = [
+      (Value::String("foo".to_string()), r#""foo""#),
+      (Value::Number(Number::Integer(42)), "42"),
+      (
+        Value::Number(Number::Unsigned(u64::MAX)),
+        "18446744073709551615",
+      ),
+      (Value::Number(Number::Float(4.2)), "4.2"),
+      (Value::Bool(true), "true"),
+      (Value::List(vec!["foo".to_string()]), r#"["foo"]"#),
+    ];
+
+    for (value, expected) in cases {
+      assert_eq!(serde_json::to_string(&value).unwrap(), expected);
+    }
+  }
+
+  #[test]
+  fn parse_integer_boundaries() {
+    let cases = [
+      ("9223372036854775807", "9223372036854775807"),
+      ("-9223372036854775808", "-9223372036854775808"),
+      ("9223372036854775808", "9223372036854775808"),
+      ("18446744073709551615", "18446744073709551615"),
+    ];
+
+    for (input, expected) in cases {
+      assert_eq!(input.parse::<Number>().unwrap().to_string(), expected);
+    }
+
+    assert!(matches!(
+      "9223372036854775808".parse::<Number>(),
+      Ok(Number::Unsigned(_))
+    ));
+    assert!(matches!(
+      "18446744073709551616".parse::<Number>(),
+      Ok(Number::Float(_))
+    ));
+  }
+
+  #[test]
+  fn float_display_without_exponent() {
+    assert_eq!(Number::Float(1e20).to_string(), "100000000000000000000");
+    assert_eq!(Number::Float(1e-7).to_string(), "0.0000001");
+  }
+
+  #[test]
+  fn unsigned_from_json() {
+    let json = serde_json::json!(u64::MAX);
+
+    assert!(matches!(
+      Value::from_json(&json),
+      Some(Value::Number(Number::Unsigned(u64::MAX)))
+    ));
+    assert_eq!(
+      Value::Number(Number::Unsigned(u64::MAX)).to_json(),
+      "18446744073709551615"
+    );
+  }
+}
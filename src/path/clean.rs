@@ -23,6 +23,9 @@ impl PathClean for Path {
 /// - Eliminates `..` elements that begin a rooted path, that is, replace `/..` by `/` at the
 ///   beginning of a path.
 /// - Leaves intact `..` elements that begin a non-rooted path.
+/// - Leaves intact Windows prefixes (drive, UNC and verbatim ones). A prefix followed by a root
+///   behaves like a root, while `..` elements that follow a prefix without a root (drive-relative
+///   paths like `C:..`) are left intact.
 ///
 /// If the result is an empty string, returns the string `"."`, representing the current directory.
 pub fn clean<P>(path: P) -> PathBuf
@@ -36,10 +39,14 @@ where
       | Component::CurDir => (),
       | Component::ParentDir => {
         match out.last() {
+          // Can't go above the root, which may also follow a prefix, e.g. `C:\`, `\\?\C:\` or
+          // `\\server\share\`.
           | Some(Component::RootDir) => (),
           | Some(Component::Normal(_)) => {
             out.pop();
           },
+          // Drive-relative paths like `C:..` are relative to the current directory on the drive,
+          // so `..` has to stay.
           | None
           | Some(Component::CurDir)
           | Some(Component::ParentDir)
@@ -165,4 +172,48 @@ mod tests {
 
     test_cases(cases);
   }
+
+  #[test]
+  #[cfg(windows)]
+  fn test_windows_verbatim_paths() {
+    let cases = vec![
+      ("\\\\?\\C:\\a\\..\\b", "\\\\?\\C:\\b"),
+      ("\\\\?\\C:\\..", "\\\\?\\C:\\"),
+      ("\\\\?\\C:\\a\\..\\..\\b", "\\\\?\\C:\\b"),
+      (
+        "\\\\?\\UNC\\server\\share\\a\\..\\b",
+        "\\\\?\\UNC\\server\\share\\b",
+      ),
+    ];
+
+    test_cases(cases);
+  }
+
+  #[test]
+  #[cfg(windows)]
+  fn test_windows_unc_paths() {
+    let cases = vec![
+      ("\\\\server\\share\\a\\..", "\\\\server\\share\\"),
+      ("\\\\server\\share\\..\\..", "\\\\server\\share\\"),
+      (
+        "\\\\server\\share\\a\\.\\b\\..\\c",
+        "\\\\server\\share\\a\\c",
+      ),
+    ];
+
+    test_cases(cases);
+  }
+
+  #[test]
+  #[cfg(windows)]
+  fn test_windows_drive_relative_paths() {
+    let cases = vec![
+      ("C:a\\..\\b", "C:b"),
+      ("C:a\\..", "C:"),
+      ("C:..\\a", "C:..\\a"),
+      ("C:\\a\\..\\..\\b", "C:\\b"),
+    ];
+
+    test_cases(cases);
+  }
 }
@@ -0,0 +1,155 @@
+use std::env;
+use std::path::{is_separator, PathBuf};
+
+/// Expands the input, lexically:
+///
+/// - Leading `~` followed by a separator or nothing is replaced with the home directory.
+/// - `$VAR` and `${VAR}` are replaced with values of environment variables. Unset variables are
+///   replaced with an empty string.
+/// - `${VAR:-default}` is replaced with `default` if the variable is unset or empty, and
+///   `${VAR:+alt}` is replaced with `alt` if the variable is set and not empty, same as in POSIX
+///   shells.
+///
+/// Everything else is left intact, including glob syntax like `**/*` or `{a,b}`.
+pub fn expand(input: &str) -> String {
+  expand_with(input, home::home_dir(), context)
+}
+
+/// Resolves the contents of a `$VAR` or `${VAR}` reference using environment variables.
+fn context(reference: &str) -> String {
+  resolve(reference, |name| env::var(name).ok())
+}
+
+/// Resolves the contents of a reference, handling `VAR:-default` and `VAR:+alt` forms. Variables
+/// are looked up with the given function.
+fn resolve<F>(reference: &str, lookup: F) -> String
+where
+  F: Fn(&str) -> Option<String>,
+{
+  let value = |name: &str| lookup(name).filter(|value| !value.is_empty());
+
+  if let Some((name, default)) = reference.split_once(":-") {
+    return value(name).unwrap_or_else(|| default.to_string());
+  }
+
+  if let Some((name, alt)) = reference.split_once(":+") {
+    return value(name).map(|_| alt.to_string()).unwrap_or_default();
+  }
+
+  lookup(reference).unwrap_or_default()
+}
+
+/// Expands the input using the given home directory and context to resolve variables with.
+fn expand_with<F>(input: &str, home: Option<PathBuf>, context: F) -> String
+where
+  F: Fn(&str) -> String,
+{
+  let mut output = String::with_capacity(input.len());
+  let mut rest = input;
+
+  if let (Some(tail), Some(home)) = (rest.strip_prefix('~'), home) {
+    if tail.is_empty() || tail.starts_with(is_separator) {
+      output.push_str(&home.display().to_string());
+      rest = tail;
+    }
+  }
+
+  while let Some(start) = rest.find('$') {
+    output.push_str(&rest[..start]);
+    rest = &rest[start + 1..];
+
+    // Braced reference: `${VAR}`.
+    if let Some(braced) = rest.strip_prefix('{') {
+      if let Some(end) = braced.find('}') {
+        output.push_str(&context(&braced[..end]));
+        rest = &braced[end + 1..];
+        continue;
+      }
+    }
+
+    // Bare reference: `$VAR`.
+    let end = rest
+      .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_'))
+      .unwrap_or(rest.len());
+
+    if end > 0 {
+      output.push_str(&context(&rest[..end]));
+      rest = &rest[end..];
+    } else {
+      output.push('$');
+    }
+  }
+
+  output.push_str(rest);
+  output
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn lookup(name: &str) -> Option<String> {
+    match name {
+      | "OUT" => Some("out".to_string()),
+      | "EMPTY" => Some(String::new()),
+      | _ => None,
+    }
+  }
+
+  #[test]
+  fn resolve_set() {
+    assert_eq!(resolve("OUT", lookup), "out");
+    assert_eq!(resolve("OUT:-dist", lookup), "out");
+    assert_eq!(resolve("OUT:+alt", lookup), "alt");
+  }
+
+  #[test]
+  fn resolve_unset_with_default() {
+    assert_eq!(resolve("UNSET:-dist", lookup), "dist");
+    assert_eq!(resolve("EMPTY:-dist", lookup), "dist");
+    assert_eq!(resolve("UNSET:-", lookup), "");
+  }
+
+  #[test]
+  fn resolve_unset_without_default() {
+    assert_eq!(resolve("UNSET", lookup), "");
+    assert_eq!(resolve("UNSET:+alt", lookup), "");
+    assert_eq!(resolve("EMPTY:+alt", lookup), "");
+  }
+
+  #[test]
+  fn expand_home() {
+    let home = Some(PathBuf::from("/home/decaff"));
+
+    let cases = [
+      ("~", "/home/decaff"),
+      ("~/dist", "/home/decaff/dist"),
+      ("~dist", "~dist"),
+      ("dist/~", "dist/~"),
+    ];
+
+    for (input, expected) in cases {
+      assert_eq!(expand_with(input, home.clone(), context), expected);
+    }
+  }
+
+  #[test]
+  fn expand_env_vars() {
+    env::set_var("DECAFF_TEST_EXPAND_OUT", "out");
+    env::remove_var("DECAFF_TEST_EXPAND_UNSET");
+
+    let cases = [
+      ("$DECAFF_TEST_EXPAND_OUT/dist", "out/dist"),
+      ("${DECAFF_TEST_EXPAND_OUT}-dist", "out-dist"),
+      ("$DECAFF_TEST_EXPAND_UNSET/dist", "/dist"),
+      ("${DECAFF_TEST_EXPAND_UNSET:-out}/dist", "out/dist"),
+      ("src/**/*.{rs,toml}", "src/**/*.{rs,toml}"),
+      ("price$", "price$"),
+      ("${unterminated", "${unterminated"),
+    ];
+
+    for (input, expected) in cases {
+      assert_eq!(expand(input), expected);
+    }
+  }
+}
@@ -1,5 +1,7 @@
 pub use clean::*;
+pub use expand::*;
 pub use traverser::*;
 
 mod clean;
+mod expand;
 mod traverser;
@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use glob_match::glob_match_with_captures;
+use glob_match::{glob_match, glob_match_with_captures};
 use thiserror::Error;
 use walkdir::{DirEntry, IntoIter as WalkDirIter, WalkDir};
 
@@ -42,6 +42,10 @@ pub struct TraverseOptions {
   ignore_dirs: bool,
   /// Whether to traverse contents of directories first (depth-first). Defaults to `false`.
   contents_first: bool,
+  /// Maximum depth to descend relative to the root. If `None`, there is no limit.
+  max_depth: Option<usize>,
+  /// Patterns to exclude paths matching them. Checked before the `pattern`.
+  exclude: Vec<String>,
 }
 
 #[derive(Debug)]
@@ -59,6 +63,8 @@ impl Traverser {
         pattern: None,
         ignore_dirs: false,
         contents_first: false,
+        max_depth: None,
+        exclude: Vec::new(),
       },
     }
   }
@@ -81,11 +87,27 @@ impl Traverser {
     self
   }
 
+  /// Set the maximum depth to descend relative to the root. If `None`, there is no limit.
+  pub fn max_depth(mut self, max_depth: Option<usize>) -> Self {
+    self.options.max_depth = max_depth;
+    self
+  }
+
+  /// Set the patterns to exclude paths matching them.
+  pub fn exclude(mut self, exclude: Vec<String>) -> Self {
+    self.options.exclude = exclude;
+    self
+  }
+
   /// Creates an iterator without consuming the traverser builder.
-  pub fn iter(&self) -> TraverserIterator {
-    let it = WalkDir::new(&self.options.root)
-      .contents_first(self.options.contents_first)
-      .into_iter();
+  pub fn iter(&self) -> TraverserIterator<'_> {
+    let mut walker = WalkDir::new(&self.options.root).contents_first(self.options.contents_first);
+
+    if let Some(max_depth) = self.options.max_depth {
+      walker = walker.max_depth(max_depth);
+    }
+
+    let it = walker.into_iter();
 
     let root_pattern = self
       .options
@@ -93,8 +115,31 @@ impl Traverser {
       .as_ref()
       .map(|pat| self.options.root.join(pat).display().to_string());
 
-    TraverserIterator { it, root_pattern, options: &self.options }
+    let root_excludes = self
+      .options
+      .exclude
+      .iter()
+      .map(|pat| self.options.root.join(pat).display().to_string())
+      .collect();
+
+    TraverserIterator {
+      it,
+      root_pattern,
+      root_excludes,
+      options: &self.options,
+    }
+  }
+}
+
+/// Returns the depth of the deepest path the glob pattern can match, relative to the root, or
+/// `None` if it isn't limited, i.e. the pattern contains `**` or braces. Useful as the maximum
+/// depth of a [Traverser], so it doesn't descend into directories the pattern can't match.
+pub fn pattern_depth(pattern: &str) -> Option<usize> {
+  if pattern.contains("**") || pattern.contains('{') {
+    return None;
   }
+
+  Some(pattern.split('/').count())
 }
 
 /// Traverser iterator.
@@ -103,6 +148,8 @@ pub struct TraverserIterator<'t> {
   it: WalkDirIter,
   /// Pattern prepended with the root path to avoid conversions on every iteration.
   root_pattern: Option<String>,
+  /// Exclude patterns prepended with the root path, same as `root_pattern`.
+  root_excludes: Vec<String>,
   /// Traverser options.
   options: &'t TraverseOptions,
 }
@@ -125,9 +172,20 @@ impl<'t> Iterator for TraverserIterator<'t> {
             continue 'skip;
           }
 
-          if let Some(pattern) = &self.root_pattern {
-            let candidate = path.display().to_string();
+          let candidate = path.display().to_string();
+
+          // Excluded entries are skipped before any pattern matching takes place.
+          if self
+            .root_excludes
+            .iter()
+            .any(|exclude| glob_match(exclude, &candidate))
+          {
+            item = self.it.next()?;
+
+            continue 'skip;
+          }
 
+          if let Some(pattern) = &self.root_pattern {
             if let Some(captures) = glob_match_with_captures(pattern, &candidate) {
               let range = captures.first().cloned().unwrap_or_default();
               let captured = PathBuf::from(&candidate[range.start..]);
@@ -155,3 +213,77 @@ impl<'t> Iterator for TraverserIterator<'t> {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use std::fs;
+  use std::path::Path;
+
+  use super::*;
+  use crate::utils::temp::TempDir;
+
+  fn temp_root(name: &str) -> TempDir {
+    let root = TempDir::new(name);
+
+    for path in ["a.txt", "src/b.txt", "src/nested/c.txt", ".git/config"] {
+      let path = root.join(path);
+      fs::create_dir_all(path.parent().unwrap()).unwrap();
+      fs::write(path, "").unwrap();
+    }
+
+    root
+  }
+
+  fn captured(traverser: &Traverser) -> Vec<PathBuf> {
+    let mut paths = traverser
+      .iter()
+      .flatten()
+      .map(|matched| matched.captured)
+      .collect::<Vec<_>>();
+
+    paths.sort();
+    paths
+  }
+
+  #[test]
+  fn traverse_with_pattern_and_excludes() {
+    let root = temp_root("traverse-pattern");
+
+    let traverser = Traverser::new(root.path())
+      .ignore_dirs(true)
+      .pattern("**/*")
+      .exclude(vec![".git/**".to_string(), "src/nested/**".to_string()]);
+
+    assert_eq!(
+      captured(&traverser),
+      [Path::new("a.txt"), Path::new("src/b.txt")]
+    );
+  }
+
+  #[test]
+  fn traverse_with_max_depth() {
+    let root = temp_root("traverse-depth");
+
+    let all = Traverser::new(root.path())
+      .ignore_dirs(true)
+      .pattern("*/*.txt");
+    let limited = Traverser::new(root.path())
+      .ignore_dirs(true)
+      .pattern("**/*.txt")
+      .max_depth(Some(2));
+
+    assert_eq!(captured(&all), [Path::new("src/b.txt")]);
+    assert_eq!(
+      captured(&limited),
+      [Path::new("a.txt"), Path::new("src/b.txt")]
+    );
+  }
+
+  #[test]
+  fn depth_of_patterns() {
+    assert_eq!(pattern_depth("*.txt"), Some(1));
+    assert_eq!(pattern_depth("src/*/mod.rs"), Some(3));
+    assert_eq!(pattern_depth("src/**/*.rs"), None);
+    assert_eq!(pattern_depth("{a,b/c}.txt"), None);
+  }
+}
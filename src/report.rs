@@ -1,6 +1,7 @@
-use crossterm::style::Stylize;
 use miette::Severity;
 
+use crate::utils::style::Stylize;
+
 /// Prints an error message and exits the program if given an error.
 pub fn try_report<T>(fallible: miette::Result<T>) {
   if let Err(err) = fallible {
@@ -18,3 +19,16 @@ pub fn try_report<T>(fallible: miette::Result<T>) {
     }
   }
 }
+
+/// Prints a warning with the given message.
+pub fn warn<S>(code: &str, message: S)
+where
+  S: Into<String> + AsRef<str>,
+{
+  try_report::<()>(Err(miette::miette!(
+    severity = Severity::Warning,
+    code = code,
+    "{}",
+    message.into()
+  )));
+}
@@ -4,13 +4,21 @@ use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::time::Duration;
 
 use git2::build::CheckoutBuilder;
-use git2::Repository as GitRepository;
+use git2::{
+  IndexAddOption, ObjectType, Oid, Repository as GitRepository, ResetType, TreeWalkMode,
+  TreeWalkResult,
+};
+use glob_match::glob_match;
 use miette::{Diagnostic, LabeledSpan, Report};
+use semver::Version;
+use serde::Deserialize;
 use thiserror::Error;
+use tokio::{task, time};
 
-use crate::path::Traverser;
+use crate::path::{expand, Traverser};
 
 /// Helper macro to create a [ParseError] in a slightly less verbose way.
 macro_rules! parse_error {
@@ -32,6 +40,9 @@ pub enum RepositoryError {
     #[source]
     source: io::Error,
   },
+  #[error("Failed to preserve the git history of the source repository.")]
+  #[diagnostic(code(decaff::repository::history))]
+  HistoryFailed(#[source] git2::Error),
 }
 
 #[derive(Debug, Diagnostic, Error)]
@@ -48,6 +59,9 @@ pub enum FetchError {
   RequestFailedWithCode { code: u16, report: Report },
   #[error("Couldn't get the response body as bytes.")]
   RequestBodyFailed,
+  #[error("Request timed out after {}s.\n\nURL: {url}", .timeout.as_secs())]
+  #[diagnostic(help("Use `--timeout` to wait longer, e.g. on a slow connection."))]
+  Timeout { timeout: Duration, url: String },
 }
 
 #[derive(Debug, Diagnostic, Error)]
@@ -64,6 +78,9 @@ pub enum RemoteError {
 pub enum ReferenceError {
   #[error("Invalid reference: `{0}`.")]
   InvalidSelector(String),
+  #[error("No tags match `{0}`.")]
+  #[diagnostic(help("Make sure the repository has tags matching the pattern."))]
+  NoMatchingTag(String),
 }
 
 #[derive(Debug, Diagnostic, Error)]
@@ -72,19 +89,28 @@ pub enum CheckoutError {
   #[error("Failed to open the git repository.")]
   OpenFailed(git2::Error),
   #[error("Failed to parse revision string `{0}`.")]
+  #[diagnostic(help("Make sure the branch, tag or commit exists in the source repository."))]
   RevparseFailed(String),
   #[error("Failed to checkout revision (tree).")]
   TreeCheckoutFailed,
-  #[error("Reference name is not a valid UTF-8 string.")]
-  InvalidRefName,
-  #[error("Failed to set HEAD to `{0}`.")]
-  SetHeadFailed(String),
-  #[error("Failed to detach HEAD to `{0}`.")]
-  DetachHeadFailed(String),
+}
+
+#[derive(Debug, Diagnostic, Error)]
+#[diagnostic(code(decaff::repository::init))]
+pub enum InitError {
+  #[error("Failed to initialize a git repository.")]
+  InitFailed(git2::Error),
+  #[error("Failed to stage files for the initial commit.")]
+  StageFailed(git2::Error),
+  #[error("Failed to get the commit author from git config.")]
+  #[diagnostic(help("Set `user.name` and `user.email` in your git config."))]
+  SignatureMissing(git2::Error),
+  #[error("Failed to make the initial commit.")]
+  CommitFailed(git2::Error),
 }
 
 /// Supported hosts. [GitHub][RepositoryHost::GitHub] is the default one.
-#[derive(Debug, Default, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub enum RepositoryHost {
   #[default]
   GitHub,
@@ -92,15 +118,36 @@ pub enum RepositoryHost {
   BitBucket,
 }
 
+impl RepositoryHost {
+  /// All supported hosts.
+  pub const ALL: [RepositoryHost; 3] = [
+    RepositoryHost::GitHub,
+    RepositoryHost::GitLab,
+    RepositoryHost::BitBucket,
+  ];
+
+  /// Returns aliases of the host, the first one being its canonical name.
+  pub fn aliases(&self) -> &'static [&'static str] {
+    match self {
+      | RepositoryHost::GitHub => &["github", "gh"],
+      | RepositoryHost::GitLab => &["gitlab", "gl"],
+      | RepositoryHost::BitBucket => &["bitbucket", "bb"],
+    }
+  }
+
+  /// Returns the host matching the given alias, if any. Aliases are case-insensitive.
+  pub fn from_alias(alias: &str) -> Option<Self> {
+    let alias = alias.to_ascii_lowercase();
+
+    Self::ALL
+      .into_iter()
+      .find(|host| host.aliases().contains(&alias.as_str()))
+  }
+}
+
 impl Display for RepositoryHost {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    let host = match self {
-      | RepositoryHost::GitHub => "github",
-      | RepositoryHost::GitLab => "gitlab",
-      | RepositoryHost::BitBucket => "bitbucket",
-    };
-
-    write!(f, "{host}")
+    write!(f, "{}", self.aliases()[0])
   }
 }
 
@@ -124,6 +171,18 @@ impl Display for RepositoryMeta {
   }
 }
 
+/// Special ref resolved to the tag of the latest GitHub release, e.g. `gh:user/repo@latest`.
+pub const LATEST_RELEASE: &str = "latest";
+
+/// Outcome of resolving the [LATEST_RELEASE] ref.
+#[derive(Debug, PartialEq)]
+pub enum Release {
+  /// Tag of the latest release, which replaced the ref.
+  Tag(String),
+  /// The repository has no releases, so the ref was replaced with the default branch.
+  Missing,
+}
+
 /// Represents a remote repository. Repositories of this kind need to be downloaded first.
 #[derive(Debug, PartialEq)]
 pub struct RemoteRepository {
@@ -132,6 +191,8 @@ pub struct RemoteRepository {
   pub repo: String,
   pub meta: RepositoryMeta,
   pub refs: HashMap<String, String>,
+  /// Names of tags, fetched along with the refs.
+  pub tags: Vec<String>,
 }
 
 impl RemoteRepository {
@@ -160,6 +221,15 @@ impl RemoteRepository {
     }
   }
 
+  /// Returns the domain name of the host.
+  pub fn get_domain(&self) -> &str {
+    match self.host {
+      | RepositoryHost::GitHub => "github.com",
+      | RepositoryHost::GitLab => "gitlab.com",
+      | RepositoryHost::BitBucket => "bitbucket.org",
+    }
+  }
+
   /// Resolves a git repository URL depending on the host and other repository fields.
   pub fn get_git_url(&self) -> String {
     let RemoteRepository { host, user, repo, .. } = self;
@@ -173,33 +243,76 @@ impl RemoteRepository {
 
   /// Returns the source string of the repository.
   pub fn get_source(&self) -> String {
-    let host = match self.host {
-      | RepositoryHost::GitHub => "github",
-      | RepositoryHost::GitLab => "gitlab",
-      | RepositoryHost::BitBucket => "bitbucket",
-    };
-
+    let host = &self.host;
     let user = &self.user;
     let repo = &self.repo;
 
     format!("{host}:{user}/{repo}")
   }
 
-  /// Fetches the refs of the remote repository.
-  pub fn fetch_refs(&mut self) -> Result<(), RemoteError> {
+  /// Fetches the refs of the remote repository. Refs are listed over the git protocol, falling back
+  /// to the API of the host if that fails.
+  pub async fn fetch_refs(&mut self, client: &Client) -> Result<(), RemoteError> {
     let git_url = self.get_git_url();
 
-    let mut remote = git2::Remote::create_detached(git_url.as_bytes()).map_err(|_| {
-      RemoteError::CreateDetachedRemoteFailed { url: miette::miette!("URL: {git_url}") }
-    })?;
+    let listed = task::spawn_blocking(move || list_refs(&git_url))
+      .await
+      .unwrap_or_else(|_| {
+        Err(RemoteError::ConnectionFailed {
+          url: miette::miette!("URL: {}", self.get_git_url()),
+        })
+      });
+
+    let refs = match listed {
+      | Ok(refs) => refs,
+      | Err(err) => self.fetch_api_refs(client).await.map_err(|_| err)?,
+    };
+
+    self.store_refs(refs);
+
+    Ok(())
+  }
+
+  /// Fetches the refs of the remote repository using the API of the host. Only the first page of
+  /// up to 100 branches and tags is fetched.
+  async fn fetch_api_refs(&self, client: &Client) -> Result<Refs, FetchError> {
+    let RemoteRepository { user, repo, .. } = self;
+
+    let refs = match self.host {
+      | RepositoryHost::GitHub => {
+        let base = format!("https://api.github.com/repos/{user}/{repo}");
+        let info = client.text(&base).await?;
+        let refs = client
+          .text(&format!("{base}/git/refs?per_page=100"))
+          .await?;
+
+        parse_github_refs(&refs, &info)
+      },
+      | RepositoryHost::GitLab => {
+        let base = format!("https://gitlab.com/api/v4/projects/{user}%2F{repo}/repository");
+        let branches = client
+          .text(&format!("{base}/branches?per_page=100"))
+          .await?;
+        let tags = client.text(&format!("{base}/tags?per_page=100")).await?;
 
-    let connection = remote
-      .connect_auth(git2::Direction::Fetch, None, None)
-      .map_err(|_| RemoteError::ConnectionFailed { url: miette::miette!("URL: {git_url}") })?;
+        parse_gitlab_refs(&branches, &tags)
+      },
+      | RepositoryHost::BitBucket => {
+        let base = format!("https://api.bitbucket.org/2.0/repositories/{user}/{repo}");
+        let info = client.text(&base).await?;
+        let refs = client.text(&format!("{base}/refs?pagelen=100")).await?;
 
-    for head in connection.list().unwrap() {
-      let original = head.name();
+        parse_bitbucket_refs(&refs, &info)
+      },
+    };
+
+    refs.map_err(|_| FetchError::RequestBodyFailed)
+  }
 
+  /// Stores refs, stripping the `refs/heads/` and `refs/tags/` prefixes from their names. Names of
+  /// tags are collected separately too.
+  fn store_refs(&mut self, refs: Refs) {
+    for (original, hash) in refs {
       let name = (original == "HEAD")
         .then_some("HEAD")
         .or_else(|| original.strip_prefix("refs/heads/"))
@@ -207,11 +320,71 @@ impl RemoteRepository {
         .map(str::to_string);
 
       if let Some(name) = name {
-        self.refs.insert(name, head.oid().to_string());
+        if original.starts_with("refs/tags/") && !name.ends_with("^{}") {
+          self.tags.push(name.clone());
+        }
+
+        self.refs.insert(name, hash);
       }
     }
+  }
 
-    Ok(())
+  /// Resolves the [LATEST_RELEASE] ref to the tag of the latest release using the GitHub API. If
+  /// there are no releases, falls back to the default branch. Other hosts have different release
+  /// semantics, so the ref is left as-is there, as it is if a branch or tag with that name exists.
+  pub async fn resolve_release(&mut self, client: &Client) -> Result<Option<Release>, FetchError> {
+    if self.meta.0 != LATEST_RELEASE
+      || self.host != RepositoryHost::GitHub
+      || self.refs.contains_key(LATEST_RELEASE)
+    {
+      return Ok(None);
+    }
+
+    let RemoteRepository { user, repo, .. } = self;
+    let url = format!("https://api.github.com/repos/{user}/{repo}/releases/latest");
+
+    match client.text(&url).await {
+      | Ok(release) => {
+        let tag = parse_github_release(&release).map_err(|_| FetchError::RequestBodyFailed)?;
+        self.meta = RepositoryMeta(tag.clone());
+
+        Ok(Some(Release::Tag(tag)))
+      },
+      | Err(FetchError::RequestFailedWithCode { code: 404, .. }) => {
+        self.meta = RepositoryMeta::default();
+
+        Ok(Some(Release::Missing))
+      },
+      | Err(err) => Err(err),
+    }
+  }
+
+  /// Resolves a glob ref, e.g. `v*`, to the latest matching tag, which replaces the ref. Tags are
+  /// compared as semver versions (with an optional `v` prefix), and non-semver tags are ordered
+  /// lexically below them. Prereleases are skipped, unless the pattern names one with a `-`. Refs
+  /// without glob characters are left as-is. Returns the matched tag.
+  pub fn resolve_tag(&mut self) -> Result<Option<String>, ReferenceError> {
+    let pattern = &self.meta.0;
+
+    if !pattern.contains(['*', '?', '[', '{']) {
+      return Ok(None);
+    }
+
+    let version = |tag: &str| Version::parse(tag.strip_prefix('v').unwrap_or(tag)).ok();
+    let prereleases = pattern.contains('-');
+
+    let tag = self
+      .tags
+      .iter()
+      .filter(|tag| glob_match(pattern, tag))
+      .filter(|tag| prereleases || version(tag).map_or(true, |version| version.pre.is_empty()))
+      .max_by_key(|tag| (version(tag), tag.as_str()))
+      .cloned()
+      .ok_or_else(|| ReferenceError::NoMatchingTag(pattern.to_string()))?;
+
+    self.meta = RepositoryMeta(tag.clone());
+
+    Ok(Some(tag))
   }
 
   /// Resolves a given reference to a commit hash.
@@ -246,37 +419,22 @@ impl RemoteRepository {
   }
 
   /// Fetches the tarball using the resolved URL, and reads it into a vector of bytes.
-  pub async fn fetch(&self) -> Result<Vec<u8>, FetchError> {
+  pub async fn fetch(&self, client: &Client) -> Result<Vec<u8>, FetchError> {
     let url = self.get_tar_url();
 
-    let response = reqwest::get(&url).await.map_err(|err| {
-      err.status().map_or(FetchError::RequestFailed, |status| {
-        FetchError::RequestFailedWithCode {
-          code: status.as_u16(),
-          report: miette::miette!("\n\nURL: {}", url.clone()),
-        }
-      })
-    })?;
-
-    let status = response.status();
-
-    if !status.is_success() {
-      let code = status.as_u16();
-
-      let report = if code == 404 {
-        miette::miette!("The requested branch, tag or commit was not found.\n\nURL: {url}")
-      } else {
-        miette::miette!("\n\nURL: {url}")
-      };
-
-      return Err(FetchError::RequestFailedWithCode { code, report });
-    }
-
-    response
-      .bytes()
-      .await
-      .map(|bytes| bytes.to_vec())
-      .map_err(|_| FetchError::RequestBodyFailed)
+    client.bytes(&url).await.map_err(|err| {
+      match err {
+        | FetchError::RequestFailedWithCode { code: 404, .. } => {
+          FetchError::RequestFailedWithCode {
+            code: 404,
+            report: miette::miette!(
+              "The requested branch, tag or commit was not found.\n\nURL: {url}"
+            ),
+          }
+        },
+        | err => err,
+      }
+    })
   }
 }
 
@@ -299,22 +457,27 @@ impl FromStr for RemoteRepository {
 
     // Parse host if present or use default otherwise.
     let (host, (input, offset)) = if let Some((host, rest)) = source.split_once(':') {
-      let host = host.to_ascii_lowercase();
       let next_offset = host.len() + 1;
 
-      match host.as_str() {
-        | "github" | "gh" => (RepositoryHost::GitHub, (rest, next_offset)),
-        | "gitlab" | "gl" => (RepositoryHost::GitLab, (rest, next_offset)),
-        | "bitbucket" | "bb" => (RepositoryHost::BitBucket, (rest, next_offset)),
-        | _ => {
+      match RepositoryHost::from_alias(host) {
+        | Some(host) => (host, (rest, next_offset)),
+        | None => {
+          let hosts = RepositoryHost::ALL
+            .iter()
+            .map(|host| host.aliases().join("/"))
+            .collect::<Vec<_>>()
+            .join(", ");
+
           return Err(parse_error!(
             source = source.to_string(),
             code = "decaff::repository::parse",
             labels = vec![LabeledSpan::at(
               (0, host.len()),
-              "must be one of: github/gh, gitlab/gl, or bitbucket/bb"
+              format!("must be one of: {hosts}")
             )],
-            "Invalid host: `{host}`."
+            help = "Run `decaff remote --list-hosts` to list supported hosts.",
+            "Invalid host: `{}`.",
+            host.to_ascii_lowercase()
           ));
         },
       }
@@ -343,11 +506,14 @@ impl FromStr for RemoteRepository {
       return Err(ParseError(miette::miette!("Missing repository name.")));
     };
 
+    // Meta can be separated either with # or with @ (as in npm or degit), whichever comes first.
+    let meta_idx = input.find(['#', '@']);
+
     // Short-circuit if the rest of the input contains another /.
     if let Some(slash_idx) = input.find('/') {
-      // Ensure we are not triggering false-positive in case there's a ref (after #) with a branch
-      // name containing slashes.
-      if matches!(input.find('#'), Some(hash_idx) if slash_idx < hash_idx) {
+      // Ensure we are not triggering false-positive in case there's a ref (after # or @) with a
+      // branch name containing slashes.
+      if matches!(meta_idx, Some(meta_idx) if slash_idx < meta_idx) {
         return Err(parse_error!(
           source = source.to_string(),
           code = "decaff::repository::parse",
@@ -358,9 +524,9 @@ impl FromStr for RemoteRepository {
     }
 
     // Parse repository name.
-    let (repo, input) = input.split_once('#').map_or_else(
+    let (repo, input) = meta_idx.map_or_else(
       || (input.to_string(), None),
-      |(repo, rest)| (repo.to_string(), Some(rest)),
+      |meta_idx| (input[..meta_idx].to_string(), Some(&input[meta_idx + 1..])),
     );
 
     if !repo.chars().all(is_valid_repo) {
@@ -384,140 +550,674 @@ impl FromStr for RemoteRepository {
       });
 
     let refs = HashMap::default();
+    let tags = Vec::new();
+
+    Ok(RemoteRepository { host, user, repo, meta, refs, tags })
+  }
+}
+
+/// Refs of a remote repository: full ref names, e.g. `HEAD`, `refs/heads/main` or `refs/tags/v1`,
+/// along with the hashes they point to. Host APIs are normalized into this form.
+pub type Refs = Vec<(String, String)>;
+
+#[derive(Deserialize)]
+struct GitHubRepository {
+  default_branch: String,
+}
+
+#[derive(Deserialize)]
+struct GitHubRelease {
+  tag_name: String,
+}
+
+#[derive(Deserialize)]
+struct GitHubRef {
+  #[serde(rename = "ref")]
+  name: String,
+  object: GitHubObject,
+}
+
+#[derive(Deserialize)]
+struct GitHubObject {
+  sha: String,
+}
+
+#[derive(Deserialize)]
+struct GitLabRef {
+  name: String,
+  commit: GitLabCommit,
+  #[serde(default)]
+  default: bool,
+}
+
+#[derive(Deserialize)]
+struct GitLabCommit {
+  id: String,
+}
+
+#[derive(Deserialize)]
+struct BitBucketRepository {
+  mainbranch: BitBucketBranch,
+}
+
+#[derive(Deserialize)]
+struct BitBucketBranch {
+  name: String,
+}
+
+#[derive(Deserialize)]
+struct BitBucketRefs {
+  values: Vec<BitBucketRef>,
+}
+
+#[derive(Deserialize)]
+struct BitBucketRef {
+  name: String,
+  #[serde(rename = "type")]
+  kind: String,
+  target: BitBucketTarget,
+}
+
+#[derive(Deserialize)]
+struct BitBucketTarget {
+  hash: String,
+}
+
+/// Lists refs of the remote repository over the git protocol. This is blocking.
+fn list_refs(git_url: &str) -> Result<Refs, RemoteError> {
+  let mut remote = git2::Remote::create_detached(git_url.as_bytes()).map_err(|_| {
+    RemoteError::CreateDetachedRemoteFailed { url: miette::miette!("URL: {git_url}") }
+  })?;
+
+  let connection = remote
+    .connect_auth(git2::Direction::Fetch, None, None)
+    .map_err(|_| RemoteError::ConnectionFailed { url: miette::miette!("URL: {git_url}") })?;
+
+  let refs = connection
+    .list()
+    .map_err(|_| RemoteError::ConnectionFailed { url: miette::miette!("URL: {git_url}") })?
+    .iter()
+    .map(|head| (head.name().to_string(), head.oid().to_string()))
+    .collect();
+
+  Ok(refs)
+}
 
-    Ok(RemoteRepository { host, user, repo, meta, refs })
+/// Default timeout of HTTP requests.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// HTTP client shared by all requests to hosts. Sends a descriptive user agent, since some hosts
+/// block or rate-limit requests without one, and fails requests that can't connect, or stall
+/// waiting for the response or the next chunk of its body for longer than the timeout. Cheap to
+/// clone, clones share the connection pool.
+#[derive(Clone, Debug)]
+pub struct Client {
+  inner: reqwest::Client,
+  timeout: Duration,
+}
+
+impl Client {
+  /// Creates a new client with the given timeout.
+  pub fn new(timeout: Duration) -> Result<Self, FetchError> {
+    let inner = reqwest::Client::builder()
+      .user_agent(concat!("decaff/", env!("CARGO_PKG_VERSION")))
+      .connect_timeout(timeout)
+      .build()
+      .map_err(|_| FetchError::RequestFailed)?;
+
+    Ok(Self { inner, timeout })
+  }
+
+  /// Sends a GET request, failing if the response status is not successful.
+  async fn get(&self, url: &str) -> Result<reqwest::Response, FetchError> {
+    let response = time::timeout(self.timeout, self.inner.get(url).send())
+      .await
+      .map_err(|_| self.timed_out(url))?
+      .map_err(|err| {
+        if err.is_timeout() {
+          self.timed_out(url)
+        } else {
+          FetchError::RequestFailed
+        }
+      })?;
+
+    let status = response.status();
+
+    if !status.is_success() {
+      return Err(FetchError::RequestFailedWithCode {
+        code: status.as_u16(),
+        report: miette::miette!("\n\nURL: {url}"),
+      });
+    }
+
+    Ok(response)
+  }
+
+  /// Fetches the response body of a successful request as text.
+  async fn text(&self, url: &str) -> Result<String, FetchError> {
+    let bytes = self.bytes(url).await?;
+    String::from_utf8(bytes).map_err(|_| FetchError::RequestBodyFailed)
+  }
+
+  /// Fetches the response body of a successful request as bytes. The body is read chunk by chunk,
+  /// so only stalled downloads time out, not slow but steady ones.
+  pub(crate) async fn bytes(&self, url: &str) -> Result<Vec<u8>, FetchError> {
+    let mut response = self.get(url).await?;
+    let mut bytes = Vec::new();
+
+    while let Some(chunk) = time::timeout(self.timeout, response.chunk())
+      .await
+      .map_err(|_| self.timed_out(url))?
+      .map_err(|_| FetchError::RequestBodyFailed)?
+    {
+      bytes.extend_from_slice(&chunk);
+    }
+
+    Ok(bytes)
+  }
+
+  fn timed_out(&self, url: &str) -> FetchError {
+    FetchError::Timeout {
+      timeout: self.timeout,
+      url: url.to_string(),
+    }
+  }
+}
+
+/// Adds `HEAD` pointing to the same hash as the default branch, if it's among the refs.
+fn with_head(mut refs: Refs, default_branch: &str) -> Refs {
+  let branch = format!("refs/heads/{default_branch}");
+
+  if let Some((_, hash)) = refs.iter().find(|(name, _)| *name == branch) {
+    refs.push(("HEAD".to_string(), hash.clone()));
+  }
+
+  refs
+}
+
+/// Parses the responses of GitHub `repos/{user}/{repo}/git/refs` and `repos/{user}/{repo}`
+/// endpoints. Refs are already in the full form there.
+fn parse_github_refs(refs: &str, repository: &str) -> serde_json::Result<Refs> {
+  let repository: GitHubRepository = serde_json::from_str(repository)?;
+  let refs: Vec<GitHubRef> = serde_json::from_str(refs)?;
+
+  let refs = refs
+    .into_iter()
+    .map(|item| (item.name, item.object.sha))
+    .collect();
+
+  Ok(with_head(refs, &repository.default_branch))
+}
+
+/// Parses the response of GitHub `repos/{user}/{repo}/releases/latest` endpoint into a tag name.
+fn parse_github_release(release: &str) -> serde_json::Result<String> {
+  let release: GitHubRelease = serde_json::from_str(release)?;
+  Ok(release.tag_name)
+}
+
+/// Parses the responses of GitLab `repository/branches` and `repository/tags` endpoints. The
+/// default branch is marked in the former.
+fn parse_gitlab_refs(branches: &str, tags: &str) -> serde_json::Result<Refs> {
+  let branches: Vec<GitLabRef> = serde_json::from_str(branches)?;
+  let tags: Vec<GitLabRef> = serde_json::from_str(tags)?;
+
+  let default_branch = branches
+    .iter()
+    .find(|branch| branch.default)
+    .map(|branch| branch.name.clone())
+    .unwrap_or_default();
+
+  let refs = branches
+    .into_iter()
+    .map(|branch| (format!("refs/heads/{}", branch.name), branch.commit.id))
+    .chain(
+      tags
+        .into_iter()
+        .map(|tag| (format!("refs/tags/{}", tag.name), tag.commit.id)),
+    )
+    .collect();
+
+  Ok(with_head(refs, &default_branch))
+}
+
+/// Parses the responses of BitBucket `repositories/{user}/{repo}/refs` and
+/// `repositories/{user}/{repo}` endpoints. Refs other than branches and tags are skipped.
+fn parse_bitbucket_refs(refs: &str, repository: &str) -> serde_json::Result<Refs> {
+  let repository: BitBucketRepository = serde_json::from_str(repository)?;
+  let refs: BitBucketRefs = serde_json::from_str(refs)?;
+
+  let refs = refs
+    .values
+    .into_iter()
+    .filter_map(|item| {
+      let prefix = match item.kind.as_str() {
+        | "branch" => "refs/heads",
+        | "tag" => "refs/tags",
+        | _ => return None,
+      };
+
+      Some((format!("{prefix}/{}", item.name), item.target.hash))
+    })
+    .collect();
+
+  Ok(with_head(refs, &repository.mainbranch.name))
+}
+
+impl FromStr for LocalRepository {
+  type Err = ParseError;
+
+  /// Parses a `&str` into a `LocalRepository`. Mirrors the remote syntax: the `file:` scheme is
+  /// optional, and a trailing `#ref` is parsed into meta. The path is expanded with [expand].
+  fn from_str(input: &str) -> Result<Self, Self::Err> {
+    let source = input.trim();
+    let source = source.strip_prefix("file:").unwrap_or(source);
+
+    // Empty meta is accepted but ignored, same as for remote repositories.
+    let (path, meta) = source
+      .split_once('#')
+      .map_or((source, None), |(path, meta)| {
+        (path, Some(meta).filter(|meta| !meta.is_empty()))
+      });
+
+    if path.is_empty() {
+      return Err(ParseError(miette::miette!("Missing repository path.")));
+    }
+
+    Ok(LocalRepository {
+      source: PathBuf::from(expand(path)),
+      meta: meta.map_or(RepositoryMeta::default(), |meta| {
+        RepositoryMeta(meta.to_string())
+      }),
+      paths: Vec::new(),
+    })
   }
 }
 
 /// Represents a local repository.
 ///
 /// Repositories of this kind don't need to be downloaded, we can:
-/// - if a git repository — simply clone it locally and switch to desired meta (ref);
+/// - if a git repository — simply check out desired meta (ref) into the destination;
 /// - if a directory — simply copy it as-is.
 #[derive(Debug, PartialEq)]
 pub struct LocalRepository {
   pub source: PathBuf,
   pub meta: RepositoryMeta,
+  /// Paths relative to the source to scaffold, e.g. `templates/service-a`. Everything is
+  /// scaffolded if empty.
+  pub paths: Vec<PathBuf>,
 }
 
 impl LocalRepository {
   /// Creates new `LocalRepository`.
-  pub fn new(source: String, meta: Option<String>) -> Self {
-    Self {
-      source: PathBuf::from(source),
-      meta: meta.map_or(RepositoryMeta::default(), RepositoryMeta),
-    }
+  pub fn new(source: String, meta: Option<String>) -> Result<Self, ParseError> {
+    let repo = Self::from_str(&source)?;
+    let meta = meta.map_or(repo.meta, RepositoryMeta);
+
+    Ok(Self {
+      source: repo.source,
+      meta,
+      paths: Vec::new(),
+    })
   }
 
-  /// Copies the repository into the `destination` directory.
-  pub fn copy(&self, destination: &Path) -> Result<(), RepositoryError> {
-    let traverser = Traverser::new(self.source.to_owned())
-      .pattern("**/*")
-      .ignore_dirs(true)
-      .contents_first(true);
+  /// Restricts copying and checking out to the given paths relative to the source.
+  pub fn with_paths(mut self, paths: Vec<PathBuf>) -> Self {
+    self.paths = paths;
+    self
+  }
 
-    for matched in traverser.iter().flatten() {
-      let target = destination.join(&matched.captured);
+  /// Checks if the path relative to the source is within the paths to scaffold.
+  fn is_included(&self, path: &Path) -> bool {
+    self.paths.is_empty() || self.paths.iter().any(|included| path.starts_with(included))
+  }
 
-      if let Some(parent) = target.parent() {
-        fs::create_dir_all(parent).map_err(|source| {
-          RepositoryError::Io {
-            message: format!(
-              "Failed to create directory structure for '{}'.",
-              parent.display()
-            ),
-            source,
-          }
-        })?;
-
-        fs::copy(&matched.path, &target).map_err(|source| {
-          RepositoryError::Io {
-            message: format!(
-              "Failed to copy from '{}' to '{}'.",
-              matched.path.display(),
-              target.display()
-            ),
-            source,
-          }
-        })?;
-      }
-    }
+  /// Returns the paths to scaffold that don't exist in the source, or in the tree of the commit
+  /// with the given hash if the source is a repository.
+  pub fn missing_paths(&self, hash: Option<Oid>) -> Result<Vec<PathBuf>, CheckoutError> {
+    let Some(hash) = hash else {
+      return Ok(
+        self
+          .paths
+          .iter()
+          .filter(|path| !self.source.join(path).exists())
+          .cloned()
+          .collect(),
+      );
+    };
 
-    Ok(())
+    let repository = GitRepository::open(&self.source).map_err(CheckoutError::OpenFailed)?;
+
+    let tree = repository
+      .find_commit(hash)
+      .and_then(|commit| commit.tree())
+      .map_err(|_| CheckoutError::RevparseFailed(hash.to_string()))?;
+
+    Ok(
+      self
+        .paths
+        .iter()
+        .filter(|path| tree.get_path(path).is_err())
+        .cloned()
+        .collect(),
+    )
+  }
+
+  /// Copies the repository into the `destination` directory. The `.git` directory is skipped, so
+  /// the history isn't duplicated into the destination. Returns paths of copied files.
+  pub fn copy(&self, destination: &Path) -> Result<Vec<PathBuf>, RepositoryError> {
+    copy_dir(
+      &self.source,
+      destination,
+      vec![".git".to_string(), ".git/**".to_string()],
+      |path| self.is_included(path),
+    )
   }
 
-  /// Checks out the repository located at the `destination`.
-  pub fn checkout(&self, destination: &Path) -> Result<(), CheckoutError> {
+  /// Resolves the desired ref of the source repository to a commit hash, so an invalid ref can be
+  /// reported before anything is written.
+  pub fn resolve(&self) -> Result<Oid, CheckoutError> {
     let meta = self.meta.to_string();
-    let head = "HEAD".to_string();
 
     // First, try to create Repository.
-    let repository = GitRepository::open(destination).map_err(CheckoutError::OpenFailed)?;
+    let repository = GitRepository::open(&self.source).map_err(CheckoutError::OpenFailed)?;
 
     // Note: in case of local repositories, instead of HEAD we want to check origin/HEAD first,
     // which should be the default branch if the repository has been cloned from a remote.
     // Otherwise we fallback to HEAD, which will point to whatever the repository points at the time
-    // of cloning (can be absolutely arbitrary reference/state).
+    // of scaffolding (can be absolutely arbitrary reference/state).
     let meta = if meta == "HEAD" {
       repository
         .revparse_ext("origin/HEAD")
         .ok()
         .and_then(|(_, reference)| reference)
         .and_then(|reference| reference.name().map(str::to_string))
-        .unwrap_or(head)
+        .unwrap_or(meta)
     } else {
-      head
+      meta
     };
 
-    // Try to find (parse revision) the desired reference: branch, tag or commit. They are encoded
-    // in two objects:
-    //
-    // - `object` contains (among other things) the commit hash.
-    // - `reference` points to the branch or tag.
-    let (object, reference) = repository
-      .revparse_ext(&meta)
-      .map_err(|_| CheckoutError::RevparseFailed(meta))?;
+    // Try to find (parse revision) the desired reference: branch, tag or commit, and peel it to
+    // the commit.
+    repository
+      .revparse_single(&meta)
+      .and_then(|object| object.peel_to_commit())
+      .map(|commit| commit.id())
+      .map_err(|_| CheckoutError::RevparseFailed(meta))
+  }
+
+  /// Checks out the commit with the given hash (see [LocalRepository::resolve]) into the
+  /// `destination` directory. The source repository itself (HEAD, index and working tree) is left
+  /// untouched. Returns paths of checked out files.
+  pub fn checkout(&self, hash: Oid, destination: &Path) -> Result<Vec<PathBuf>, CheckoutError> {
+    let repository = GitRepository::open(&self.source).map_err(CheckoutError::OpenFailed)?;
+
+    let object = repository
+      .find_object(hash, None)
+      .map_err(|_| CheckoutError::RevparseFailed(hash.to_string()))?;
 
-    // Build checkout options.
+    // Build checkout options. Files are written into the destination instead of the working tree
+    // of the source, without updating its index. Existing files are overwritten, but untracked ones
+    // are left alone, since the destination may be an existing directory.
     let mut checkout = CheckoutBuilder::new();
 
     checkout
+      .target_dir(destination)
+      .update_index(false)
       .skip_unmerged(true)
-      .remove_untracked(true)
-      .remove_ignored(true)
       .force();
 
-    // Updates files in the index and working tree.
+    for path in &self.paths {
+      checkout.path(path);
+    }
+
     repository
       .checkout_tree(&object, Some(&mut checkout))
       .map_err(|_| CheckoutError::TreeCheckoutFailed)?;
 
-    match reference {
-      // Here `gref` is an actual reference like branch or tag.
-      | Some(gref) => {
-        let ref_name = gref.name().ok_or(CheckoutError::InvalidRefName)?;
+    // Collect paths of files in the checked out tree. Here `root` is the path of the parent tree,
+    // either empty or ending with a slash.
+    let mut paths = Vec::new();
 
-        repository
-          .set_head(ref_name)
-          .map_err(|_| CheckoutError::SetHeadFailed(ref_name.to_string()))?;
-      },
-      // This is a commit, detach HEAD.
-      | None => {
-        let hash = object.id();
+    object
+      .peel_to_tree()
+      .and_then(|tree| {
+        tree.walk(TreeWalkMode::PreOrder, |root, entry| {
+          if let (Some(ObjectType::Blob), Some(name)) = (entry.kind(), entry.name()) {
+            let path = Path::new(root).join(name);
 
-        repository
-          .set_head_detached(hash)
-          .map_err(|_| CheckoutError::DetachHeadFailed(hash.to_string()))?;
-      },
+            if self.is_included(&path) {
+              paths.push(destination.join(path));
+            }
+          }
+
+          TreeWalkResult::Ok
+        })
+      })
+      .map_err(|_| CheckoutError::TreeCheckoutFailed)?;
+
+    Ok(paths)
+  }
+
+  /// Preserves the history by copying the `.git` directory of the source into the `destination`
+  /// with checked out files (see [LocalRepository::checkout]). HEAD is pointed at the branch named
+  /// by the ref if it's at the given commit, otherwise it's detached at the commit. The index is
+  /// reset to the commit, so the working tree is clean. Returns `false` if the source's `.git`
+  /// isn't a directory (e.g. in worktrees or submodules) or the `destination` is a repository.
+  pub fn keep_history(&self, hash: Oid, destination: &Path) -> Result<bool, RepositoryError> {
+    let source = self.source.join(".git");
+    let target = destination.join(".git");
+
+    if !source.is_dir() || target.try_exists().unwrap_or(true) {
+      return Ok(false);
     }
 
-    Ok(())
+    copy_dir(&source, &target, Vec::new(), |_| true)?;
+
+    let repository = GitRepository::open(destination).map_err(RepositoryError::HistoryFailed)?;
+
+    // The default ref is resolved to whatever HEAD points at, so keep the current branch then.
+    let branch = if self.meta == RepositoryMeta::default() {
+      repository
+        .head()
+        .ok()
+        .and_then(|head| head.name().map(str::to_string))
+    } else {
+      Some(format!("refs/heads/{}", self.meta))
+    };
+
+    let branch = branch.filter(|branch| {
+      repository
+        .find_reference(branch)
+        .and_then(|reference| reference.peel_to_commit())
+        .is_ok_and(|commit| commit.id() == hash)
+    });
+
+    match branch {
+      | Some(branch) => repository.set_head(&branch),
+      | None => repository.set_head_detached(hash),
+    }
+    .and_then(|_| repository.find_object(hash, None))
+    .and_then(|commit| repository.reset(&commit, ResetType::Mixed, None))
+    .map_err(RepositoryError::HistoryFailed)?;
+
+    Ok(true)
+  }
+
+  /// Checks if the source is a git repository.
+  pub fn is_repository(&self) -> bool {
+    self.source.join(".git").try_exists().unwrap_or(false)
+  }
+}
+
+/// Copies files and directories of the `source` directory into the `destination` directory,
+/// skipping entries matching the `exclude` globs. Empty directories are copied too, since some are
+/// required, e.g. `refs/heads` of a repository with packed refs. Returns paths of copied files.
+fn copy_dir(
+  source: &Path,
+  destination: &Path,
+  exclude: Vec<String>,
+  include: impl Fn(&Path) -> bool,
+) -> Result<Vec<PathBuf>, RepositoryError> {
+  let mut copied = Vec::new();
+
+  let traverser = Traverser::new(source.to_owned())
+    .pattern("**/*")
+    .contents_first(true)
+    .exclude(exclude);
+
+  for matched in traverser.iter().flatten() {
+    if !include(&matched.captured) {
+      continue;
+    }
+
+    let target = destination.join(&matched.captured);
+
+    if matched.is_dir() {
+      fs::create_dir_all(&target).map_err(|source| {
+        RepositoryError::Io {
+          message: format!("Failed to create directory '{}'.", target.display()),
+          source,
+        }
+      })?;
+
+      continue;
+    }
+
+    if let Some(parent) = target.parent() {
+      fs::create_dir_all(parent).map_err(|source| {
+        RepositoryError::Io {
+          message: format!(
+            "Failed to create directory structure for '{}'.",
+            parent.display()
+          ),
+          source,
+        }
+      })?;
+
+      fs::copy(&matched.path, &target).map_err(|source| {
+        RepositoryError::Io {
+          message: format!(
+            "Failed to copy from '{}' to '{}'.",
+            matched.path.display(),
+            target.display()
+          ),
+          source,
+        }
+      })?;
+
+      copy_permissions(&matched.path, &target).map_err(|source| {
+        RepositoryError::Io {
+          message: format!("Failed to set permissions of '{}'.", target.display()),
+          source,
+        }
+      })?;
+
+      copied.push(target);
+    }
+  }
+
+  Ok(copied)
+}
+
+/// Re-applies the mode of the `source` file to the `target` file, since `fs::copy` may drop the
+/// executable bit on some setups. Mirrors what the unpacker does for tarballs.
+#[cfg(unix)]
+fn copy_permissions(source: &Path, target: &Path) -> io::Result<()> {
+  use std::os::unix::fs::PermissionsExt;
+
+  let mode = fs::metadata(source)?.permissions().mode();
+
+  fs::set_permissions(target, fs::Permissions::from_mode(mode))
+}
+
+/// No-op on platforms without Unix permissions.
+#[cfg(not(unix))]
+fn copy_permissions(_source: &Path, _target: &Path) -> io::Result<()> {
+  Ok(())
+}
+
+/// Initializes a git repository at the `destination` and commits all of its files, honoring
+/// `.gitignore` files. Returns `false` if the `destination` is already a repository.
+pub fn init(destination: &Path, message: &str) -> Result<bool, InitError> {
+  if let Ok(true) = destination.join(".git").try_exists() {
+    return Ok(false);
+  }
+
+  let repository = GitRepository::init(destination).map_err(InitError::InitFailed)?;
+
+  // Don't leave a half-initialized repository behind, e.g. if the author isn't configured.
+  if let Err(err) = commit_all(&repository, message) {
+    let _ = fs::remove_dir_all(destination.join(".git"));
+    return Err(err);
   }
+
+  Ok(true)
+}
+
+/// Stages all files of the repository and commits them.
+fn commit_all(repository: &GitRepository, message: &str) -> Result<(), InitError> {
+  let mut index = repository.index().map_err(InitError::StageFailed)?;
+
+  index
+    .add_all(["*"], IndexAddOption::DEFAULT, None)
+    .map_err(InitError::StageFailed)?;
+
+  index.write().map_err(InitError::StageFailed)?;
+
+  let tree = index
+    .write_tree()
+    .and_then(|oid| repository.find_tree(oid))
+    .map_err(InitError::StageFailed)?;
+
+  let signature = repository
+    .signature()
+    .map_err(InitError::SignatureMissing)?;
+
+  repository
+    .commit(Some("HEAD"), &signature, &signature, message, &tree, &[])
+    .map_err(InitError::CommitFailed)?;
+
+  Ok(())
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use crate::utils::temp::TempDir;
+
+  /// Initializes a repository in the directory and commits all files in it to `HEAD`.
+  fn init_repo_with_commit(path: &Path) -> (GitRepository, Oid) {
+    let repository = GitRepository::init(path).unwrap();
+    let commit = commit_files(&repository, "init", Some("HEAD"));
+
+    (repository, commit)
+  }
+
+  /// Commits all files in the working directory on top of `HEAD`, updating the given ref if any.
+  fn commit_files(repository: &GitRepository, message: &str, update: Option<&str>) -> Oid {
+    let signature = git2::Signature::now("decaff", "decaff@example.com").unwrap();
+
+    let mut index = repository.index().unwrap();
+    index.add_all(["*"], IndexAddOption::DEFAULT, None).unwrap();
+    let tree = repository.find_tree(index.write_tree().unwrap()).unwrap();
+
+    let parents = repository
+      .head()
+      .and_then(|head| head.peel_to_commit())
+      .map(|parent| vec![parent])
+      .unwrap_or_default();
+
+    repository
+      .commit(
+        update,
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &parents.iter().collect::<Vec<_>>(),
+      )
+      .unwrap()
+  }
 
   #[test]
   fn parse_remote_default() {
@@ -528,7 +1228,8 @@ mod tests {
         user: "foo".to_string(),
         repo: "bar".to_string(),
         meta: RepositoryMeta::default(),
-        refs: HashMap::default()
+        refs: HashMap::default(),
+        tags: Vec::new()
       })
     );
   }
@@ -552,8 +1253,8 @@ mod tests {
   #[test]
   fn parse_remote_invalid_reponame() {
     assert_eq!(
-      RemoteRepository::from_str("foo-bar/b@z").map_err(|report| report.to_string()),
-      Err("Invalid repository name: `b@z`.".to_string())
+      RemoteRepository::from_str("foo-bar/b$z").map_err(|report| report.to_string()),
+      Err("Invalid repository name: `b$z`.".to_string())
     );
   }
 
@@ -567,8 +1268,9 @@ mod tests {
           code = "decaff::repository::parse",
           labels = vec![LabeledSpan::at(
             (0, 5),
-            "must be one of: github/gh, gitlab/gl, or bitbucket/bb"
+            "must be one of: github/gh, gitlab/gl, bitbucket/bb"
           )],
+          help = "Run `decaff remote --list-hosts` to list supported hosts.",
           "Invalid host: `srht`."
         )
         .to_string()
@@ -596,12 +1298,72 @@ mod tests {
           user: "foo".to_string(),
           repo: "bar".to_string(),
           refs: HashMap::default(),
+          tags: Vec::new(),
           meta,
         })
       );
     }
   }
 
+  #[test]
+  fn parse_remote_meta_with_at() {
+    let cases = [
+      ("foo/bar@v1", "foo/bar#v1"),
+      ("gh:foo/bar@feat/x", "gh:foo/bar#feat/x"),
+      ("foo/bar@feat#x", "foo/bar#feat#x"),
+      ("foo/bar#user@host", "foo/bar#user@host"),
+    ];
+
+    for (at, hash) in cases {
+      assert_eq!(
+        RemoteRepository::from_str(at).map_err(|report| report.to_string()),
+        RemoteRepository::from_str(hash).map_err(|report| report.to_string())
+      );
+    }
+
+    assert!(RemoteRepository::from_str("foo/bar/baz@v1").is_err());
+  }
+
+  #[test]
+  fn resolve_latest_tag() {
+    let mut remote = RemoteRepository::new("foo/bar".to_string(), Some("v*".to_string())).unwrap();
+
+    remote.tags = [
+      "v1.2.0",
+      "v1.10.0",
+      "v1.9.3",
+      "v2.0.0-rc.1",
+      "vnext",
+      "nightly",
+    ]
+    .map(str::to_string)
+    .to_vec();
+
+    assert_eq!(remote.resolve_tag().unwrap(), Some("v1.10.0".to_string()));
+    assert_eq!(remote.meta, RepositoryMeta("v1.10.0".to_string()));
+
+    remote.meta = RepositoryMeta("v2.0.0-*".to_string());
+    assert_eq!(
+      remote.resolve_tag().unwrap(),
+      Some("v2.0.0-rc.1".to_string())
+    );
+
+    remote.meta = RepositoryMeta("v2*".to_string());
+    assert!(remote.resolve_tag().is_err());
+
+    remote.meta = RepositoryMeta("v1.*".to_string());
+    assert_eq!(remote.resolve_tag().unwrap(), Some("v1.10.0".to_string()));
+
+    remote.meta = RepositoryMeta("n*".to_string());
+    assert_eq!(remote.resolve_tag().unwrap(), Some("nightly".to_string()));
+
+    remote.meta = RepositoryMeta("main".to_string());
+    assert_eq!(remote.resolve_tag().unwrap(), None);
+
+    remote.meta = RepositoryMeta("release-*".to_string());
+    assert!(remote.resolve_tag().is_err());
+  }
+
   #[test]
   fn parse_remote_hosts() {
     let cases = [
@@ -621,12 +1383,44 @@ mod tests {
           user: "foo".to_string(),
           repo: "bar".to_string(),
           meta: RepositoryMeta::default(),
-          refs: HashMap::default()
+          refs: HashMap::default(),
+          tags: Vec::new()
         })
       );
     }
   }
 
+  #[test]
+  fn display_host_round_trip() {
+    let hosts = [
+      RepositoryHost::GitHub,
+      RepositoryHost::GitLab,
+      RepositoryHost::BitBucket,
+    ];
+
+    for host in hosts {
+      let input = format!("{host}:foo/bar");
+
+      assert_eq!(input, input.to_ascii_lowercase());
+      assert_eq!(RemoteRepository::from_str(&input).unwrap().host, host);
+    }
+  }
+
+  #[test]
+  fn host_from_alias() {
+    for host in RepositoryHost::ALL {
+      for alias in host.aliases() {
+        assert_eq!(RepositoryHost::from_alias(alias), Some(host));
+        assert_eq!(
+          RepositoryHost::from_alias(&alias.to_ascii_uppercase()),
+          Some(host)
+        );
+      }
+    }
+
+    assert_eq!(RepositoryHost::from_alias("srht"), None);
+  }
+
   #[test]
   fn test_remote_empty_meta() {
     assert_eq!(
@@ -636,7 +1430,8 @@ mod tests {
         user: "foo".to_string(),
         repo: "bar".to_string(),
         meta: RepositoryMeta::default(),
-        refs: HashMap::default()
+        refs: HashMap::default(),
+        tags: Vec::new()
       })
     );
   }
@@ -660,9 +1455,417 @@ mod tests {
           user: user.to_string(),
           repo: repo.to_string(),
           meta: RepositoryMeta::default(),
-          refs: HashMap::default()
+          refs: HashMap::default(),
+          tags: Vec::new()
         })
       );
     }
   }
+
+  #[test]
+  fn parse_local() {
+    let home = home::home_dir().unwrap();
+
+    let cases = [
+      (
+        "./template",
+        PathBuf::from("./template"),
+        RepositoryMeta::default(),
+      ),
+      (
+        "file:./template",
+        PathBuf::from("./template"),
+        RepositoryMeta::default(),
+      ),
+      (
+        "file:./template#",
+        PathBuf::from("./template"),
+        RepositoryMeta::default(),
+      ),
+      (
+        "file:~/templates/x#dev",
+        home.join("templates/x"),
+        RepositoryMeta("dev".to_string()),
+      ),
+      (
+        "../template#feat/x",
+        PathBuf::from("../template"),
+        RepositoryMeta("feat/x".to_string()),
+      ),
+    ];
+
+    for (input, source, meta) in cases {
+      assert_eq!(
+        LocalRepository::from_str(input).map_err(|report| report.to_string()),
+        Ok(LocalRepository { source, meta, paths: Vec::new() })
+      );
+    }
+  }
+
+  #[test]
+  fn parse_local_missing_path() {
+    assert_eq!(
+      LocalRepository::from_str("file:#dev").map_err(|report| report.to_string()),
+      Err("Missing repository path.".to_string())
+    );
+  }
+
+  #[test]
+  fn local_ref_overrides_parsed_meta() {
+    let local = LocalRepository::new("./template#dev".to_string(), Some("main".to_string()));
+
+    assert_eq!(local.unwrap().meta, RepositoryMeta("main".to_string()));
+  }
+
+  #[test]
+  #[cfg(unix)]
+  fn local_copy_preserves_executable_bit() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let root = TempDir::new("local-copy");
+    let source = root.join("source");
+    let destination = root.join("destination");
+
+    fs::create_dir_all(source.join("bin")).unwrap();
+
+    let script = source.join("bin/setup.sh");
+    fs::write(&script, "#!/bin/sh\n").unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let local = LocalRepository::new(source.display().to_string(), None).unwrap();
+    local.copy(&destination).unwrap();
+
+    let mode = fs::metadata(destination.join("bin/setup.sh"))
+      .unwrap()
+      .permissions()
+      .mode();
+
+    assert_eq!(mode & 0o777, 0o755);
+  }
+
+  #[test]
+  fn local_checkout_requested_branch() {
+    let root = TempDir::new("local-branch");
+    let source = root.join("source");
+
+    fs::create_dir_all(&source).unwrap();
+
+    fs::write(source.join("README.md"), "default\n").unwrap();
+    let (repository, initial) = init_repo_with_commit(&source);
+
+    // Commit on top of the initial one without moving HEAD.
+    fs::write(source.join("README.md"), "feature\n").unwrap();
+    let feature = commit_files(&repository, "feature", None);
+
+    repository
+      .branch("feature", &repository.find_commit(feature).unwrap(), false)
+      .unwrap();
+
+    let checkout = |meta: Option<&str>, name: &str| {
+      let local =
+        LocalRepository::new(source.display().to_string(), meta.map(str::to_string)).unwrap();
+      let destination = root.join(name);
+      let hash = local.resolve().unwrap();
+
+      local.checkout(hash, &destination).unwrap();
+
+      (
+        hash,
+        fs::read_to_string(destination.join("README.md")).unwrap(),
+      )
+    };
+
+    assert_eq!(
+      checkout(Some("feature"), "feature"),
+      (feature, "feature\n".to_string())
+    );
+    assert_eq!(
+      checkout(None, "default"),
+      (initial, "default\n".to_string())
+    );
+
+    // The source repository is left on its branch.
+    assert_eq!(
+      repository.head().unwrap().peel_to_commit().unwrap().id(),
+      initial
+    );
+  }
+
+  #[test]
+  fn local_keep_history_points_head_at_ref() {
+    let root = TempDir::new("local-history");
+    let source = root.join("source");
+    let destination = root.join("destination");
+
+    fs::create_dir_all(&source).unwrap();
+    fs::write(source.join("README.md"), "hello\n").unwrap();
+
+    let (repository, commit) = init_repo_with_commit(&source);
+
+    repository
+      .branch("dev", &repository.find_commit(commit).unwrap(), false)
+      .unwrap();
+
+    let local =
+      LocalRepository::new(source.display().to_string(), Some("dev".to_string())).unwrap();
+    let hash = local.resolve().unwrap();
+
+    local.checkout(hash, &destination).unwrap();
+    assert!(local.keep_history(hash, &destination).unwrap());
+    assert!(!local.keep_history(hash, &destination).unwrap());
+
+    let kept = GitRepository::open(&destination).unwrap();
+
+    assert_eq!(kept.head().unwrap().name(), Some("refs/heads/dev"));
+    assert!(kept.statuses(None).unwrap().is_empty());
+  }
+
+  #[test]
+  fn local_keep_history_with_packed_refs() {
+    let root = TempDir::new("packed-refs");
+    let source = root.join("source");
+    let destination = root.join("destination");
+
+    fs::create_dir_all(&source).unwrap();
+    fs::write(source.join("README.md"), "hello\n").unwrap();
+
+    let (repository, commit) = init_repo_with_commit(&source);
+
+    let head = repository.head().unwrap().name().unwrap().to_string();
+
+    // Pack the refs like `git gc` does, leaving `refs/heads` and `refs/tags` empty.
+    fs::write(
+      source.join(".git/packed-refs"),
+      format!("# pack-refs with: peeled fully-peeled sorted\n{commit} {head}\n"),
+    )
+    .unwrap();
+    fs::remove_file(source.join(".git").join(&head)).unwrap();
+
+    assert!(fs::read_dir(source.join(".git/refs/heads"))
+      .unwrap()
+      .next()
+      .is_none());
+
+    let local = LocalRepository::new(source.display().to_string(), None).unwrap();
+    let hash = local.resolve().unwrap();
+
+    local.checkout(hash, &destination).unwrap();
+    assert!(local.keep_history(hash, &destination).unwrap());
+
+    let kept = GitRepository::open(&destination).unwrap();
+
+    assert_eq!(kept.head().unwrap().name(), Some(head.as_str()));
+    assert!(kept.statuses(None).unwrap().is_empty());
+  }
+
+  #[test]
+  fn local_copy_skips_git_directory() {
+    let root = TempDir::new("local-git");
+    let source = root.join("source");
+    let destination = root.join("destination");
+
+    fs::create_dir_all(source.join(".git/objects")).unwrap();
+    fs::write(source.join(".git/HEAD"), "ref: refs/heads/main\n").unwrap();
+    fs::write(source.join(".gitignore"), "dist\n").unwrap();
+
+    let local = LocalRepository::new(source.display().to_string(), None).unwrap();
+    local.copy(&destination).unwrap();
+
+    assert!(destination.join(".gitignore").is_file());
+    assert!(!destination.join(".git").exists());
+  }
+
+  #[test]
+  fn parse_github_api_refs() {
+    let repository = r#"{ "name": "bar", "default_branch": "main" }"#;
+    let refs = r#"[
+      {
+        "ref": "refs/heads/main",
+        "node_id": "MDM6UmVm",
+        "object": { "sha": "aa218f56b14c9653891f9e74264a383fa43fefbd", "type": "commit" }
+      },
+      {
+        "ref": "refs/tags/v1.0.0",
+        "object": { "sha": "bb218f56b14c9653891f9e74264a383fa43fefbd", "type": "commit" }
+      }
+    ]"#;
+
+    assert_eq!(
+      parse_github_refs(refs, repository).unwrap(),
+      vec![
+        (
+          "refs/heads/main".to_string(),
+          "aa218f56b14c9653891f9e74264a383fa43fefbd".to_string()
+        ),
+        (
+          "refs/tags/v1.0.0".to_string(),
+          "bb218f56b14c9653891f9e74264a383fa43fefbd".to_string()
+        ),
+        (
+          "HEAD".to_string(),
+          "aa218f56b14c9653891f9e74264a383fa43fefbd".to_string()
+        ),
+      ]
+    );
+  }
+
+  #[test]
+  fn parse_github_api_release() {
+    let release = r#"{ "tag_name": "v1.2.0", "name": "Release 1.2.0", "draft": false }"#;
+
+    assert_eq!(parse_github_release(release).unwrap(), "v1.2.0");
+    assert!(parse_github_release("{}").is_err());
+  }
+
+  #[tokio::test]
+  async fn resolve_release_only_for_github_latest() {
+    let client = Client::new(DEFAULT_TIMEOUT).unwrap();
+
+    let mut remote = RemoteRepository::new("gl:foo/bar@latest".to_string(), None).unwrap();
+    assert_eq!(remote.resolve_release(&client).await.unwrap(), None);
+    assert_eq!(remote.meta, RepositoryMeta("latest".to_string()));
+
+    let mut remote = RemoteRepository::new("foo/bar@main".to_string(), None).unwrap();
+    assert_eq!(remote.resolve_release(&client).await.unwrap(), None);
+
+    // A branch or tag named `latest` takes precedence.
+    let mut remote = RemoteRepository::new("foo/bar@latest".to_string(), None).unwrap();
+    remote
+      .refs
+      .insert("latest".to_string(), "aa218f56".to_string());
+    assert_eq!(remote.resolve_release(&client).await.unwrap(), None);
+    assert_eq!(remote.meta, RepositoryMeta("latest".to_string()));
+  }
+
+  #[tokio::test]
+  async fn client_times_out_on_stalled_response() {
+    // Connections are queued by the OS, but nothing is ever sent back.
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    let client = Client::new(Duration::from_millis(100)).unwrap();
+
+    assert!(matches!(
+      client.text(&url).await,
+      Err(FetchError::Timeout { .. })
+    ));
+  }
+
+  #[test]
+  fn parse_gitlab_api_refs() {
+    let branches = r#"[
+      { "name": "develop", "default": false, "commit": { "id": "cc01", "short_id": "cc01" } },
+      { "name": "main", "default": true, "commit": { "id": "dd02", "short_id": "dd02" } }
+    ]"#;
+    let tags = r#"[{ "name": "v2.0.0", "message": "", "commit": { "id": "ee03" } }]"#;
+
+    assert_eq!(
+      parse_gitlab_refs(branches, tags).unwrap(),
+      vec![
+        ("refs/heads/develop".to_string(), "cc01".to_string()),
+        ("refs/heads/main".to_string(), "dd02".to_string()),
+        ("refs/tags/v2.0.0".to_string(), "ee03".to_string()),
+        ("HEAD".to_string(), "dd02".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn parse_bitbucket_api_refs() {
+    let repository =
+      r#"{ "full_name": "foo/bar", "mainbranch": { "type": "branch", "name": "master" } }"#;
+    let refs = r#"{
+      "pagelen": 100,
+      "values": [
+        { "name": "master", "type": "branch", "target": { "hash": "ff04", "type": "commit" } },
+        { "name": "v3.0.0", "type": "tag", "target": { "hash": "0a05", "type": "commit" } },
+        { "name": "other", "type": "bookmark", "target": { "hash": "0b06" } }
+      ]
+    }"#;
+
+    assert_eq!(
+      parse_bitbucket_refs(refs, repository).unwrap(),
+      vec![
+        ("refs/heads/master".to_string(), "ff04".to_string()),
+        ("refs/tags/v3.0.0".to_string(), "0a05".to_string()),
+        ("HEAD".to_string(), "ff04".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn store_normalized_refs() {
+    let mut remote = RemoteRepository::from_str("gitlab:foo/bar#v2.0.0").unwrap();
+
+    remote.store_refs(vec![
+      ("HEAD".to_string(), "dd02".to_string()),
+      ("refs/heads/main".to_string(), "dd02".to_string()),
+      ("refs/tags/v2.0.0".to_string(), "ee03".to_string()),
+      ("refs/tags/v2.0.0^{}".to_string(), "ee04".to_string()),
+      ("refs/pull/1/head".to_string(), "ff05".to_string()),
+    ]);
+
+    assert_eq!(remote.tags, vec!["v2.0.0".to_string()]);
+    assert_eq!(remote.refs.len(), 4);
+    assert_eq!(remote.resolve_hash().unwrap(), "ee03");
+  }
+
+  #[test]
+  fn local_paths_restrict_copy_and_checkout() {
+    let root = TempDir::new("local-paths");
+    let source = root.join("source");
+
+    for path in ["templates/a/a.txt", "templates/b/b.txt", "README.md"] {
+      fs::create_dir_all(source.join(path).parent().unwrap()).unwrap();
+      fs::write(source.join(path), path).unwrap();
+    }
+
+    let local = |paths: &[&str]| {
+      LocalRepository::new(source.display().to_string(), None)
+        .unwrap()
+        .with_paths(paths.iter().map(PathBuf::from).collect())
+    };
+
+    // Plain directory.
+    let copied = local(&["templates/a", "README.md"])
+      .copy(&root.join("copied"))
+      .unwrap();
+
+    assert_eq!(copied.len(), 2);
+    assert!(root.join("copied/templates/a/a.txt").is_file());
+    assert!(root.join("copied/README.md").is_file());
+    assert!(!root.join("copied/templates/b").exists());
+
+    assert_eq!(
+      local(&["templates/a", "templates/c"])
+        .missing_paths(None)
+        .unwrap(),
+      vec![PathBuf::from("templates/c")]
+    );
+
+    // Repository.
+    init_repo_with_commit(&source);
+
+    let local = local(&["templates/b"]);
+    let hash = local.resolve().unwrap();
+    let checked_out = local.checkout(hash, &root.join("checked-out")).unwrap();
+
+    assert_eq!(
+      checked_out,
+      vec![root.join("checked-out/templates/b/b.txt")]
+    );
+    assert!(!root.join("checked-out/templates/a").exists());
+    assert!(!root.join("checked-out/README.md").exists());
+
+    assert!(local.missing_paths(Some(hash)).unwrap().is_empty());
+    assert_eq!(
+      LocalRepository {
+        paths: vec![PathBuf::from("nope")],
+        ..local
+      }
+      .missing_paths(Some(hash))
+      .unwrap(),
+      vec![PathBuf::from("nope")]
+    );
+  }
 }
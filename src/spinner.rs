@@ -42,12 +42,21 @@ impl Spinner {
   }
 
   /// Stops the spinner and clears the message.
-  #[allow(dead_code)]
   pub fn stop_with_clear(&self) {
     self.spinner.finish_and_clear();
   }
 }
 
+impl Drop for Spinner {
+  /// Clears the spinner if it wasn't stopped, e.g. when an error was propagated past it, so its
+  /// ticker doesn't keep drawing over the following output.
+  fn drop(&mut self) {
+    if !self.spinner.is_finished() {
+      self.spinner.finish_and_clear();
+    }
+  }
+}
+
 impl Default for Spinner {
   fn default() -> Self {
     Self::new()
@@ -1,11 +1,16 @@
+use std::collections::HashSet;
 use std::fs;
-use std::io;
+use std::io::{self, Read};
 use std::path::{Path, PathBuf};
 
+use bzip2::bufread::BzDecoder;
 use flate2::bufread::GzDecoder;
 use miette::Diagnostic;
 use tar::Archive;
 use thiserror::Error;
+use xz2::bufread::XzDecoder;
+
+use crate::spinner::Spinner;
 
 #[cfg(target_os = "windows")]
 const USE_XATTRS: bool = false;
@@ -28,21 +33,101 @@ pub enum UnpackError {
     #[source]
     source: io::Error,
   },
+  #[error(
+    "Unsupported archive format. Only uncompressed, gzip, xz and bzip2 compressed tarballs are \
+     supported."
+  )]
+  #[diagnostic(code(decaff::unpack::unsupported_format))]
+  UnsupportedFormat,
+}
+
+/// Offset of the `ustar` magic in the header of the first tar entry.
+const USTAR_MAGIC_OFFSET: usize = 257;
+
+/// Supported compression formats, detected by magic bytes.
+#[derive(Debug, PartialEq)]
+enum Compression {
+  Gzip,
+  Xz,
+  Bzip2,
+  /// Plain tarball, e.g. served by a proxy or a cache that already decompressed it.
+  Uncompressed,
+}
+
+impl Compression {
+  /// Detects the compression format from the leading (magic) bytes. Uncompressed tarballs are
+  /// detected by the `ustar` magic of the first header, used by both POSIX and GNU tar.
+  fn detect(bytes: &[u8]) -> Option<Self> {
+    match bytes {
+      | [0x1f, 0x8b, ..] => Some(Self::Gzip),
+      | [0xfd, b'7', b'z', b'X', b'Z', 0x00, ..] => Some(Self::Xz),
+      | [b'B', b'Z', b'h', ..] => Some(Self::Bzip2),
+      | _ if bytes[USTAR_MAGIC_OFFSET.min(bytes.len())..].starts_with(b"ustar") => {
+        Some(Self::Uncompressed)
+      },
+      | _ => None,
+    }
+  }
 }
 
+/// Default number of leading path components to strip. Tarballs served by GitHub, GitLab and
+/// BitBucket wrap everything in a single top-level directory.
+pub const DEFAULT_STRIP_COMPONENTS: usize = 1;
+
+/// Number of unpacked entries between updates of the progress spinner.
+const PROGRESS_STEP: usize = 100;
+
 pub struct Unpacker {
   bytes: Vec<u8>,
+  strip_components: usize,
+  progress: bool,
 }
 
 impl Unpacker {
   pub fn new(bytes: Vec<u8>) -> Self {
-    Self { bytes }
+    Self {
+      bytes,
+      strip_components: DEFAULT_STRIP_COMPONENTS,
+      progress: false,
+    }
+  }
+
+  /// Set the number of leading path components to strip from entries.
+  pub fn with_strip(mut self, strip_components: usize) -> Self {
+    self.strip_components = strip_components;
+    self
+  }
+
+  /// Set whether to show a spinner with the number of unpacked entries.
+  pub fn with_progress(mut self, progress: bool) -> Self {
+    self.progress = progress;
+    self
   }
 
-  /// Unpacks the tar archive to the given [Path].
+  /// Unpacks the tar archive to the given [Path]. Compression format is detected automatically.
   pub fn unpack_to(&self, path: &Path) -> Result<Vec<PathBuf>, UnpackError> {
-    let mut archive = Archive::new(GzDecoder::new(&self.bytes[..]));
+    let bytes = &self.bytes[..];
+
+    match Compression::detect(bytes) {
+      | Some(Compression::Gzip) => self.unpack_archive(GzDecoder::new(bytes), path),
+      | Some(Compression::Xz) => self.unpack_archive(XzDecoder::new(bytes), path),
+      | Some(Compression::Bzip2) => self.unpack_archive(BzDecoder::new(bytes), path),
+      | Some(Compression::Uncompressed) => self.unpack_archive(bytes, path),
+      | None => Err(UnpackError::UnsupportedFormat),
+    }
+  }
+
+  /// Unpacks entries of the tar archive read from the given decoder.
+  fn unpack_archive<R: Read>(&self, decoder: R, path: &Path) -> Result<Vec<PathBuf>, UnpackError> {
+    let mut archive = Archive::new(decoder);
     let mut written_paths = Vec::new();
+    let mut seen_paths = HashSet::new();
+
+    let spinner = self.progress.then(|| {
+      let spinner = Spinner::new();
+      spinner.set_message("Unpacking");
+      spinner
+    });
 
     // Get iterator over the entries.
     let raw_entries = archive.entries().map_err(|source| {
@@ -68,7 +153,10 @@ impl Unpacker {
         }
       })?;
 
-      let fixed_path = fix_entry_path(&entry_path, path);
+      // Skip entries that are entirely consumed by stripping.
+      let Some(fixed_path) = fix_entry_path(&entry_path, path, self.strip_components) else {
+        continue;
+      };
 
       entry.set_preserve_permissions(USE_PERMISSIONS);
       entry.set_unpack_xattrs(USE_XATTRS);
@@ -80,11 +168,22 @@ impl Unpacker {
         }
       })?;
 
-      written_paths.push(fixed_path);
+      // Entries may repeat, and not necessarily one after another, so keep only the first one.
+      if seen_paths.insert(fixed_path.clone()) {
+        written_paths.push(fixed_path);
+
+        if let Some(spinner) = &spinner {
+          if written_paths.len() % PROGRESS_STEP == 0 {
+            spinner.set_message(format!("Unpacking: {} entries", written_paths.len()));
+          }
+        }
+      }
     }
 
-    // Deduplicate, because it **will** contain duplicates.
-    written_paths.dedup();
+    // The summary is reported by the caller, so the spinner only needs to be cleared.
+    if let Some(spinner) = spinner {
+      spinner.stop_with_clear();
+    }
 
     Ok(written_paths)
   }
@@ -96,11 +195,151 @@ impl From<Vec<u8>> for Unpacker {
   }
 }
 
-/// Produces a "fixed" path for an entry.
+/// Produces a "fixed" path for an entry by stripping `strip` leading components and prepending the
+/// destination path. Returns `None` if the entry has no components left after stripping.
 #[inline(always)]
-fn fix_entry_path(entry_path: &Path, dest_path: &Path) -> PathBuf {
-  dest_path
+fn fix_entry_path(entry_path: &Path, dest_path: &Path, strip: usize) -> Option<PathBuf> {
+  if entry_path.components().count() <= strip {
+    return None;
+  }
+
+  let fixed = dest_path
     .components()
-    .chain(entry_path.components().skip(1))
-    .fold(PathBuf::new(), |acc, next| acc.join(next))
+    .chain(entry_path.components().skip(strip))
+    .fold(PathBuf::new(), |acc, next| acc.join(next));
+
+  Some(fixed)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::utils::temp::TempDir;
+
+  /// Builds a plain tarball with a single file wrapped in a top-level directory.
+  fn tarball() -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::new());
+    let mut header = tar::Header::new_gnu();
+
+    header.set_mode(0o644);
+    header.set_size(6);
+    builder
+      .append_data(&mut header, "repo/README.md", &b"# repo"[..])
+      .unwrap();
+
+    builder.into_inner().unwrap()
+  }
+
+  #[test]
+  fn detect_compression() {
+    let cases = [
+      (&[0x1f, 0x8b, 0x08, 0x00][..], Some(Compression::Gzip)),
+      (
+        &[0xfd, b'7', b'z', b'X', b'Z', 0x00, 0x00][..],
+        Some(Compression::Xz),
+      ),
+      (&b"BZh91AY"[..], Some(Compression::Bzip2)),
+      (&b"plain"[..], None),
+      (&tarball()[..], Some(Compression::Uncompressed)),
+      (&[][..], None),
+    ];
+
+    for (bytes, expected) in cases {
+      assert_eq!(Compression::detect(bytes), expected);
+    }
+  }
+
+  #[test]
+  fn fix_entry_path_default_strip() {
+    assert_eq!(
+      fix_entry_path(Path::new("repo-4a5a56fd/src/main.rs"), Path::new("dest"), 1),
+      Some(PathBuf::from("dest/src/main.rs"))
+    );
+  }
+
+  #[test]
+  fn fix_entry_path_custom_strip() {
+    let cases = [
+      ("a/b/c/file.txt", 0, Some("dest/a/b/c/file.txt")),
+      ("a/b/c/file.txt", 2, Some("dest/c/file.txt")),
+      ("a/b/c/file.txt", 3, Some("dest/file.txt")),
+    ];
+
+    for (entry, strip, expected) in cases {
+      assert_eq!(
+        fix_entry_path(Path::new(entry), Path::new("dest"), strip),
+        expected.map(PathBuf::from)
+      );
+    }
+  }
+
+  #[test]
+  fn unpack_deduplicates_interleaved_paths() {
+    let mut builder = tar::Builder::new(Vec::new());
+
+    let append_dir = |builder: &mut tar::Builder<Vec<u8>>, path: &str| {
+      let mut header = tar::Header::new_gnu();
+      header.set_entry_type(tar::EntryType::Directory);
+      header.set_mode(0o755);
+      header.set_size(0);
+      builder.append_data(&mut header, path, io::empty()).unwrap();
+    };
+
+    let append_file = |builder: &mut tar::Builder<Vec<u8>>, path: &str, contents: &[u8]| {
+      let mut header = tar::Header::new_gnu();
+      header.set_mode(0o644);
+      header.set_size(contents.len() as u64);
+      builder.append_data(&mut header, path, contents).unwrap();
+    };
+
+    append_dir(&mut builder, "repo/src");
+    append_file(&mut builder, "repo/src/main.rs", b"fn main() {}");
+    append_dir(&mut builder, "repo/src");
+    append_file(&mut builder, "repo/README.md", b"# repo");
+    append_file(&mut builder, "repo/src/main.rs", b"fn main() {}");
+
+    let tarball = builder.into_inner().unwrap();
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    io::Write::write_all(&mut encoder, &tarball).unwrap();
+
+    let root = TempDir::new("unpack");
+
+    let written = Unpacker::new(encoder.finish().unwrap())
+      .unpack_to(&root)
+      .unwrap();
+
+    assert_eq!(
+      written,
+      vec![
+        root.join("src"),
+        root.join("src/main.rs"),
+        root.join("README.md"),
+      ]
+    );
+  }
+
+  #[test]
+  fn unpack_uncompressed() {
+    let root = TempDir::new("unpack-tar");
+
+    let written = Unpacker::new(tarball()).unpack_to(&root).unwrap();
+
+    assert_eq!(written, vec![root.join("README.md")]);
+    assert_eq!(
+      fs::read_to_string(root.join("README.md")).unwrap(),
+      "# repo"
+    );
+  }
+
+  #[test]
+  fn fix_entry_path_skips_short_entries() {
+    let cases = [("repo-4a5a56fd", 1), ("a/b", 2), ("a/b", 3)];
+
+    for (entry, strip) in cases {
+      assert_eq!(
+        fix_entry_path(Path::new(entry), Path::new("dest"), strip),
+        None
+      );
+    }
+  }
 }
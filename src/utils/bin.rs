@@ -0,0 +1,121 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use semver::Version;
+
+/// Looks up the binary in the directories listed in `PATH`, like `which` does. On Windows, the
+/// extensions listed in `PATHEXT` are tried as well.
+pub fn find(name: &str) -> Option<PathBuf> {
+  let paths = env::var_os("PATH")?;
+
+  let extensions = if cfg!(windows) {
+    env::var("PATHEXT")
+      .unwrap_or_else(|_| ".EXE;.CMD;.BAT;.COM".to_string())
+      .split(';')
+      .map(str::to_string)
+      .collect()
+  } else {
+    Vec::new()
+  };
+
+  env::split_paths(&paths).find_map(|dir| {
+    let candidate = dir.join(name);
+
+    if is_executable(&candidate) {
+      return Some(candidate);
+    }
+
+    extensions
+      .iter()
+      .map(|ext| dir.join(format!("{name}{ext}")))
+      .find(|candidate| is_executable(candidate))
+  })
+}
+
+/// Checks if the path is an executable file.
+fn is_executable(path: &Path) -> bool {
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::PermissionsExt;
+
+    path
+      .metadata()
+      .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+  }
+
+  #[cfg(not(unix))]
+  {
+    path.is_file()
+  }
+}
+
+/// Runs the binary with `--version` and parses the first version-like token of its output.
+pub fn version(path: &Path) -> Option<Version> {
+  let output = Command::new(path).arg("--version").output().ok()?;
+
+  let stdout = String::from_utf8_lossy(&output.stdout);
+  let stderr = String::from_utf8_lossy(&output.stderr);
+
+  stdout
+    .split_whitespace()
+    .chain(stderr.split_whitespace())
+    .find_map(parse_version)
+}
+
+/// Leniently parses a version token, e.g. `v18.2`, `1.75.0,` or `2.43.0.windows.1`, padding
+/// missing components with zeros.
+fn parse_version(token: &str) -> Option<Version> {
+  let version = token
+    .trim_start_matches('v')
+    .split(|char: char| !char.is_ascii_digit() && char != '.')
+    .next()?;
+
+  // Plain numbers are more likely years or build numbers than versions.
+  if !version.contains('.') {
+    return None;
+  }
+
+  let numbers = version
+    .split('.')
+    .filter(|part| !part.is_empty())
+    .take(3)
+    .map(|part| part.parse::<u64>().ok())
+    .collect::<Option<Vec<_>>>()?;
+
+  match numbers[..] {
+    | [major] => Some(Version::new(major, 0, 0)),
+    | [major, minor] => Some(Version::new(major, minor, 0)),
+    | [major, minor, patch] => Some(Version::new(major, minor, patch)),
+    | _ => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse_version_tokens() {
+    let cases = [
+      ("1.75.0", Some(Version::new(1, 75, 0))),
+      ("v18.2", Some(Version::new(18, 2, 0))),
+      ("24.0.7,", Some(Version::new(24, 0, 7))),
+      ("2.43.0.windows.1", Some(Version::new(2, 43, 0))),
+      ("8.15.1-beta", Some(Version::new(8, 15, 1))),
+      ("cargo", None),
+      ("2024", None),
+    ];
+
+    for (token, expected) in cases {
+      assert_eq!(parse_version(token), expected, "{token}");
+    }
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn find_binaries_on_path() {
+    assert!(find("sh").is_some());
+    assert!(find("decaff-surely-missing-binary").is_none());
+  }
+}
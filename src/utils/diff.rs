@@ -0,0 +1,207 @@
+/// Kind of a diff line.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Change {
+  /// Line is present in both texts.
+  Equal,
+  /// Line is present only in the old text.
+  Delete,
+  /// Line is present only in the new text.
+  Insert,
+}
+
+/// Computes the shortest edit script between the old and new lines using the Myers algorithm.
+fn changes<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(Change, &'a str)> {
+  let (n, m) = (old.len() as isize, new.len() as isize);
+  let max = n + m;
+
+  // Furthest reaching x for each diagonal k, offset by max, so that negative diagonals fit.
+  let mut v = vec![0; 2 * max as usize + 2];
+  let mut trace = Vec::new();
+
+  let at = |k: isize| (k + max) as usize;
+
+  'search: for d in 0..=max {
+    trace.push(v.clone());
+
+    for k in (-d..=d).step_by(2) {
+      let mut x = if k == -d || (k != d && v[at(k - 1)] < v[at(k + 1)]) {
+        v[at(k + 1)]
+      } else {
+        v[at(k - 1)] + 1
+      };
+
+      let mut y = x - k;
+
+      while x < n && y < m && old[x as usize] == new[y as usize] {
+        x += 1;
+        y += 1;
+      }
+
+      v[at(k)] = x;
+
+      if x >= n && y >= m {
+        break 'search;
+      }
+    }
+  }
+
+  // Walk the trace backwards to recover the edit script.
+  let mut result = Vec::new();
+  let (mut x, mut y) = (n, m);
+
+  for (d, v) in trace.iter().enumerate().rev() {
+    let d = d as isize;
+    let k = x - y;
+
+    let prev_k = if k == -d || (k != d && v[at(k - 1)] < v[at(k + 1)]) {
+      k + 1
+    } else {
+      k - 1
+    };
+
+    let prev_x = v[at(prev_k)];
+    let prev_y = prev_x - prev_k;
+
+    while x > prev_x && y > prev_y {
+      result.push((Change::Equal, old[x as usize - 1]));
+      x -= 1;
+      y -= 1;
+    }
+
+    if d > 0 {
+      if x == prev_x {
+        result.push((Change::Insert, new[y as usize - 1]));
+      } else {
+        result.push((Change::Delete, old[x as usize - 1]));
+      }
+    }
+
+    x = prev_x;
+    y = prev_y;
+  }
+
+  result.reverse();
+  result
+}
+
+/// Computes a unified diff between the old and new texts, with the given number of context lines
+/// around changes. Returns hunk headers and prefixed lines, without the file headers. Returns
+/// nothing if the texts have the same lines.
+pub fn unified(old: &str, new: &str, context: usize) -> Vec<String> {
+  let old = old.lines().collect::<Vec<_>>();
+  let new = new.lines().collect::<Vec<_>>();
+
+  let changes = changes(&old, &new);
+
+  let changed = changes
+    .iter()
+    .enumerate()
+    .filter(|(_, (change, _))| *change != Change::Equal)
+    .map(|(idx, _)| idx)
+    .collect::<Vec<_>>();
+
+  let mut lines = Vec::new();
+  let mut idx = 0;
+
+  while idx < changed.len() {
+    // Merge changes into a single hunk if their contexts overlap or touch.
+    let mut last = idx;
+
+    while last + 1 < changed.len() && changed[last + 1] - changed[last] <= 2 * context + 1 {
+      last += 1;
+    }
+
+    let start = changed[idx].saturating_sub(context);
+    let end = (changed[last] + context).min(changes.len() - 1);
+
+    let count = |range: &[(Change, &str)], skip: Change| {
+      range.iter().filter(|(change, _)| *change != skip).count()
+    };
+
+    let (old_before, new_before) = (
+      count(&changes[..start], Change::Insert),
+      count(&changes[..start], Change::Delete),
+    );
+
+    let hunk = &changes[start..=end];
+    let (old_len, new_len) = (count(hunk, Change::Insert), count(hunk, Change::Delete));
+
+    // Empty ranges start at the line before them, as in the output of `diff -u`.
+    let old_start = if old_len == 0 {
+      old_before
+    } else {
+      old_before + 1
+    };
+    let new_start = if new_len == 0 {
+      new_before
+    } else {
+      new_before + 1
+    };
+
+    lines.push(format!(
+      "@@ -{old_start},{old_len} +{new_start},{new_len} @@"
+    ));
+
+    for (change, line) in hunk {
+      let prefix = match change {
+        | Change::Equal => ' ',
+        | Change::Delete => '-',
+        | Change::Insert => '+',
+      };
+
+      lines.push(format!("{prefix}{line}"));
+    }
+
+    idx = last + 1;
+  }
+
+  lines
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn unified_same_texts() {
+    assert!(unified("foo\nbar\n", "foo\nbar\n", 3).is_empty());
+    assert!(unified("", "", 3).is_empty());
+  }
+
+  #[test]
+  fn unified_single_hunk() {
+    let old = "a\nb\nc\nd\ne\n";
+    let new = "a\nb\nC\nd\ne\n";
+
+    assert_eq!(
+      unified(old, new, 1),
+      vec!["@@ -2,3 +2,3 @@", " b", "-c", "+C", " d"]
+    );
+  }
+
+  #[test]
+  fn unified_separate_hunks() {
+    let old = "1\n2\n3\n4\n5\n6\n7\n8\n";
+    let new = "one\n2\n3\n4\n5\n6\n7\neight\n";
+
+    assert_eq!(
+      unified(old, new, 1),
+      vec![
+        "@@ -1,2 +1,2 @@",
+        "-1",
+        "+one",
+        " 2",
+        "@@ -7,2 +7,2 @@",
+        " 7",
+        "-8",
+        "+eight",
+      ]
+    );
+  }
+
+  #[test]
+  fn unified_insertions_and_deletions() {
+    assert_eq!(unified("", "foo\n", 3), vec!["@@ -0,0 +1,1 @@", "+foo"]);
+    assert_eq!(unified("foo\n", "", 3), vec!["@@ -1,1 +0,0 @@", "-foo"]);
+  }
+}
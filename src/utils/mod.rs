@@ -1,2 +1,8 @@
+pub mod bin;
+pub mod diff;
 pub mod net;
 pub mod prompts;
+pub mod style;
+#[cfg(test)]
+pub mod temp;
+pub mod terminal;
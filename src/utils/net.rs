@@ -1,10 +1,16 @@
-use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, TcpStream};
+use std::net::{TcpStream, ToSocketAddrs};
 use std::time::Duration;
 
-#[allow(dead_code)]
-pub fn is_online() -> bool {
-  let ip = Ipv4Addr::new(1, 1, 1, 1);
-  let address = SocketAddr::V4(SocketAddrV4::new(ip, 80));
+/// Timeout of a single connectivity probe.
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
 
-  TcpStream::connect_timeout(&address, Duration::from_secs(5)).map_or(false, |_| true)
+/// Checks connectivity by trying to connect to the given host and port. Failing to resolve the host
+/// is also treated as being offline.
+pub fn is_online(host: &str, port: u16, timeout: Duration) -> bool {
+  (host, port)
+    .to_socket_addrs()
+    .map(|mut addresses| {
+      addresses.any(|address| TcpStream::connect_timeout(&address, timeout).is_ok())
+    })
+    .unwrap_or(false)
 }
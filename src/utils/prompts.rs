@@ -1,10 +1,40 @@
 use std::fmt::Display;
-use std::process;
+use std::io::{self, Write};
+use std::time::Duration;
 
-use crossterm::style::Stylize;
+use crossterm::terminal::{Clear, ClearType};
+use crossterm::{cursor, execute};
+use inquire::error::InquireResult;
 use inquire::formatter::StringFormatter;
 use inquire::ui::{Color, RenderConfig, StyleSheet, Styled};
-use inquire::InquireError;
+use inquire::{Confirm, InquireError};
+use miette::Diagnostic;
+use thiserror::Error;
+
+use crate::actions::{Output, State};
+use crate::config::Value;
+use crate::utils::style::Stylize;
+use crate::utils::terminal;
+
+#[derive(Debug, Diagnostic, Error)]
+pub enum PromptError {
+  #[error("Prompt was cancelled.")]
+  #[diagnostic(code(decaff::prompts::cancelled))]
+  Cancelled,
+  #[error("Prompt was interrupted.")]
+  #[diagnostic(code(decaff::prompts::interrupted))]
+  Interrupted,
+  #[error("Failed to prompt.")]
+  #[diagnostic(code(decaff::prompts::failed))]
+  Failed(#[source] InquireError),
+}
+
+impl PromptError {
+  /// Checks if the prompt was cancelled or interrupted by the user, as opposed to failing.
+  pub fn is_cancellation(&self) -> bool {
+    matches!(self, Self::Cancelled | Self::Interrupted)
+  }
+}
 
 /// Returns configured theme.
 pub fn theme<'r>() -> RenderConfig<'r> {
@@ -43,16 +73,74 @@ where
   (name, hint, help)
 }
 
-/// Handle interruption/cancelation events.
-pub fn interrupt(err: InquireError) {
+/// Stores an answer that was provided without asking and prints it the same way as the answered
+/// prompts are printed.
+pub fn answer(state: &mut State, name: &str, value: Value, output: &Output) {
+  output.notice(format!(
+    "{} {} {}",
+    "✓".green(),
+    format!("{name}:").dim(),
+    value
+  ));
+
+  state.set(name, value);
+}
+
+/// Runs the prompt, giving up on it if the user doesn't start answering within the timeout, in
+/// which case `None` is returned. Without a timeout the prompt is simply run in place.
+pub fn prompt_with_timeout<T, P>(
+  timeout: Option<Duration>,
+  hint: &str,
+  prompt: P,
+) -> InquireResult<Option<T>>
+where
+  P: FnOnce() -> InquireResult<T>,
+{
+  let Some(timeout) = timeout else {
+    return prompt().map(Some);
+  };
+
+  // NOTE: Prompts block on reading input until they are answered, so a prompt can't be abandoned
+  // without leaving a reader behind that steals keystrokes from later prompts. Instead, the hint is
+  // shown while waiting for the first keystroke, and only then the prompt takes over.
+  let mut stderr = io::stderr();
+
+  let _ = write!(stderr, "{} {hint} ", "?".yellow());
+  let _ = stderr.flush();
+
+  let pending = terminal::wait_for_input(timeout);
+
+  let _ = execute!(
+    stderr,
+    cursor::MoveToColumn(0),
+    Clear(ClearType::CurrentLine)
+  );
+
+  if pending? {
+    prompt().map(Some)
+  } else {
+    eprintln!("{} {hint} {}", "?".yellow(), "<timed out>".dim());
+    Ok(None)
+  }
+}
+
+/// Asks for a yes/no confirmation. Defaults to no.
+pub fn confirm(message: &str) -> Result<bool, PromptError> {
+  Confirm::new(message)
+    .with_default(false)
+    .with_render_config(theme())
+    .prompt()
+    .map_err(interrupt)
+}
+
+/// Converts a prompt error into [PromptError], telling cancellation and interruption apart from
+/// other failures. Exiting is left to the app, so it can clean up first.
+pub fn interrupt(err: InquireError) -> PromptError {
+  terminal::restore();
+
   match err {
-    | InquireError::OperationCanceled => {
-      process::exit(0);
-    },
-    | InquireError::OperationInterrupted => {
-      println!("{}", "<interrupted>".red());
-      process::exit(0);
-    },
-    | _ => {},
+    | InquireError::OperationCanceled => PromptError::Cancelled,
+    | InquireError::OperationInterrupted => PromptError::Interrupted,
+    | err => PromptError::Failed(err),
   }
 }
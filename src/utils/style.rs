@@ -0,0 +1,60 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crossterm::style;
+
+/// Whether to style the output. Enabled unless disabled with [set_colors].
+static COLORS: AtomicBool = AtomicBool::new(true);
+
+/// Enables or disables styling of the output globally. This also overrides the `NO_COLOR` check of
+/// crossterm, since the caller is expected to take it into account.
+pub fn set_colors(enabled: bool) {
+  COLORS.store(enabled, Ordering::Relaxed);
+  style::force_color_output(enabled);
+}
+
+/// Drop-in replacement for [style::Stylize] that leaves the content unstyled if styling is
+/// disabled. Disabling colors in crossterm alone still prints attributes like `dim` and resets.
+pub trait Stylize: style::Stylize + Sized {
+  fn red(self) -> Self::Styled {
+    styled(self, style::Stylize::red)
+  }
+
+  fn green(self) -> Self::Styled {
+    styled(self, style::Stylize::green)
+  }
+
+  fn yellow(self) -> Self::Styled {
+    styled(self, style::Stylize::yellow)
+  }
+
+  fn cyan(self) -> Self::Styled {
+    styled(self, style::Stylize::cyan)
+  }
+
+  fn grey(self) -> Self::Styled {
+    styled(self, style::Stylize::grey)
+  }
+
+  fn dark_grey(self) -> Self::Styled {
+    styled(self, style::Stylize::dark_grey)
+  }
+
+  fn dim(self) -> Self::Styled {
+    styled(self, style::Stylize::dim)
+  }
+
+  fn bold(self) -> Self::Styled {
+    styled(self, style::Stylize::bold)
+  }
+}
+
+impl<T: style::Stylize> Stylize for T {}
+
+/// Applies the style if styling is enabled, otherwise wraps the content without any style.
+fn styled<T: style::Stylize>(content: T, apply: fn(T) -> T::Styled) -> T::Styled {
+  if COLORS.load(Ordering::Relaxed) {
+    apply(content)
+  } else {
+    content.stylize()
+  }
+}
@@ -0,0 +1,66 @@
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// Temporary directory for tests. Created empty, and removed along with its contents when dropped,
+/// even if the test panics.
+#[derive(Debug)]
+pub struct TempDir {
+  path: PathBuf,
+}
+
+impl TempDir {
+  /// Creates an empty temporary directory. The name has to be unique across tests, since tests run
+  /// in parallel.
+  pub fn new(name: &str) -> Self {
+    let path = std::env::temp_dir().join(format!("decaff-test-{name}-{}", process::id()));
+
+    let _ = fs::remove_dir_all(&path);
+    fs::create_dir_all(&path).unwrap();
+
+    Self { path }
+  }
+
+  /// Returns the path of the directory.
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+}
+
+impl Deref for TempDir {
+  type Target = Path;
+
+  fn deref(&self) -> &Self::Target {
+    &self.path
+  }
+}
+
+impl AsRef<Path> for TempDir {
+  fn as_ref(&self) -> &Path {
+    &self.path
+  }
+}
+
+impl Drop for TempDir {
+  fn drop(&mut self) {
+    let _ = fs::remove_dir_all(&self.path);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn remove_on_drop() {
+    let dir = TempDir::new("temp-drop");
+    let path = dir.to_path_buf();
+
+    fs::write(dir.join("file.txt"), "content").unwrap();
+    assert!(path.join("file.txt").exists());
+
+    drop(dir);
+    assert!(!path.exists());
+  }
+}
@@ -0,0 +1,49 @@
+use std::io::{self, IsTerminal};
+use std::time::Duration;
+
+use crossterm::{cursor, execute, terminal};
+
+/// Restores the terminal state on drop, so neither errors nor panics leave the terminal in raw mode
+/// with the cursor hidden. Keep it alive for as long as the terminal can be messed with.
+pub struct Guard;
+
+impl Drop for Guard {
+  fn drop(&mut self) {
+    restore();
+  }
+}
+
+/// Disables raw mode and shows the cursor. Both are no-ops if the terminal is already fine, so it's
+/// safe to call this more than once.
+pub fn restore() {
+  let _ = terminal::disable_raw_mode();
+
+  // Don't pollute the output with escape codes if it is piped, e.g. when printing JSON events.
+  if io::stdout().is_terminal() {
+    let _ = execute!(io::stdout(), cursor::Show);
+  }
+}
+
+/// Waits for input, e.g. a keystroke, for at most the given time. Returns `true` if there's input.
+/// The input is left queued for prompts, so nothing the user typed is lost.
+pub fn wait_for_input(timeout: Duration) -> io::Result<bool> {
+  inquire_crossterm::terminal::enable_raw_mode()?;
+
+  let pending = inquire_crossterm::event::poll(timeout);
+
+  inquire_crossterm::terminal::disable_raw_mode()?;
+
+  pending
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn restore_is_idempotent() {
+    restore();
+    restore();
+    drop(Guard);
+  }
+}